@@ -0,0 +1,128 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The `asm` command.
+
+use crate::file_error;
+use crate::usage;
+use crate::CliError;
+use chip8_core::assembler;
+use std::path::PathBuf;
+
+/// The command line of `asm`.
+#[derive(Debug, PartialEq)]
+pub struct AsmOptions {
+    pub source: PathBuf,
+    /// Where to write the ROM.
+    pub output: PathBuf,
+    /// Where to write the symbol table, if anywhere.
+    pub symbols: Option<PathBuf>,
+}
+
+impl AsmOptions {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<AsmOptions, CliError> {
+        let mut source = None;
+        let mut output = None;
+        let mut symbols = None;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| usage(format!("{} needs a value", arg)))
+            };
+            match arg.as_str() {
+                "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+                "--symbols" => symbols = Some(PathBuf::from(value()?)),
+                _ if arg.starts_with('-') => {
+                    return Err(usage(format!("unknown option '{}'", arg)))
+                }
+                _ if source.is_none() => source = Some(PathBuf::from(arg)),
+                _ => return Err(usage(format!("unexpected argument '{}'", arg))),
+            }
+        }
+        let source: PathBuf = source.ok_or_else(|| usage("no source given".to_string()))?;
+        let output = output.unwrap_or_else(|| source.with_extension("ch8"));
+        Ok(AsmOptions {
+            source,
+            output,
+            symbols,
+        })
+    }
+}
+
+/// Assembles the source and writes the ROM and symbol table.
+pub fn asm(options: &AsmOptions) -> Result<(), CliError> {
+    let source = std::fs::read_to_string(&options.source)
+        .map_err(|error| file_error(&options.source, error))?;
+    let assembly = assembler::assemble(&source)
+        .map_err(|error| usage(format!("{}: {}", options.source.display(), error)))?;
+    std::fs::write(&options.output, &assembly.rom)
+        .map_err(|error| file_error(&options.output, error))?;
+    if let Some(path) = &options.symbols {
+        let table = assembly.symbol_table().to_text();
+        std::fs::write(path, table).map_err(|error| file_error(path, error))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asm::asm;
+    use crate::asm::AsmOptions;
+    use chip8_core::movie;
+    use std::path::PathBuf;
+
+    fn options(args: &[&str]) -> AsmOptions {
+        AsmOptions::parse(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = options(&["src/game.8o"]);
+        assert_eq!(options.output, PathBuf::from("src/game.ch8"));
+        assert_eq!(options.symbols, None);
+        let options = self::options(&["game.8o", "-o", "out.ch8", "--symbols", "game.sym"]);
+        assert_eq!(options.output, PathBuf::from("out.ch8"));
+        assert_eq!(options.symbols, Some(PathBuf::from("game.sym")));
+    }
+
+    #[test]
+    fn test_asm() {
+        let directory = std::env::temp_dir().join(format!("chip8-cli-asm-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let source = directory.join("game.8o");
+        std::fs::write(&source, ": main\n  clear\n: spin\n  jump spin\n").unwrap();
+        let symbols = directory.join("game.sym");
+        asm(&options(&[
+            source.to_str().unwrap(),
+            "--symbols",
+            symbols.to_str().unwrap(),
+        ]))
+        .unwrap();
+        assert_eq!(
+            std::fs::read(directory.join("game.ch8")).unwrap(),
+            vec![0x00, 0xE0, 0x12, 0x02]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&symbols).unwrap(),
+            format!(
+                "# rom {}\nmain 0x200\nspin 0x202\n",
+                movie::rom_hash(&[0x00, 0xE0, 0x12, 0x02])
+            )
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
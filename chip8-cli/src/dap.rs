@@ -0,0 +1,99 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The `dap` command.
+
+use crate::number;
+use crate::usage;
+use crate::CliError;
+use chip8_core::audio::NullBuzzer;
+use chip8_core::dap;
+use chip8_core::graphics::ConsoleDisplay;
+use chip8_core::movie::PlaybackKeyboard;
+use chip8_core::Interpreter;
+use std::io::BufReader;
+use std::net::Ipv4Addr;
+use std::net::TcpListener;
+
+/// The command line of `dap`.
+#[derive(Debug, PartialEq)]
+pub struct DapOptions {
+    /// The port to wait on, or `None` to talk over standard input and
+    /// output.
+    pub port: Option<u16>,
+}
+
+impl DapOptions {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<DapOptions, CliError> {
+        let mut options = DapOptions { port: None };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--port" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| usage(format!("{} needs a value", arg)))?;
+                    options.port = Some(number(&arg, &value)?);
+                }
+                _ => return Err(usage(format!("unexpected argument '{}'", arg))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Serves one editor, which launches the ROM to debug itself.
+pub fn serve(options: &DapOptions) -> Result<(), CliError> {
+    let launcher: dap::Launcher = Box::new(|rom, platform| {
+        Interpreter::with_platform(
+            Box::new(ConsoleDisplay::quiet()),
+            Box::new(NullBuzzer),
+            Box::new(PlaybackKeyboard::from_frames(vec![])),
+            rom,
+            platform,
+        )
+    });
+    let port = match options.port {
+        Some(port) => port,
+        None => {
+            let input = BufReader::new(std::io::stdin());
+            return dap::serve(input, std::io::stdout(), launcher)
+                .map_err(|error| usage(error.to_string()));
+        }
+    };
+    let network_error = |error: std::io::Error| usage(format!("port {}: {}", port, error));
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).map_err(network_error)?;
+    eprintln!("waiting for an editor on 127.0.0.1:{}", port);
+    let (stream, _) = listener.accept().map_err(network_error)?;
+    let input = BufReader::new(stream.try_clone().map_err(network_error)?);
+    dap::serve(input, stream, launcher).map_err(network_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dap::DapOptions;
+
+    fn parse(args: &[&str]) -> Result<DapOptions, crate::CliError> {
+        DapOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse(&[]).unwrap(), DapOptions { port: None });
+        assert_eq!(parse(&["--port", "4711"]).unwrap().port, Some(4711));
+        assert!(parse(&["game.ch8"]).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The `disasm` command.
+
+use crate::address;
+use crate::number;
+use crate::read_program;
+use crate::read_symbols;
+use crate::usage;
+use crate::CliError;
+use chip8_core::disasm;
+use chip8_core::memory::PROGRAM_START;
+use chip8_core::Platform;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// The command line of `disasm`.
+#[derive(Debug, PartialEq)]
+pub struct DisasmOptions {
+    pub rom: PathBuf,
+    pub platform: Option<Platform>,
+    /// The address to start listing from, where the ROM starts at 0x200.
+    pub start: u16,
+    /// How many bytes to list, or the rest of the ROM.
+    pub length: Option<usize>,
+    /// The symbol file naming addresses, instead of the ROM's own.
+    pub symbols: Option<PathBuf>,
+}
+
+impl DisasmOptions {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<DisasmOptions, CliError> {
+        let mut rom = None;
+        let mut options = DisasmOptions {
+            rom: PathBuf::new(),
+            platform: None,
+            start: PROGRAM_START,
+            length: None,
+            symbols: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| usage(format!("{} needs a value", arg)))
+            };
+            match arg.as_str() {
+                "--platform" => {
+                    let name = value()?;
+                    let platform = Platform::from_name(&name)
+                        .ok_or_else(|| usage(format!("unknown platform '{}'", name)))?;
+                    options.platform = Some(platform);
+                }
+                "--start" => options.start = address(&arg, &value()?)?,
+                "--length" => options.length = Some(number(&arg, &value()?)?),
+                "--symbols" => options.symbols = Some(PathBuf::from(value()?)),
+                _ if arg.starts_with("--") => {
+                    return Err(usage(format!("unknown option '{}'", arg)))
+                }
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(usage(format!("unexpected argument '{}'", arg))),
+            }
+        }
+        options.rom = rom.ok_or_else(|| usage("no ROM given".to_string()))?;
+        if options.start < PROGRAM_START {
+            return Err(usage(format!(
+                "--start must be at least 0x{:03X}",
+                PROGRAM_START
+            )));
+        }
+        Ok(options)
+    }
+}
+
+/// Prints the listing of the ROM, with a `name:` line before each labelled
+/// address.
+pub fn disasm(options: &DisasmOptions) -> Result<(), CliError> {
+    let (rom, symbols) = read_program(&options.rom)?;
+    let symbols = match &options.symbols {
+        Some(path) => read_symbols(path)?,
+        None => symbols,
+    };
+    let platform = options
+        .platform
+        .unwrap_or_else(|| Platform::from_path(&options.rom));
+    let range = listing_range(rom.len(), options.start, options.length);
+    let lines = disasm::disassemble_with_symbols(&rom[range], options.start, platform, &symbols);
+    for line in lines {
+        if let Some(label) = &line.label {
+            println!("{}:", label);
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// The bytes of a `rom_len` byte ROM to list, from the address `start` for
+/// `length` bytes, cut short at the end of the ROM.
+fn listing_range(rom_len: usize, start: u16, length: Option<usize>) -> Range<usize> {
+    let start = usize::from(start - PROGRAM_START).min(rom_len);
+    let end = match length {
+        Some(length) => start.saturating_add(length).min(rom_len),
+        None => rom_len,
+    };
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disasm::listing_range;
+    use crate::disasm::DisasmOptions;
+    use crate::CliError;
+    use std::path::PathBuf;
+
+    fn options(args: &[&str]) -> Result<DisasmOptions, CliError> {
+        DisasmOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = options(&["game.ch8", "--start", "0x210", "--length", "8"]).unwrap();
+        assert_eq!(options.rom, PathBuf::from("game.ch8"));
+        assert_eq!(options.symbols, None);
+        assert_eq!(options.start, 0x210);
+        assert_eq!(options.length, Some(8));
+        assert!(matches!(
+            self::options(&["game.ch8", "--start", "0x100"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(
+            self::options(&["game.ch8", "--start", "zz"]),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_listing_range() {
+        assert_eq!(listing_range(16, 0x200, None), 0..16);
+        assert_eq!(listing_range(16, 0x204, Some(4)), 4..8);
+        assert_eq!(listing_range(16, 0x20C, Some(8)), 12..16);
+        assert_eq!(listing_range(16, 0x300, Some(8)), 16..16);
+        assert_eq!(listing_range(16, 0x204, Some(usize::MAX)), 4..16);
+    }
+}
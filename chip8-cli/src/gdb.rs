@@ -0,0 +1,132 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The `gdb` command.
+
+use crate::number;
+use crate::read_rom;
+use crate::usage;
+use crate::CliError;
+use chip8_core::audio::NullBuzzer;
+use chip8_core::gdb;
+use chip8_core::graphics::ConsoleDisplay;
+use chip8_core::movie::PlaybackKeyboard;
+use chip8_core::Interpreter;
+use chip8_core::Platform;
+use chip8_core::Quirks;
+use std::net::Ipv4Addr;
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+/// The command line of `gdb`.
+#[derive(Debug, PartialEq)]
+pub struct GdbOptions {
+    pub rom: PathBuf,
+    pub port: u16,
+    pub platform: Option<Platform>,
+    pub quirks: Option<Quirks>,
+}
+
+impl GdbOptions {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<GdbOptions, CliError> {
+        let mut rom = None;
+        let mut options = GdbOptions {
+            rom: PathBuf::new(),
+            port: gdb::DEFAULT_PORT,
+            platform: None,
+            quirks: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| usage(format!("{} needs a value", arg)))
+            };
+            match arg.as_str() {
+                "--port" => options.port = number(&arg, &value()?)?,
+                "--platform" => {
+                    let name = value()?;
+                    let platform = Platform::from_name(&name)
+                        .ok_or_else(|| usage(format!("unknown platform '{}'", name)))?;
+                    options.platform = Some(platform);
+                }
+                "--quirks" => {
+                    let name = value()?;
+                    let quirks = Quirks::preset(&name)
+                        .ok_or_else(|| usage(format!("unknown quirks preset '{}'", name)))?;
+                    options.quirks = Some(quirks);
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(usage(format!("unknown option '{}'", arg)))
+                }
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(usage(format!("unexpected argument '{}'", arg))),
+            }
+        }
+        options.rom = rom.ok_or_else(|| usage("no ROM given".to_string()))?;
+        Ok(options)
+    }
+}
+
+/// Loads the ROM paused and debugs it for one GDB connection on the local
+/// machine.
+pub fn serve(options: &GdbOptions) -> Result<(), CliError> {
+    let rom = read_rom(&options.rom)?;
+    let platform = options
+        .platform
+        .unwrap_or_else(|| Platform::from_path(&options.rom));
+    let mut interpreter = Interpreter::with_platform(
+        Box::new(ConsoleDisplay::quiet()),
+        Box::new(NullBuzzer),
+        Box::new(PlaybackKeyboard::from_frames(vec![])),
+        &rom,
+        platform,
+    )
+    .map_err(|error| usage(format!("{}: {}", options.rom.display(), error)))?;
+    if let Some(quirks) = options.quirks {
+        interpreter.set_quirks(quirks);
+    }
+    let network_error = |error: std::io::Error| usage(format!("port {}: {}", options.port, error));
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, options.port)).map_err(network_error)?;
+    eprintln!("waiting for GDB on 127.0.0.1:{}", options.port);
+    let (stream, peer) = listener.accept().map_err(network_error)?;
+    eprintln!("debugging for {}", peer);
+    gdb::serve(stream, &mut interpreter).map_err(network_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gdb::GdbOptions;
+    use chip8_core::Platform;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_options() {
+        let options = GdbOptions::parse(["game.ch8"].iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(options.rom, PathBuf::from("game.ch8"));
+        assert_eq!(options.port, 1234);
+        let args = ["--port", "3333", "game.ch8", "--platform", "super-chip"];
+        let options = GdbOptions::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(options.port, 3333);
+        assert_eq!(options.platform, Some(Platform::SuperChip));
+        assert!(GdbOptions::parse(
+            ["--port", "x", "game.ch8"]
+                .iter()
+                .map(|arg| arg.to_string())
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,202 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! `chip8-cli`, the CHIP-8 interpreter for the command line.
+//!
+//! It runs and lists ROMs with no window, for batch runs on build machines:
+//!
+//! ```text
+//! chip8-cli run ROM [--frames N] [--input SCRIPT] [--png FILE] [--text FILE]
+//! chip8-cli disasm ROM [--start ADDRESS] [--length BYTES] [--symbols FILE]
+//! chip8-cli asm SOURCE [-o ROM] [--symbols FILE]
+//! chip8-cli gdb ROM [--port PORT]
+//! chip8-cli dap [--port PORT]
+//! ```
+
+mod asm;
+mod dap;
+mod disasm;
+mod gdb;
+mod run;
+
+use chip8_core::assembler;
+use chip8_core::movie;
+use chip8_core::Chip8Error;
+use chip8_core::Symbols;
+use std::fmt;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage: chip8-cli run ROM [options]
+       chip8-cli disasm ROM [--platform NAME] [--start ADDRESS] [--length BYTES]
+                        [--symbols FILE]
+       chip8-cli asm SOURCE [-o ROM] [--symbols FILE]
+       chip8-cli gdb ROM [--port PORT] [--platform NAME] [--quirks PRESET]
+       chip8-cli dap [--port PORT]
+
+run: runs ROM for a number of frames, or until it halts or jumps to itself,
+and writes the final screen.
+
+disasm: lists ROM as instructions, from 0x200 or ADDRESS, for the whole ROM
+or BYTES bytes. Labels come from FILE, from the source of a .8o ROM, or from
+a symbol file next to the ROM named after it or after its SHA-1.
+
+asm: assembles Octo SOURCE into ROM, by default SOURCE with a .ch8
+extension, and optionally writes the address of every label to FILE, a
+symbol file that disasm and dap read back.
+
+gdb: loads ROM paused and waits on 127.0.0.1:PORT (default 1234) for GDB,
+or another front end speaking its remote protocol, to connect and debug it.
+
+dap: speaks the Debug Adapter Protocol over standard input and output, or
+waits on 127.0.0.1:PORT, for an editor to launch and debug a ROM or .8o
+source.
+
+run, disasm and gdb assemble a ROM ending in .8o before using it.
+
+run options:
+  --frames N          stop after N frames (default 600, 10 seconds)
+  --platform NAME     chip-8, super-chip or xo-chip (default from the ROM's extension)
+  --quirks PRESET     cosmac-vip, chip-48, super-chip or octo
+  --speed N           instructions per frame
+  --seed N            seed for Cxkk (default 0)
+  --rng MODE          xorshift or cosmac-vip
+  --input SCRIPT      hold keys from a script of `<frame> <keys>` lines
+  --movie FILE        replay a movie recorded in the app
+  --png FILE          write the final screen as a PNG
+  --scale N           draw each pixel in the PNG as an N by N square (default 1)
+  --text FILE         write the final screen as text, - for standard output
+  --watch             print the screen whenever it changes
+";
+
+/// Why a command failed.
+#[derive(Debug)]
+pub enum CliError {
+    /// The command line, or a file it names, could not be used.
+    Usage(String),
+    /// The program being run failed.
+    Interpreter(Chip8Error),
+}
+
+impl CliError {
+    /// Interpreter errors exit with 1 and everything else with 2, so scripts
+    /// can tell a broken ROM from a broken command line.
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Usage(_) => ExitCode::from(2),
+            CliError::Interpreter(_) => ExitCode::from(1),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::Interpreter(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Reads a file named on the command line.
+fn read(path: &Path) -> Result<Vec<u8>, CliError> {
+    std::fs::read(path).map_err(|error| file_error(path, error))
+}
+
+/// Reads a ROM named on the command line, assembling it first if it is Octo
+/// source.
+fn read_rom(path: &Path) -> Result<Vec<u8>, CliError> {
+    if !assembler::is_source(path) {
+        return read(path);
+    }
+    let source = std::fs::read_to_string(path).map_err(|error| file_error(path, error))?;
+    assembler::assemble(&source)
+        .map(|assembly| assembly.rom)
+        .map_err(|error| usage(format!("{}: {}", path.display(), error)))
+}
+
+/// Reads a ROM like `read_rom`, along with its labels if it is Octo source,
+/// or the symbol file kept next to it if there is one.
+fn read_program(path: &Path) -> Result<(Vec<u8>, Symbols), CliError> {
+    if !assembler::is_source(path) {
+        let rom = read(path)?;
+        let symbols = Symbols::find(path, &movie::rom_hash(&rom)).unwrap_or_default();
+        return Ok((rom, symbols));
+    }
+    let source = std::fs::read_to_string(path).map_err(|error| file_error(path, error))?;
+    assembler::assemble(&source)
+        .map(|assembly| {
+            let symbols = assembly.symbol_table();
+            (assembly.rom, symbols)
+        })
+        .map_err(|error| usage(format!("{}: {}", path.display(), error)))
+}
+
+/// Reads a symbol file named on the command line.
+fn read_symbols(path: &Path) -> Result<Symbols, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|error| file_error(path, error))?;
+    Symbols::parse(&text).map_err(|error| usage(format!("{}: {}", path.display(), error)))
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| usage(format!("{} expects a number, not '{}'", option, value)))
+}
+
+/// Reads an address, in hexadecimal with a `0x` prefix or in decimal.
+fn address(option: &str, value: &str) -> Result<u16, CliError> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| usage(format!("{} expects an address, not '{}'", option, value)))
+}
+
+fn file_error(path: &Path, error: std::io::Error) -> CliError {
+    usage(format!("{}: {}", path.display(), error))
+}
+
+fn usage(message: String) -> CliError {
+    CliError::Usage(message)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("run") => run::RunOptions::parse(args).and_then(|options| run::run(&options)),
+        Some("disasm") => {
+            disasm::DisasmOptions::parse(args).and_then(|options| disasm::disasm(&options))
+        }
+        Some("asm") => asm::AsmOptions::parse(args).and_then(|options| asm::asm(&options)),
+        Some("dap") => dap::DapOptions::parse(args).and_then(|options| dap::serve(&options)),
+        Some("gdb") => gdb::GdbOptions::parse(args).and_then(|options| gdb::serve(&options)),
+        None | Some("help") | Some("-h") | Some("--help") => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(CliError::Usage(format!("unknown command '{}'", command))),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("chip8-cli: {}", error);
+            error.exit_code()
+        }
+    }
+}
@@ -0,0 +1,500 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The `run` command.
+
+use crate::file_error;
+use crate::number;
+use crate::read;
+use crate::read_program;
+use crate::usage;
+use crate::CliError;
+use chip8_core::audio::NullBuzzer;
+use chip8_core::graphics::ConsoleDisplay;
+use chip8_core::graphics::FrameBuffer;
+use chip8_core::movie::PlaybackKeyboard;
+use chip8_core::rng::RngMode;
+use chip8_core::Chip8Error;
+use chip8_core::Interpreter;
+use chip8_core::Movie;
+use chip8_core::Platform;
+use chip8_core::Quirks;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The colours of the app's screen, indexed by pixel colour.
+const PALETTE: [[u8; 3]; 4] = [[0x00; 3], [0xFF; 3], [0xAA; 3], [0x55; 3]];
+
+const DEFAULT_FRAMES: usize = 600;
+
+/// The command line of `run`.
+#[derive(Debug, PartialEq)]
+pub struct RunOptions {
+    pub rom: PathBuf,
+    pub frames: usize,
+    pub platform: Option<Platform>,
+    pub quirks: Option<Quirks>,
+    pub instructions_per_frame: Option<usize>,
+    pub seed: u64,
+    pub rng_mode: RngMode,
+    pub input: Option<PathBuf>,
+    pub movie: Option<PathBuf>,
+    pub png: Option<PathBuf>,
+    pub scale: usize,
+    /// Where to write the screen as text, `-` being standard output.
+    pub text: Option<PathBuf>,
+    pub watch: bool,
+}
+
+impl RunOptions {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<RunOptions, CliError> {
+        let mut rom = None;
+        let mut options = RunOptions {
+            rom: PathBuf::new(),
+            frames: DEFAULT_FRAMES,
+            platform: None,
+            quirks: None,
+            instructions_per_frame: None,
+            seed: 0,
+            rng_mode: RngMode::default(),
+            input: None,
+            movie: None,
+            png: None,
+            scale: 1,
+            text: None,
+            watch: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| usage(format!("{} needs a value", arg)))
+            };
+            match arg.as_str() {
+                "--frames" => options.frames = number(&arg, &value()?)?,
+                "--platform" => {
+                    let name = value()?;
+                    let platform = Platform::from_name(&name)
+                        .ok_or_else(|| usage(format!("unknown platform '{}'", name)))?;
+                    options.platform = Some(platform);
+                }
+                "--quirks" => {
+                    let name = value()?;
+                    let quirks = Quirks::preset(&name)
+                        .ok_or_else(|| usage(format!("unknown quirks preset '{}'", name)))?;
+                    options.quirks = Some(quirks);
+                }
+                "--speed" => options.instructions_per_frame = Some(number(&arg, &value()?)?),
+                "--seed" => options.seed = number(&arg, &value()?)?,
+                "--rng" => {
+                    let name = value()?;
+                    options.rng_mode = RngMode::from_name(&name)
+                        .ok_or_else(|| usage(format!("unknown random number mode '{}'", name)))?;
+                }
+                "--input" => options.input = Some(PathBuf::from(value()?)),
+                "--movie" => options.movie = Some(PathBuf::from(value()?)),
+                "--png" => options.png = Some(PathBuf::from(value()?)),
+                "--scale" => options.scale = number(&arg, &value()?)?,
+                "--text" => options.text = Some(PathBuf::from(value()?)),
+                "--watch" => options.watch = true,
+                _ if arg.starts_with("--") => {
+                    return Err(usage(format!("unknown option '{}'", arg)))
+                }
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(usage(format!("unexpected argument '{}'", arg))),
+            }
+        }
+        options.rom = rom.ok_or_else(|| usage("no ROM given".to_string()))?;
+        if options.input.is_some() && options.movie.is_some() {
+            return Err(usage(
+                "--input and --movie can't be used together".to_string(),
+            ));
+        }
+        if options.instructions_per_frame == Some(0) || options.scale == 0 {
+            return Err(usage("--speed and --scale must be at least 1".to_string()));
+        }
+        Ok(options)
+    }
+}
+
+/// Why a run ended.
+#[derive(Debug, PartialEq)]
+enum Stop {
+    FrameLimit,
+    /// The program executed 00FD.
+    Halted,
+    /// The program jumped to the instruction doing the jump, which is how
+    /// most programs end.
+    Spinning,
+}
+
+/// How a run went.
+#[derive(Debug)]
+struct Outcome {
+    frames: usize,
+    result: Result<Stop, Chip8Error>,
+    /// The most calls in progress at once.
+    max_stack_depth: u8,
+    /// Where the program was when the run ended.
+    backtrace: String,
+}
+
+/// Runs the ROM and writes the final screen, even when the program fails,
+/// in which case the backtrace is printed too.
+pub fn run(options: &RunOptions) -> Result<(), CliError> {
+    let outcome = execute(options)?;
+    match outcome.result {
+        Ok(stop) => {
+            eprintln!(
+                "ran {} frames, stopped: {:?}, max stack depth: {}",
+                outcome.frames, stop, outcome.max_stack_depth
+            );
+            Ok(())
+        }
+        Err(error) => {
+            eprintln!(
+                "ran {} frames, max stack depth: {}",
+                outcome.frames, outcome.max_stack_depth
+            );
+            eprint!("{}", outcome.backtrace);
+            Err(CliError::Interpreter(error))
+        }
+    }
+}
+
+/// Does the work of `run`, returning how it went.
+fn execute(options: &RunOptions) -> Result<Outcome, CliError> {
+    let (rom, symbols) = read_program(&options.rom)?;
+    let movie = match &options.movie {
+        Some(path) => {
+            Some(Movie::from_bytes(&read(path)?).map_err(|error| usage(error.to_string()))?)
+        }
+        None => None,
+    };
+    let keyboard = match (&movie, &options.input) {
+        (Some(movie), _) => PlaybackKeyboard::new(movie),
+        (None, Some(path)) => {
+            let script = String::from_utf8_lossy(&read(path)?).into_owned();
+            let frames = parse_input_script(&script, options.frames)
+                .map_err(|message| usage(format!("{}: {}", path.display(), message)))?;
+            PlaybackKeyboard::from_frames(frames)
+        }
+        (None, None) => PlaybackKeyboard::from_frames(vec![]),
+    };
+    let platform = match (&movie, options.platform) {
+        (Some(movie), _) => movie.platform,
+        (None, Some(platform)) => platform,
+        (None, None) => Platform::from_path(&options.rom),
+    };
+    let display = match options.watch {
+        true => ConsoleDisplay::new(),
+        false => ConsoleDisplay::quiet(),
+    };
+    let mut interpreter = Interpreter::with_platform(
+        Box::new(display),
+        Box::new(NullBuzzer),
+        Box::new(keyboard),
+        &rom,
+        platform,
+    )
+    .map_err(|error| usage(format!("{}: {}", options.rom.display(), error)))?;
+    interpreter.set_rng_mode(options.rng_mode);
+    interpreter.set_symbols(symbols);
+    interpreter.set_seed(options.seed);
+    if let Some(quirks) = options.quirks {
+        interpreter.set_quirks(quirks);
+    }
+    if let Some(instructions_per_frame) = options.instructions_per_frame {
+        interpreter.set_instructions_per_frame(instructions_per_frame);
+    }
+    if let Some(movie) = &movie {
+        movie
+            .configure(&mut interpreter)
+            .map_err(|error| usage(error.to_string()))?;
+    }
+
+    let mut frames = 0;
+    let result = loop {
+        if frames == options.frames {
+            break Ok(Stop::FrameLimit);
+        }
+        if let Err(error) = interpreter.run_frame() {
+            break Err(error);
+        }
+        frames += 1;
+        if interpreter.is_halted() {
+            break Ok(Stop::Halted);
+        }
+        if is_spinning(&interpreter) {
+            break Ok(Stop::Spinning);
+        }
+    };
+    if let Some(path) = &options.png {
+        write_png(interpreter.frame_buffer(), options.scale, path)?;
+    }
+    if let Some(path) = &options.text {
+        let text = interpreter.frame_buffer().to_text();
+        if path == Path::new("-") {
+            print!("{}", text);
+        } else {
+            std::fs::write(path, text).map_err(|error| file_error(path, error))?;
+        }
+    }
+    Ok(Outcome {
+        frames,
+        result,
+        max_stack_depth: interpreter.max_stack_depth(),
+        backtrace: interpreter.format_backtrace(),
+    })
+}
+
+/// Returns true if the next instruction is a jump to itself.
+fn is_spinning(interpreter: &Interpreter) -> bool {
+    let address = usize::from(interpreter.program_counter());
+    match interpreter.memory().bytes().get(address..address + 2) {
+        Some(&[high, low]) => u16::from_be_bytes([high, low]) == 0x1000 | address as u16,
+        _ => false,
+    }
+}
+
+/// Turns an input script into the keys held in each of the first `frames`
+/// frames.
+///
+/// Each line of a script is a frame number followed by the keys to hold from
+/// that frame on, as hex digits, or `-` for no keys. Lines must be in frame
+/// order and `#` starts a comment:
+///
+/// ```text
+/// # press 5 for two frames, then hold A and F
+/// 30 5
+/// 32 -
+/// 90 af
+/// ```
+pub fn parse_input_script(script: &str, frames: usize) -> Result<Vec<u16>, String> {
+    let mut keys = vec![0; frames];
+    let mut last_frame = None;
+    for (number, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+        let (frame, held) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| error("expected a frame and keys"))?;
+        let frame: usize = frame.parse().map_err(|_| error("bad frame number"))?;
+        if last_frame.is_some_and(|last| frame <= last) {
+            return Err(error("frames must increase"));
+        }
+        last_frame = Some(frame);
+        let mut mask = 0;
+        match held.trim() {
+            "-" => {}
+            held => {
+                for key in held.chars() {
+                    let key = key
+                        .to_digit(16)
+                        .ok_or_else(|| error("keys must be hex digits"))?;
+                    mask |= 1 << key;
+                }
+            }
+        }
+        for held in keys.iter_mut().skip(frame) {
+            *held = mask;
+        }
+    }
+    Ok(keys)
+}
+
+/// Writes the screen as an RGB image in the app's colours.
+fn write_png(buffer: &FrameBuffer, scale: usize, path: &Path) -> Result<(), CliError> {
+    let width = buffer.width() * scale;
+    let height = buffer.height() * scale;
+    let mut data = Vec::with_capacity(width * height * 3);
+    for row in buffer.pixels().chunks(buffer.width()) {
+        let mut line = Vec::with_capacity(width * 3);
+        for pixel in row {
+            for _ in 0..scale {
+                line.extend_from_slice(&PALETTE[usize::from(*pixel & 3)]);
+            }
+        }
+        for _ in 0..scale {
+            data.extend_from_slice(&line);
+        }
+    }
+    let file = File::create(path).map_err(|error| file_error(path, error))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|error| usage(format!("{}: {}", path.display(), error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::execute;
+    use crate::run::parse_input_script;
+    use crate::run::run;
+    use crate::run::RunOptions;
+    use crate::run::Stop;
+    use crate::CliError;
+    use chip8_core::Chip8Error;
+    use chip8_core::Platform;
+    use std::path::PathBuf;
+
+    fn options(args: &[&str]) -> Result<RunOptions, CliError> {
+        RunOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// A scratch directory for one test's files.
+    fn scratch(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("chip8-cli-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = options(&[
+            "game.ch8",
+            "--frames",
+            "10",
+            "--platform",
+            "xo-chip",
+            "--text",
+            "-",
+        ])
+        .unwrap();
+        assert_eq!(options.rom, PathBuf::from("game.ch8"));
+        assert_eq!(options.frames, 10);
+        assert_eq!(options.platform, Some(Platform::XoChip));
+        assert_eq!(options.text, Some(PathBuf::from("-")));
+        assert!(matches!(self::options(&[]), Err(CliError::Usage(_))));
+        assert!(matches!(
+            self::options(&["a.ch8", "--frames"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(
+            self::options(&["a.ch8", "--frames", "x"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(
+            self::options(&["a.ch8", "b.ch8"]),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_input_script() {
+        let script = "# comment\n2 5\n\n4 -  # let go\n5 a0\n";
+        assert_eq!(
+            parse_input_script(script, 7),
+            Ok(vec![0, 0, 0x20, 0x20, 0, 0x401, 0x401])
+        );
+        assert!(parse_input_script("3 1\n2 1", 5).is_err());
+        assert!(parse_input_script("3 g", 5).is_err());
+        assert!(parse_input_script("3", 5).is_err());
+    }
+
+    #[test]
+    fn test_run_writes_screen() {
+        let directory = scratch("screen");
+        let rom = directory.join("rom.ch8");
+        // LD I, 0x208; DRW V0, V0, 1; JP 0x204; 0x80 (one pixel)
+        std::fs::write(&rom, [0xA2, 0x08, 0xD0, 0x01, 0x12, 0x04, 0x00, 0x00, 0x80]).unwrap();
+        let text = directory.join("screen.txt");
+        let png = directory.join("screen.png");
+        let options = RunOptions {
+            text: Some(text.clone()),
+            png: Some(png.clone()),
+            scale: 2,
+            ..options(&[rom.to_str().unwrap()]).unwrap()
+        };
+        run(&options).unwrap();
+        let text = std::fs::read_to_string(text).unwrap();
+        assert!(text.starts_with("*      "));
+        assert_eq!(text.lines().count(), 32);
+        let decoder = png::Decoder::new(std::fs::File::open(png).unwrap());
+        let info = decoder.read_info().unwrap().info().clone();
+        assert_eq!((info.width, info.height), (128, 64));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_run_reports_interpreter_errors() {
+        let directory = scratch("error");
+        let rom = directory.join("rom.ch8");
+        std::fs::write(&rom, [0xFF, 0xFF]).unwrap();
+        let result = run(&options(&[rom.to_str().unwrap()]).unwrap());
+        assert!(matches!(
+            result,
+            Err(CliError::Interpreter(Chip8Error::InvalidOpcode {
+                opcode: 0xFFFF,
+                ..
+            }))
+        ));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_run_reports_stack_depth() {
+        let directory = scratch("stack");
+        let rom = directory.join("rom.ch8");
+        // CALL 0x204; JP 0x202; CALL 0x208; RET; JP 0x208
+        std::fs::write(
+            &rom,
+            [0x22, 0x04, 0x12, 0x02, 0x22, 0x08, 0x00, 0xEE, 0x12, 0x08],
+        )
+        .unwrap();
+        std::fs::write(rom.with_extension("sym"), "main 0x200\nouter 0x204\n").unwrap();
+        let outcome = execute(&options(&[rom.to_str().unwrap()]).unwrap()).unwrap();
+        assert_eq!(outcome.result, Ok(Stop::Spinning));
+        assert_eq!(outcome.max_stack_depth, 2);
+        assert_eq!(
+            outcome.backtrace,
+            "#0 0x208 outer+4\n#1 0x204 outer\n#2 0x200 main\n"
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_run_follows_input_script() {
+        let directory = scratch("input");
+        let rom = directory.join("rom.ch8");
+        // SKP V0; JP 0x200; JP 0x204
+        std::fs::write(&rom, [0xE0, 0x9E, 0x12, 0x00, 0x12, 0x04]).unwrap();
+        let input = directory.join("input.txt");
+        std::fs::write(&input, "3 0\n").unwrap();
+        let options = RunOptions {
+            frames: 5,
+            ..options(&[rom.to_str().unwrap()]).unwrap()
+        };
+        let outcome = execute(&options).unwrap();
+        assert_eq!((outcome.frames, outcome.result), (5, Ok(Stop::FrameLimit)));
+        let options = RunOptions {
+            input: Some(input),
+            ..options
+        };
+        let outcome = execute(&options).unwrap();
+        assert_eq!((outcome.frames, outcome.result), (4, Ok(Stop::Spinning)));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,990 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! An assembler for Octo, the language of John Earnest's Octo IDE.
+//!
+//! It covers the parts of the language used to write games: labels,
+//! `:alias`, `:const`, `:macro`, `:org`, `:byte`, `:call`, the statements
+//! for every CHIP-8, SUPER-CHIP and XO-CHIP instruction, `if`/`then`,
+//! `if`/`begin`/`else`/`end`, `loop`/`while`/`again` and sprite data given
+//! as bare numbers. `:calc`, `:unpack`, `:next` and `:stringmode` are not
+//! supported.
+
+use crate::instruction::Instruction;
+use crate::memory::PROGRAM_START;
+use crate::movie;
+use crate::symbols::Symbols;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
+
+/// The most bytes a program can take up, on XO-CHIP's 64 KiB of memory.
+const MAX_SIZE: usize = 0x10000 - PROGRAM_START as usize;
+
+/// How deeply macros can expand inside one another, which stops a macro
+/// that uses itself.
+const MAX_MACRO_DEPTH: usize = 64;
+
+/// An assembled program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assembly {
+    /// The ROM, to be loaded at 0x200.
+    pub rom: Vec<u8>,
+    /// The address of every label.
+    pub symbols: BTreeMap<String, u16>,
+    /// The source line of every instruction, by address, for debuggers.
+    pub lines: BTreeMap<u16, usize>,
+}
+
+impl Assembly {
+    /// The labels as symbols for the ROM.
+    pub fn symbol_table(&self) -> Symbols {
+        let mut symbols = Symbols::from_labels(&self.symbols);
+        symbols.rom_hash = Some(movie::rom_hash(&self.rom));
+        symbols
+    }
+}
+
+/// Why Octo source could not be assembled: the problem on `line`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+/// Assembles Octo `source` into a ROM.
+pub fn assemble(source: &str) -> Result<Assembly, AssemblyError> {
+    let mut assembler = Assembler::new(source);
+    assembler.run()?;
+    let Assembler {
+        rom, labels, lines, ..
+    } = assembler;
+    Ok(Assembly {
+        rom,
+        symbols: labels.into_iter().collect(),
+        lines,
+    })
+}
+
+/// Whether `path` names Octo source rather than a ROM, by its `.8o`
+/// extension.
+pub fn is_source(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("8o"))
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    text: String,
+    line: usize,
+    /// How many macro expansions the token came out of.
+    depth: usize,
+}
+
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<Token>,
+}
+
+/// Where a label that wasn't defined yet has to be filled in.
+enum Fixup {
+    /// The low 12 bits of the instruction at the offset.
+    Address(usize),
+    /// The word at the offset, after F000.
+    Long(usize),
+}
+
+/// A condition of `if` or `while`.
+#[derive(Clone, Copy)]
+enum Condition {
+    Equal(u8, Operand),
+    NotEqual(u8, Operand),
+    Key(u8),
+    NotKey(u8),
+}
+
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(u8),
+    Byte(u8),
+}
+
+/// An open `loop`: where it starts and the jumps made by its `while`s.
+struct Loop {
+    start: u16,
+    breaks: Vec<usize>,
+}
+
+struct Assembler {
+    tokens: VecDeque<Token>,
+    line: usize,
+    /// The macro depth of the last token read.
+    depth: usize,
+    rom: Vec<u8>,
+    /// The offset into `rom` the next byte goes to.
+    here: usize,
+    labels: HashMap<String, u16>,
+    lines: BTreeMap<u16, usize>,
+    constants: HashMap<String, u16>,
+    aliases: HashMap<String, u8>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<(String, Fixup, usize)>,
+    loops: Vec<Loop>,
+    /// The jumps of the open `begin`s, waiting for their `else` or `end`.
+    branches: Vec<usize>,
+}
+
+impl Assembler {
+    fn new(source: &str) -> Self {
+        let mut tokens = VecDeque::new();
+        for (number, line) in source.lines().enumerate() {
+            for word in line.split_whitespace() {
+                if word.starts_with('#') {
+                    break;
+                }
+                tokens.push_back(Token {
+                    text: word.to_string(),
+                    line: number + 1,
+                    depth: 0,
+                });
+            }
+        }
+        Assembler {
+            tokens,
+            line: 0,
+            depth: 0,
+            rom: Vec::new(),
+            here: 0,
+            labels: HashMap::new(),
+            lines: BTreeMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            fixups: Vec::new(),
+            loops: Vec::new(),
+            branches: Vec::new(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), AssemblyError> {
+        // like Octo, start with a jump to main unless main comes first
+        let main_first = matches!(
+            (self.tokens.front(), self.tokens.get(1)),
+            (Some(colon), Some(main)) if colon.text == ":" && main.text == "main"
+        );
+        if !main_first {
+            self.emit_with_address(|address| Instruction::Jump { address }, "main")?;
+        }
+        while let Some(token) = self.next_token() {
+            self.statement(&token.text)?;
+        }
+        if !self.loops.is_empty() {
+            return Err(self.error("a loop is missing its again"));
+        }
+        if !self.branches.is_empty() {
+            return Err(self.error("a begin is missing its end"));
+        }
+        for (label, fixup, line) in std::mem::take(&mut self.fixups) {
+            let address = *self.labels.get(&label).ok_or(AssemblyError {
+                line,
+                message: format!("undefined name '{}'", label),
+            })?;
+            match fixup {
+                Fixup::Address(offset) => {
+                    if address > 0xFFF {
+                        return Err(AssemblyError {
+                            line,
+                            message: format!("'{}' is out of reach at 0x{:04X}", label, address),
+                        });
+                    }
+                    self.rom[offset] |= (address >> 8) as u8;
+                    self.rom[offset + 1] = address as u8;
+                }
+                Fixup::Long(offset) => {
+                    self.rom[offset..offset + 2].copy_from_slice(&address.to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, token: &str) -> Result<(), AssemblyError> {
+        use Instruction::*;
+        match token {
+            ":" => {
+                let name = self.name()?;
+                let address = self.address();
+                if self.labels.insert(name.clone(), address).is_some() {
+                    return Err(self.error(&format!("'{}' is already defined", name)));
+                }
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let register = self.register()?;
+                self.aliases.insert(name, register);
+            }
+            ":const" => {
+                let name = self.name()?;
+                let value = self.value()?;
+                self.constants.insert(name, value);
+            }
+            ":macro" => self.define_macro()?,
+            ":org" => {
+                let address = self.value()?;
+                if address < PROGRAM_START {
+                    return Err(self.error(":org can't go below 0x200"));
+                }
+                self.here = usize::from(address - PROGRAM_START);
+            }
+            ":byte" => {
+                let value = self.byte()?;
+                self.emit_bytes(&[value])?;
+            }
+            ":call" => {
+                let target = self.word()?;
+                self.emit_with_address(|address| Call { address }, &target)?;
+            }
+            ":breakpoint" | ":monitor" => {
+                // debugger hints, which take one name
+                self.word()?;
+            }
+            ":calc" | ":unpack" | ":next" | ":stringmode" => {
+                return Err(self.error(&format!("{} is not supported", token)));
+            }
+            "clear" => self.emit(Clear)?,
+            "return" | ";" => self.emit(Return)?,
+            "hires" => self.emit(HighResolution)?,
+            "lores" => self.emit(LowResolution)?,
+            "exit" => self.emit(Exit)?,
+            "scroll-down" => {
+                let rows = self.nibble()?;
+                self.emit(ScrollDown { rows })?;
+            }
+            "scroll-right" => self.emit(ScrollRight)?,
+            "scroll-left" => self.emit(ScrollLeft)?,
+            "audio" => self.emit(LoadAudioPattern)?,
+            "plane" => {
+                let planes = self.nibble()?;
+                self.emit(SelectPlanes { planes })?;
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.emit(StoreBcd { x })?;
+            }
+            "save" | "load" => {
+                let x = self.register()?;
+                let range = self.tokens.front().is_some_and(|next| next.text == "-");
+                let instruction = match (token, range) {
+                    ("save", false) => StoreRegisters { x },
+                    (_, false) => LoadRegisters { x },
+                    (_, true) => {
+                        self.next_token();
+                        let y = self.register()?;
+                        if token == "save" {
+                            SaveRange { x, y }
+                        } else {
+                            LoadRange { x, y }
+                        }
+                    }
+                };
+                self.emit(instruction)?;
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.emit(SaveFlags { x })?;
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.emit(LoadFlags { x })?;
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let rows = self.nibble()?;
+                self.emit(Draw { x, y, rows })?;
+            }
+            "jump" => {
+                let target = self.word()?;
+                self.emit_with_address(|address| Jump { address }, &target)?;
+            }
+            "jump0" => {
+                let target = self.word()?;
+                self.emit_with_address(|address| JumpOffset { address }, &target)?;
+            }
+            "native" => {
+                let target = self.word()?;
+                self.emit_with_address(|address| System { address }, &target)?;
+            }
+            "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(SetPitch { x })?;
+            }
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(if token == "delay" {
+                    SetDelayTimer { x }
+                } else {
+                    SetSoundTimer { x }
+                })?;
+            }
+            "i" => self.address_register()?,
+            "if" => {
+                let condition = self.condition()?;
+                match self.word()?.as_str() {
+                    "then" => self.emit(skip_unless(condition))?,
+                    "begin" => {
+                        self.emit(skip_if(condition))?;
+                        let jump = self.here;
+                        self.emit(Jump { address: 0 })?;
+                        self.branches.push(jump);
+                    }
+                    other => {
+                        return Err(self.error(&format!("expected then or begin, not '{}'", other)))
+                    }
+                }
+            }
+            "else" => {
+                let jump = self
+                    .branches
+                    .pop()
+                    .ok_or_else(|| self.error("else without begin"))?;
+                let end = self.here;
+                self.emit(Jump { address: 0 })?;
+                self.patch_jump(jump);
+                self.branches.push(end);
+            }
+            "end" => {
+                let jump = self
+                    .branches
+                    .pop()
+                    .ok_or_else(|| self.error("end without begin"))?;
+                self.patch_jump(jump);
+            }
+            "loop" => {
+                let start = self.address();
+                self.loops.push(Loop {
+                    start,
+                    breaks: Vec::new(),
+                });
+            }
+            "while" => {
+                let condition = self.condition()?;
+                if self.loops.is_empty() {
+                    return Err(self.error("while outside of a loop"));
+                }
+                self.emit(skip_if(condition))?;
+                let jump = self.here;
+                self.emit(Jump { address: 0 })?;
+                self.loops.last_mut().unwrap().breaks.push(jump);
+            }
+            "again" => {
+                let open = self
+                    .loops
+                    .pop()
+                    .ok_or_else(|| self.error("again without loop"))?;
+                self.emit(Jump {
+                    address: open.start,
+                })?;
+                for jump in open.breaks {
+                    self.patch_jump(jump);
+                }
+            }
+            _ if self.macros.contains_key(token) => self.expand_macro(token)?,
+            _ if self.parse_register(token).is_some() => {
+                let x = self.parse_register(token).unwrap();
+                self.assignment(x)?;
+            }
+            _ => match parse_number(token).or_else(|| self.constants.get(token).copied()) {
+                Some(value) => {
+                    let byte = self.to_byte(value)?;
+                    self.emit_bytes(&[byte])?;
+                }
+                // anything else is the name of a subroutine to call
+                None => self.emit_with_address(|address| Call { address }, token)?,
+            },
+        }
+        Ok(())
+    }
+
+    /// The statements starting with a register, `vx op ...`.
+    fn assignment(&mut self, x: u8) -> Result<(), AssemblyError> {
+        use Instruction::*;
+        let operator = self.word()?;
+        let operand = self.word()?;
+        let instruction = match (operator.as_str(), operand.as_str()) {
+            (":=", "random") => Random {
+                x,
+                byte: self.byte()?,
+            },
+            (":=", "key") => WaitForKey { x },
+            (":=", "delay") => LoadDelayTimer { x },
+            (":=", _) => match self.operand(&operand)? {
+                Operand::Register(y) => Load { x, y },
+                Operand::Byte(byte) => LoadByte { x, byte },
+            },
+            ("+=", _) => match self.operand(&operand)? {
+                Operand::Register(y) => Add { x, y },
+                Operand::Byte(byte) => AddByte { x, byte },
+            },
+            ("-=", _) => match self.operand(&operand)? {
+                Operand::Register(y) => Subtract { x, y },
+                Operand::Byte(byte) => AddByte {
+                    x,
+                    byte: byte.wrapping_neg(),
+                },
+            },
+            (operator, _) => {
+                let y = self.parse_register(&operand).ok_or_else(|| {
+                    self.error(&format!("expected a register, not '{}'", operand))
+                })?;
+                match operator {
+                    "=-" => SubtractReversed { x, y },
+                    "|=" => Or { x, y },
+                    "&=" => And { x, y },
+                    "^=" => Xor { x, y },
+                    ">>=" => ShiftRight { x, y },
+                    "<<=" => ShiftLeft { x, y },
+                    _ => return Err(self.error(&format!("unknown operator '{}'", operator))),
+                }
+            }
+        };
+        self.emit(instruction)
+    }
+
+    /// The statements starting with `i`.
+    fn address_register(&mut self) -> Result<(), AssemblyError> {
+        use Instruction::*;
+        match self.word()?.as_str() {
+            ":=" => match self.word()?.as_str() {
+                "long" => {
+                    let target = self.word()?;
+                    self.emit(LoadLongAddress)?;
+                    match self.resolve(&target) {
+                        Some(address) => self.emit_bytes(&address.to_be_bytes()),
+                        None => {
+                            self.fixups
+                                .push((target, Fixup::Long(self.here), self.line));
+                            self.emit_bytes(&[0, 0])
+                        }
+                    }
+                }
+                "hex" => {
+                    let x = self.register()?;
+                    self.emit(LoadFont { x })
+                }
+                "bighex" => {
+                    let x = self.register()?;
+                    self.emit(LoadLargeFont { x })
+                }
+                target => {
+                    let target = target.to_string();
+                    self.emit_with_address(|address| LoadAddress { address }, &target)
+                }
+            },
+            "+=" => {
+                let x = self.register()?;
+                self.emit(AddAddress { x })
+            }
+            other => Err(self.error(&format!("unknown operator '{}'", other))),
+        }
+    }
+
+    /// Reads the condition of `if` or `while`. Like Octo, `<`, `>`, `<=`
+    /// and `>=` are worked out into vf by subtracting, so they emit those
+    /// instructions and leave a test of vf.
+    fn condition(&mut self) -> Result<Condition, AssemblyError> {
+        use Instruction::*;
+        let x = self.register()?;
+        let operator = self.word()?;
+        match operator.as_str() {
+            "key" => Ok(Condition::Key(x)),
+            "-key" => Ok(Condition::NotKey(x)),
+            "==" | "!=" => {
+                let operand = self.word()?;
+                let operand = self.operand(&operand)?;
+                Ok(if operator == "==" {
+                    Condition::Equal(x, operand)
+                } else {
+                    Condition::NotEqual(x, operand)
+                })
+            }
+            "<" | ">" | "<=" | ">=" => {
+                if x == 0xF {
+                    return Err(self.error(&format!("vf can't be compared with '{}'", operator)));
+                }
+                let operand = self.word()?;
+                self.emit(match self.operand(&operand)? {
+                    Operand::Register(y) => Load { x: 0xF, y },
+                    Operand::Byte(byte) => LoadByte { x: 0xF, byte },
+                })?;
+                // vf -= vx leaves vf set when the operand >= vx, and vf =- vx
+                // when vx >= the operand
+                self.emit(match operator.as_str() {
+                    ">" | "<=" => Subtract { x: 0xF, y: x },
+                    _ => SubtractReversed { x: 0xF, y: x },
+                })?;
+                Ok(match operator.as_str() {
+                    "<" | ">" => Condition::Equal(0xF, Operand::Byte(0)),
+                    _ => Condition::Equal(0xF, Operand::Byte(1)),
+                })
+            }
+            _ => Err(self.error(&format!("unsupported comparison '{}'", operator))),
+        }
+    }
+
+    fn define_macro(&mut self) -> Result<(), AssemblyError> {
+        let name = self.name()?;
+        let mut parameters = Vec::new();
+        loop {
+            let word = self.word()?;
+            if word == "{" {
+                break;
+            }
+            parameters.push(word);
+        }
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let token = self
+                .next_token()
+                .ok_or_else(|| self.error(&format!("macro '{}' is missing its }}", name)))?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { parameters, body });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<(), AssemblyError> {
+        let depth = self.depth + 1;
+        if depth > MAX_MACRO_DEPTH {
+            return Err(self.error(&format!(
+                "macro '{}' is nested more than {} deep",
+                name, MAX_MACRO_DEPTH
+            )));
+        }
+        let count = self.macros[name].parameters.len();
+        let mut arguments = HashMap::new();
+        for index in 0..count {
+            let argument = self.word()?;
+            arguments.insert(self.macros[name].parameters[index].clone(), argument);
+        }
+        let line = self.line;
+        for token in self.macros[name].body.iter().rev() {
+            let text = arguments.get(&token.text).unwrap_or(&token.text).clone();
+            self.tokens.push_front(Token { text, line, depth });
+        }
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.pop_front()?;
+        self.line = token.line;
+        self.depth = token.depth;
+        Some(token)
+    }
+
+    fn word(&mut self) -> Result<String, AssemblyError> {
+        match self.next_token() {
+            Some(token) => Ok(token.text),
+            None => Err(self.error("unexpected end of the source")),
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AssemblyError> {
+        let word = self.word()?;
+        if word != expected {
+            return Err(self.error(&format!("expected '{}', not '{}'", expected, word)));
+        }
+        Ok(())
+    }
+
+    /// A name being defined, which mustn't look like anything else.
+    fn name(&mut self) -> Result<String, AssemblyError> {
+        let name = self.word()?;
+        if self.parse_register(&name).is_some() || parse_number(&name).is_some() {
+            return Err(self.error(&format!("'{}' can't be used as a name", name)));
+        }
+        Ok(name)
+    }
+
+    fn register(&mut self) -> Result<u8, AssemblyError> {
+        let word = self.word()?;
+        self.parse_register(&word)
+            .ok_or_else(|| self.error(&format!("expected a register, not '{}'", word)))
+    }
+
+    fn parse_register(&self, word: &str) -> Option<u8> {
+        if let Some(register) = self.aliases.get(word) {
+            return Some(*register);
+        }
+        match word.strip_prefix('v').or_else(|| word.strip_prefix('V')) {
+            Some(digit) if digit.len() == 1 => u8::from_str_radix(digit, 16).ok(),
+            _ => None,
+        }
+    }
+
+    /// A number, a constant or a label defined earlier.
+    fn parse_value(&self, word: &str) -> Option<u16> {
+        parse_number(word)
+            .or_else(|| self.constants.get(word).copied())
+            .or_else(|| self.labels.get(word).copied())
+    }
+
+    fn value(&mut self) -> Result<u16, AssemblyError> {
+        let word = self.word()?;
+        self.parse_value(&word)
+            .ok_or_else(|| self.error(&format!("expected a number, not '{}'", word)))
+    }
+
+    fn to_byte(&self, value: u16) -> Result<u8, AssemblyError> {
+        // negative numbers arrive as their 16 bit two's complement
+        if value <= 0xFF || value >= 0xFF80 {
+            Ok(value as u8)
+        } else {
+            Err(self.error(&format!("{} doesn't fit in a byte", value)))
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, AssemblyError> {
+        let value = self.value()?;
+        self.to_byte(value)
+    }
+
+    fn nibble(&mut self) -> Result<u8, AssemblyError> {
+        let value = self.value()?;
+        if value > 0xF {
+            return Err(self.error(&format!("{} doesn't fit in a nibble", value)));
+        }
+        Ok(value as u8)
+    }
+
+    fn operand(&self, word: &str) -> Result<Operand, AssemblyError> {
+        if let Some(register) = self.parse_register(word) {
+            return Ok(Operand::Register(register));
+        }
+        let value = self
+            .parse_value(word)
+            .ok_or_else(|| self.error(&format!("expected a register or number, not '{}'", word)))?;
+        Ok(Operand::Byte(self.to_byte(value)?))
+    }
+
+    fn resolve(&self, word: &str) -> Option<u16> {
+        self.parse_value(word)
+    }
+
+    fn address(&self) -> u16 {
+        PROGRAM_START + self.here as u16
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> Result<(), AssemblyError> {
+        // the jump to main comes before any line
+        if self.line > 0 {
+            self.lines.insert(self.address(), self.line);
+        }
+        self.emit_bytes(&instruction.encode().to_be_bytes())
+    }
+
+    /// Emits an instruction whose low 12 bits are the address of `target`,
+    /// which may be a label defined further on.
+    fn emit_with_address(
+        &mut self,
+        instruction: fn(u16) -> Instruction,
+        target: &str,
+    ) -> Result<(), AssemblyError> {
+        match self.resolve(target) {
+            Some(address) if address > 0xFFF => Err(self.error(&format!(
+                "'{}' is out of reach at 0x{:04X}",
+                target, address
+            ))),
+            Some(address) => self.emit(instruction(address)),
+            None => {
+                self.fixups
+                    .push((target.to_string(), Fixup::Address(self.here), self.line));
+                self.emit(instruction(0))
+            }
+        }
+    }
+
+    fn emit_bytes(&mut self, bytes: &[u8]) -> Result<(), AssemblyError> {
+        let end = self.here + bytes.len();
+        if end > MAX_SIZE {
+            return Err(self.error("the program doesn't fit in memory"));
+        }
+        if self.rom.len() < end {
+            self.rom.resize(end, 0);
+        }
+        self.rom[self.here..end].copy_from_slice(bytes);
+        self.here = end;
+        Ok(())
+    }
+
+    /// Points the placeholder jump at `offset` to the current address.
+    fn patch_jump(&mut self, offset: usize) {
+        let jump = Instruction::Jump {
+            address: self.address(),
+        };
+        self.rom[offset..offset + 2].copy_from_slice(&jump.encode().to_be_bytes());
+    }
+
+    fn error(&self, message: &str) -> AssemblyError {
+        AssemblyError {
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// The instruction that skips the next one when `condition` holds.
+fn skip_if(condition: Condition) -> Instruction {
+    use Instruction::*;
+    match condition {
+        Condition::Equal(x, Operand::Byte(byte)) => SkipIfEqualByte { x, byte },
+        Condition::Equal(x, Operand::Register(y)) => SkipIfEqual { x, y },
+        Condition::NotEqual(x, Operand::Byte(byte)) => SkipIfNotEqualByte { x, byte },
+        Condition::NotEqual(x, Operand::Register(y)) => SkipIfNotEqual { x, y },
+        Condition::Key(x) => SkipIfKey { x },
+        Condition::NotKey(x) => SkipIfNotKey { x },
+    }
+}
+
+/// The instruction that skips the next one unless `condition` holds, which
+/// is how `if ... then` runs the next statement only when it does.
+fn skip_unless(condition: Condition) -> Instruction {
+    skip_if(match condition {
+        Condition::Equal(x, operand) => Condition::NotEqual(x, operand),
+        Condition::NotEqual(x, operand) => Condition::Equal(x, operand),
+        Condition::Key(x) => Condition::NotKey(x),
+        Condition::NotKey(x) => Condition::Key(x),
+    })
+}
+
+/// Reads a decimal, `0x` hexadecimal or `0b` binary number, which may be
+/// negative.
+fn parse_number(word: &str) -> Option<u16> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        u16::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative {
+        value.wrapping_neg()
+    } else {
+        value
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::assemble;
+    use crate::assembler::AssemblyError;
+
+    fn rom(source: &str) -> Vec<u8> {
+        assemble(source).unwrap().rom
+    }
+
+    #[test]
+    fn test_statements() {
+        let source = "
+            : main
+                clear
+                v3 := 0x1F  # comment
+                v0 += v1
+                v2 -= 1
+                i := sprite
+                sprite v0 v1 5
+                if v0 == 3 then v1 := key
+                vf := random 0b1111
+                i := long sprite
+                return
+            : sprite
+                0xF0 0x90 -1
+        ";
+        assert_eq!(
+            rom(source),
+            vec![
+                0x00, 0xE0, // clear
+                0x63, 0x1F, // v3 := 0x1F
+                0x80, 0x14, // v0 += v1
+                0x72, 0xFF, // v2 -= 1
+                0xA2, 0x18, // i := sprite
+                0xD0, 0x15, // sprite v0 v1 5
+                0x40, 0x03, // if v0 == 3 then
+                0xF1, 0x0A, // v1 := key
+                0xCF, 0x0F, // vf := random 0b1111
+                0xF0, 0x00, 0x02, 0x18, // i := long sprite
+                0x00, 0xEE, // return
+                0xF0, 0x90, 0xFF, // sprite data
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jump_to_main() {
+        let assembly = assemble(": draw ; : main draw").unwrap();
+        assert_eq!(assembly.rom, vec![0x12, 0x04, 0x00, 0xEE, 0x22, 0x02]);
+        assert_eq!(assembly.symbols["draw"], 0x202);
+        assert_eq!(assembly.symbols["main"], 0x204);
+        let symbols = assembly.symbol_table();
+        assert_eq!(symbols.describe(0x204), "main");
+        assert!(symbols.matches(&crate::movie::rom_hash(&assembly.rom)));
+        let assembly = assemble(": draw\n  return\n: main\n  draw\n").unwrap();
+        assert_eq!(
+            assembly.lines.into_iter().collect::<Vec<_>>(),
+            vec![(0x202, 2), (0x204, 4)]
+        );
+    }
+
+    #[test]
+    fn test_alias_const_and_macro() {
+        let source = "
+            :alias x v4
+            :const SPEED 3
+            :macro move reg amount { reg += amount }
+            : main
+                move x SPEED
+        ";
+        assert_eq!(rom(source), vec![0x12, 0x02, 0x74, 0x03]);
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let source = "
+            : main
+                loop
+                    v0 += 1
+                    while v0 != 10
+                    if v1 key begin
+                        v2 := 1
+                    else
+                        v2 := 2
+                    end
+                again
+        ";
+        assert_eq!(
+            rom(source),
+            vec![
+                0x70, 0x01, // 0x200: v0 += 1
+                0x40, 0x0A, // while v0 != 10: skip if v0 != 10
+                0x12, 0x12, // jump out of the loop
+                0xE1, 0x9E, // if v1 key begin: skip if v1 is held
+                0x12, 0x0E, // jump to else
+                0x62, 0x01, // v2 := 1
+                0x12, 0x10, // else: jump to end
+                0x62, 0x02, // 0x20E: v2 := 2
+                0x12, 0x00, // 0x210: again
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let source = "
+            : main
+                if v1 < v2 then v0 := 1
+                if v1 > 5 then v0 := 2
+                if v1 <= v2 then v0 := 3
+                loop
+                    while v1 >= 5
+                again
+        ";
+        assert_eq!(
+            rom(source),
+            vec![
+                0x8F, 0x20, // if v1 < v2: vf := v2
+                0x8F, 0x17, // vf =- v1, set when v1 >= v2
+                0x4F, 0x00, // skip if vf != 0
+                0x60, 0x01, // v0 := 1
+                0x6F, 0x05, // if v1 > 5: vf := 5
+                0x8F, 0x15, // vf -= v1, set when 5 >= v1
+                0x4F, 0x00, // skip if vf != 0
+                0x60, 0x02, // v0 := 2
+                0x8F, 0x20, // if v1 <= v2: vf := v2
+                0x8F, 0x15, // vf -= v1, set when v2 >= v1
+                0x4F, 0x01, // skip if vf != 1
+                0x60, 0x03, // v0 := 3
+                0x6F, 0x05, // 0x218: while v1 >= 5: vf := 5
+                0x8F, 0x17, // vf =- v1, set when v1 >= 5
+                0x3F, 0x01, // skip if vf == 1
+                0x12, 0x22, // jump out of the loop
+                0x12, 0x18, // again
+            ]
+        );
+        assert!(assemble(": main if vf < v1 then v0 := 1").is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        assert_eq!(
+            rom(": main save v3 load v3 save v1 - v2 load v2 - v1"),
+            vec![0xF3, 0x55, 0xF3, 0x65, 0x51, 0x22, 0x52, 0x13]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            assemble(": main\njump nowhere"),
+            Err(AssemblyError {
+                line: 2,
+                message: "undefined name 'nowhere'".to_string()
+            })
+        );
+        assert!(matches!(
+            assemble(": main\nv0 := 300"),
+            Err(AssemblyError { line: 2, .. })
+        ));
+        assert!(assemble(": main loop").is_err());
+        assert!(assemble(": main : main").is_err());
+        assert!(assemble(": main :calc x { 1 }").is_err());
+        assert_eq!(
+            assemble(":macro forever { forever }\n: main forever"),
+            Err(AssemblyError {
+                line: 2,
+                message: "macro 'forever' is nested more than 64 deep".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,64 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/// The tone generator driven by the sound timer.
+pub trait Buzzer: Send + Sync {
+    /// Configures the tone before the first call to `play`.
+    fn initialize(self, frequency: f32, volume: f32);
+    /// Starts the tone. Called when the sound timer is set.
+    fn play(&self);
+    /// Stops the tone. Called once the sound timer has reached zero.
+    fn pause(&self);
+    /// Replaces the tone with an XO-CHIP audio pattern: 128 one bit samples,
+    /// most significant bit first, looped at `sample_rate` samples per
+    /// second. Buzzers that can only beep may ignore it.
+    fn set_pattern(&self, _pattern: &[u8; 16], _sample_rate: f32) {}
+}
+
+/// A buzzer that makes no sound, for running without a speaker.
+pub struct NullBuzzer;
+
+impl Buzzer for NullBuzzer {
+    fn initialize(self, _frequency: f32, _volume: f32) {}
+
+    fn play(&self) {}
+
+    fn pause(&self) {}
+}
+
+/// The pitch register's value when a program starts.
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// The rate an XO-CHIP audio pattern is played back at for a given value of
+/// the pitch register: 4000 samples per second at the default pitch of 64,
+/// doubling every 48 steps.
+pub fn playback_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((f32::from(pitch) - 64.0) / 48.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio::playback_rate;
+    use crate::audio::DEFAULT_PITCH;
+
+    #[test]
+    fn test_playback_rate() {
+        assert_eq!(playback_rate(DEFAULT_PITCH), 4000.0);
+        assert!((playback_rate(112) - 8000.0).abs() < 0.01);
+        assert!((playback_rate(16) - 2000.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,2759 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audio;
+use crate::audio::Buzzer;
+use crate::clock;
+use crate::clock::Clock;
+use crate::debugger::Debugger;
+use crate::debugger::PauseReason;
+use crate::debugger::Watchpoint;
+use crate::error::Chip8Error;
+use crate::expression::MachineState;
+use crate::graphics;
+use crate::graphics::Display;
+use crate::graphics::FrameBuffer;
+use crate::history::History;
+use crate::history::Undo;
+use crate::instruction::Instruction;
+use crate::keyboard::Keyboard;
+use crate::memory;
+use crate::memory::Memory;
+use crate::memory::Stack;
+use crate::movie;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::registers::Address;
+use crate::registers::AddressRegister;
+use crate::registers::DelayTimer;
+use crate::registers::ProgramCounter;
+use crate::registers::Register;
+use crate::registers::SoundTimer;
+use crate::rng::Rng;
+use crate::rng::RngMode;
+use crate::snapshot::Snapshot;
+use crate::snapshot::SNAPSHOT_VERSION;
+use crate::symbols::Symbols;
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use std::time::Duration;
+
+/// The number of instructions `run_frame` executes unless told otherwise,
+/// giving a CPU speed of 600 instructions per second.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 10;
+
+/// A CHIP-8 machine: memory, registers, timers and the devices attached to
+/// them.
+pub struct Interpreter {
+    platform: Platform,
+    rom_hash: String,
+    memory: Memory,
+    program_counter: ProgramCounter,
+    display_screen: Box<dyn Display>,
+    stack: Stack,
+    address_register: AddressRegister,
+    registers: Vec<Register>,
+    delay_timer: DelayTimer,
+    sound_timer: SoundTimer,
+    instructions_per_frame: usize,
+    quirks: Quirks,
+    waiting_for_display: bool,
+    halted: bool,
+    rpl_flags: [u8; 16],
+    audio_pattern: [u8; 16],
+    pitch: u8,
+    rng: Rng,
+    /// Drives the timers instead of `run_frame` when set.
+    clock: Option<Box<dyn Clock>>,
+    timer_epoch: Duration,
+    timer_ticks: u64,
+    buzzer: Box<dyn Buzzer>,
+    keyboard_device: Box<dyn Keyboard>,
+    debugger: Debugger,
+    history: History,
+    symbols: Symbols,
+}
+
+impl Interpreter {
+    /// Creates a CHIP-8 machine with `rom` loaded at 0x200 and the program
+    /// counter pointing at it. Fails if the ROM does not fit in memory.
+    pub fn new(
+        display: Box<dyn Display>,
+        buzzer: Box<dyn Buzzer>,
+        keyboard_device: Box<dyn Keyboard>,
+        rom: &[u8],
+    ) -> Result<Self, Chip8Error> {
+        Interpreter::with_platform(display, buzzer, keyboard_device, rom, Platform::Chip8)
+    }
+
+    /// Creates a machine for `platform`, which sets the size of memory and
+    /// whether XO-CHIP instructions are recognised.
+    pub fn with_platform(
+        display: Box<dyn Display>,
+        buzzer: Box<dyn Buzzer>,
+        keyboard_device: Box<dyn Keyboard>,
+        rom: &[u8],
+        platform: Platform,
+    ) -> Result<Self, Chip8Error> {
+        let mut memory = memory::Memory::with_size(platform.memory_size());
+        memory.load_rom(rom)?;
+        let rom_hash = movie::rom_hash(rom);
+        Ok(Self {
+            platform,
+            rom_hash,
+            memory,
+            program_counter: ProgramCounter::new(),
+            display_screen: display,
+            stack: Stack::new(),
+            address_register: AddressRegister::new(),
+            registers: vec![Register::from(0); 16],
+            delay_timer: DelayTimer::new(),
+            sound_timer: SoundTimer::new(),
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            quirks: Quirks::default(),
+            waiting_for_display: false,
+            halted: false,
+            rpl_flags: [0; 16],
+            audio_pattern: [0; 16],
+            pitch: audio::DEFAULT_PITCH,
+            rng: Rng::new(),
+            clock: None,
+            timer_epoch: Duration::ZERO,
+            timer_ticks: 0,
+            buzzer,
+            keyboard_device,
+            debugger: Debugger::new(),
+            history: History::default(),
+            symbols: Symbols::new(),
+        })
+    }
+
+    /// Hands the timers to `clock`, counting from its current time. From
+    /// then on `run_frame` ticks them by `update_timers` instead of once per
+    /// frame, so they never tick twice for the same 1/60th of a second.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.timer_epoch = clock.now();
+        self.timer_ticks = 0;
+        self.clock = Some(clock);
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// The address of the next instruction.
+    pub fn program_counter(&self) -> u16 {
+        u16::from(self.program_counter.value)
+    }
+
+    /// Moves the program counter, for example from a debugger.
+    pub fn set_program_counter(&mut self, address: u16) {
+        self.program_counter.set(Address::from(address));
+    }
+
+    /// The value of Vx.
+    pub fn register(&self, x: u8) -> u8 {
+        u8::from(self.registers[usize::from(x & 0xF)])
+    }
+
+    pub fn set_register(&mut self, x: u8, value: u8) {
+        self.registers[usize::from(x & 0xF)].set(value);
+    }
+
+    /// The value of I.
+    pub fn address_register(&self) -> u16 {
+        u16::from(self.address_register)
+    }
+
+    pub fn set_address_register(&mut self, value: u16) {
+        self.address_register.set(Address::from(value));
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Overwrites memory from `address` without setting off watchpoints.
+    pub fn write_memory(&mut self, address: u16, bytes: &[u8]) -> Result<(), Chip8Error> {
+        self.memory.write(address, bytes)
+    }
+
+    /// The screen as it was last drawn.
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+        self.display_screen.frame_buffer()
+    }
+
+    /// Replaces the keypad, for example to record or replay the input.
+    pub fn set_keyboard(&mut self, keyboard_device: Box<dyn Keyboard>) {
+        self.keyboard_device = keyboard_device;
+    }
+
+    /// The SHA-1 of the ROM the machine was created with, in hexadecimal.
+    pub fn rom_hash(&self) -> &str {
+        &self.rom_hash
+    }
+
+    /// The breakpoints and execution state.
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Pauses after any instruction that makes an access `watchpoint`
+    /// watches for.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.memory.add_watchpoint(watchpoint);
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        self.memory.remove_watchpoint(watchpoint)
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        self.memory.watchpoints()
+    }
+
+    /// Stops executing instructions until the machine is resumed or stepped.
+    pub fn pause(&mut self) {
+        if !self.debugger.is_paused() {
+            self.debugger.pause(PauseReason::Requested);
+        }
+    }
+
+    /// Carries on from a pause, running until the next breakpoint.
+    pub fn resume(&mut self) {
+        self.debugger.resume();
+    }
+
+    /// Executes the next instruction, even if there is a breakpoint on it,
+    /// and pauses again. A watchpoint the instruction sets off is kept as
+    /// the reason for the pause.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        self.debugger.resume();
+        let result = self.run_iteration();
+        if !self.debugger.is_paused() {
+            self.debugger.pause(PauseReason::Step);
+        }
+        result
+    }
+
+    /// Steps over a 2NNN (CALL) by running until the subroutine returns,
+    /// or steps any other instruction.
+    pub fn step_over(&mut self) -> Result<(), Chip8Error> {
+        let address = self.program_counter();
+        // read the instruction without setting off a watchpoint
+        let start = usize::from(address);
+        let code = match self.memory.bytes().get(start..start + 2) {
+            Some(&[high, low]) => u16::from_be_bytes([high, low]),
+            _ => return self.step(),
+        };
+        match Instruction::decode(code, self.platform) {
+            Ok(Instruction::Call { .. }) => {
+                self.debugger
+                    .resume_until_return_to(address.wrapping_add(2), self.stack.stack_pointer());
+                Ok(())
+            }
+            _ => self.step(),
+        }
+    }
+
+    /// Runs until the current subroutine returns. Outside of a subroutine
+    /// this is the same as `resume`.
+    pub fn step_out(&mut self) {
+        match self.stack.stack_pointer() {
+            0 => self.debugger.resume(),
+            depth => self.debugger.resume_until_return(depth),
+        }
+    }
+
+    /// Runs until the program counter reaches `address`, or a breakpoint.
+    pub fn run_to(&mut self, address: u16) {
+        self.debugger.resume_until(address);
+    }
+
+    /// The call stack of return addresses.
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// The most calls that have been in progress at once.
+    pub fn max_stack_depth(&self) -> u8 {
+        self.stack.max_depth()
+    }
+
+    /// The program counter followed by the call behind each return address
+    /// on the stack, innermost first.
+    pub fn backtrace(&self) -> Vec<u16> {
+        let returns = self.stack.return_addresses().iter().rev();
+        std::iter::once(self.program_counter())
+            .chain(returns.map(|&address| u16::from(address).wrapping_sub(2)))
+            .collect()
+    }
+
+    /// Lists the backtrace one frame to a line, such as `#1 0x204 main+4`,
+    /// naming addresses by the symbols where they can.
+    pub fn format_backtrace(&self) -> String {
+        let mut text = String::new();
+        for (depth, address) in self.backtrace().into_iter().enumerate() {
+            text.push_str(&format!("#{} 0x{:03X}", depth, address));
+            if let Some(location) = self.symbols.locate(address) {
+                text.push_str(&format!(" {}", location));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The names of the program's addresses, used to describe them.
+    pub fn symbols(&self) -> &Symbols {
+        &self.symbols
+    }
+
+    pub fn set_symbols(&mut self, symbols: Symbols) {
+        self.symbols = symbols;
+    }
+
+    /// The undo log of recent instructions.
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Sets how many of the most recent instructions can be stepped back
+    /// over. 0 stops recording them.
+    pub fn set_history_limit(&mut self, instructions: usize) {
+        self.history.set_limit(instructions);
+    }
+
+    /// Undoes the last instruction and pauses. Returns false, leaving the
+    /// machine as it is, if there is no instruction left to undo.
+    pub fn step_back(&mut self) -> bool {
+        let undone = self.undo();
+        self.debugger.pause(match undone {
+            true => PauseReason::Step,
+            false => PauseReason::HistoryStart,
+        });
+        undone
+    }
+
+    /// Undoes instructions until the program counter is back at a
+    /// breakpoint, or the history runs out, and pauses. Returns true if it
+    /// stopped at a breakpoint.
+    pub fn reverse_continue(&mut self) -> bool {
+        while self.undo() {
+            if self.debugger.has_breakpoint(self) {
+                self.debugger.pause(PauseReason::Breakpoint {
+                    address: self.program_counter(),
+                });
+                return true;
+            }
+        }
+        self.debugger.pause(PauseReason::HistoryStart);
+        false
+    }
+
+    /// Captures what the next instruction can change and starts noting the
+    /// memory it stores to.
+    fn record_undo(&mut self) -> Undo {
+        self.memory.start_journal();
+        let mut registers = [0; 16];
+        for (saved, register) in registers.iter_mut().zip(&self.registers) {
+            *saved = u8::from(*register);
+        }
+        Undo {
+            program_counter: self.program_counter(),
+            address_register: self.address_register(),
+            registers,
+            stack: self.stack.clone(),
+            delay_timer: self.delay_timer.get(),
+            sound_timer: self.sound_timer.get(),
+            rng: self.rng,
+            halted: self.halted,
+            memory: Vec::new(),
+        }
+    }
+
+    /// Puts back the state from before the last recorded instruction,
+    /// returning false if there is none.
+    fn undo(&mut self) -> bool {
+        let undo = match self.history.pop() {
+            Some(undo) => undo,
+            None => return false,
+        };
+        self.memory.undo(&undo.memory);
+        self.program_counter
+            .set(Address::from(undo.program_counter));
+        self.address_register
+            .set(Address::from(undo.address_register));
+        for (register, value) in self.registers.iter_mut().zip(undo.registers) {
+            register.set(value);
+        }
+        self.stack = undo.stack;
+        self.delay_timer.set(undo.delay_timer);
+        self.set_sound_timer(undo.sound_timer);
+        self.rng = undo.rng;
+        self.halted = undo.halted;
+        true
+    }
+
+    /// The XO-CHIP audio pattern last loaded by F002.
+    pub fn audio_pattern(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    /// The XO-CHIP pitch register set by Fx3A.
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Captures the whole machine: memory, stack, registers, timers, the
+    /// display and the random number generator.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut stack = [0; 16];
+        for (saved, frame) in stack.iter_mut().zip(self.stack.frames()) {
+            *saved = u16::from(*frame);
+        }
+        let mut registers = [0; 16];
+        for (saved, register) in registers.iter_mut().zip(&self.registers) {
+            *saved = u8::from(*register);
+        }
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            platform: self.platform,
+            memory: self.memory.bytes().to_vec(),
+            stack,
+            stack_pointer: self.stack.stack_pointer(),
+            program_counter: u16::from(self.program_counter.value),
+            address_register: u16::from(self.address_register),
+            registers,
+            delay_timer: self.delay_timer.get(),
+            sound_timer: self.sound_timer.get(),
+            frame_buffer: self.display_screen.frame_buffer().clone(),
+            rng: self.rng,
+            halted: self.halted,
+            rpl_flags: self.rpl_flags,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+        }
+    }
+
+    /// Puts the machine back into the state captured by `snapshot` and
+    /// refreshes the display. Fails without changing anything if the
+    /// snapshot is invalid or was taken on another platform.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), Chip8Error> {
+        snapshot.validate()?;
+        if snapshot.platform != self.platform {
+            return Err(Chip8Error::InvalidSnapshot {
+                reason: format!(
+                    "taken on {}, not {}",
+                    snapshot.platform.name(),
+                    self.platform.name()
+                ),
+            });
+        }
+        self.history.clear();
+        let watchpoints = self.memory.watchpoints().to_vec();
+        self.memory = Memory::from_bytes(&snapshot.memory);
+        self.memory.set_watchpoints(watchpoints);
+        self.stack = Stack::from_frames(snapshot.stack.map(Address::from), snapshot.stack_pointer);
+        self.program_counter
+            .set(Address::from(snapshot.program_counter));
+        self.address_register
+            .set(Address::from(snapshot.address_register));
+        self.registers = snapshot
+            .registers
+            .iter()
+            .map(|value| Register::from(*value))
+            .collect();
+        self.delay_timer.set(snapshot.delay_timer);
+        self.sound_timer.set(snapshot.sound_timer);
+        *self.display_screen.frame_buffer_mut() = snapshot.frame_buffer.clone();
+        self.rng = snapshot.rng;
+        self.halted = snapshot.halted;
+        self.rpl_flags = snapshot.rpl_flags;
+        self.audio_pattern = snapshot.audio_pattern;
+        self.pitch = snapshot.pitch;
+        self.waiting_for_display = false;
+        if self.platform == Platform::XoChip {
+            self.buzzer
+                .set_pattern(&self.audio_pattern, audio::playback_rate(self.pitch));
+        }
+        if self.sound_timer.get() > 0 {
+            self.buzzer.play();
+        } else {
+            self.buzzer.pause();
+        }
+        self.display_screen.refresh();
+        Ok(())
+    }
+
+    /// The seed of the generator behind Cxkk. Interpreters are seeded from
+    /// the operating system when they are created.
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Restarts the random number generator from `seed`, so that a run can
+    /// be reproduced. Call it before the first instruction.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::with_mode(seed, self.rng.mode());
+    }
+
+    pub fn rng_mode(&self) -> RngMode {
+        self.rng.mode()
+    }
+
+    /// Selects how Cxkk generates numbers, restarting the generator from
+    /// the current seed.
+    pub fn set_rng_mode(&mut self, mode: RngMode) {
+        self.rng = Rng::with_mode(self.rng.seed(), mode);
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Selects how the instructions listed in `Quirks` behave from the next
+    /// instruction on.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer.get()
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer.get()
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer.set(value);
+    }
+
+    /// Sets the sound timer, starting or stopping the buzzer to match.
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer.set(value);
+        if value > 0 {
+            self.buzzer.play();
+        } else {
+            self.buzzer.pause();
+        }
+    }
+
+    /// Counts both timers down by one, as happens 60 times a second.
+    pub fn tick_timers(&mut self) {
+        self.delay_timer.tick();
+        if self.sound_timer.get() > 0 {
+            self.sound_timer.tick();
+            if self.sound_timer.get() == 0 {
+                self.buzzer.pause();
+            }
+        }
+    }
+
+    /// Ticks the timers once for every 1/60th of a second the clock has
+    /// advanced since they were last updated. Without a clock this does
+    /// nothing.
+    pub fn update_timers(&mut self) {
+        let Some(clock) = &self.clock else {
+            return;
+        };
+        let due = clock::timer_ticks(clock.now().saturating_sub(self.timer_epoch));
+        while self.timer_ticks < due {
+            self.tick_timers();
+            self.timer_ticks += 1;
+        }
+    }
+
+    pub fn instructions_per_frame(&self) -> usize {
+        self.instructions_per_frame
+    }
+
+    /// Sets how many instructions `run_frame` executes, which sets the CPU
+    /// speed to 60 times that many instructions per second.
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: usize) {
+        self.instructions_per_frame = instructions_per_frame;
+    }
+
+    /// Returns true once the program has executed 00FD (EXIT). A halted
+    /// machine no longer executes instructions.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Runs one 60 Hz frame: `instructions_per_frame` instructions followed
+    /// by a single timer tick, or the ticks the clock says are due if one is
+    /// set, and a display refresh. Stops at the first instruction that
+    /// fails.
+    ///
+    /// With the `display_wait` quirk the frame also ends early after the
+    /// first sprite is drawn. A paused machine does nothing, and a frame in
+    /// which the debugger pauses ends at the pause.
+    pub fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        if self.debugger.is_paused() {
+            return Ok(());
+        }
+        self.keyboard_device.start_frame();
+        self.waiting_for_display = false;
+        for _ in 0..self.instructions_per_frame {
+            self.run_iteration()?;
+            if self.waiting_for_display || self.halted || self.debugger.is_paused() {
+                break;
+            }
+        }
+        match self.clock {
+            Some(_) => self.update_timers(),
+            None => self.tick_timers(),
+        }
+        self.display_screen.refresh();
+        Ok(())
+    }
+
+    /// Fetches, decodes and executes a single instruction.
+    ///
+    /// When the instruction fails the program counter is left pointing at the
+    /// instruction after it, so calling `run_iteration` again carries on from
+    /// there. Nothing is executed if the debugger is paused or pauses on
+    /// this instruction.
+    pub fn run_iteration(&mut self) -> Result<(), Chip8Error> {
+        if self.halted {
+            return Ok(());
+        }
+        // the debugger looks at the whole machine, so it is moved out first
+        let mut debugger = std::mem::take(&mut self.debugger);
+        let paused = debugger.should_pause(self);
+        self.debugger = debugger;
+        if paused {
+            return Ok(());
+        }
+        self.memory.take_watch_hit();
+        // fetch
+        let instruction_address = self.program_counter.value;
+        let instruction_code = self.memory.fetch(&self.program_counter)?;
+        let mut instruction_code = &instruction_code[..];
+        let instruction_code = instruction_code.read_u16::<BigEndian>().unwrap();
+        let undo = self.history.is_recording().then(|| self.record_undo());
+        // increment
+        self.program_counter.increment();
+        // decode and execute
+        let result = Instruction::decode(instruction_code, self.platform)
+            .map_err(|invalid| invalid.at(instruction_address))
+            .and_then(|instruction| self.execute(&instruction));
+        if let Some(mut undo) = undo {
+            undo.memory = self.memory.take_journal();
+            self.history.push(undo);
+        }
+        if let Some(hit) = self.memory.take_watch_hit() {
+            self.debugger.pause(PauseReason::Watchpoint {
+                instruction_address: u16::from(instruction_address),
+                address: hit.address,
+                access: hit.access,
+                old: hit.old,
+                new: hit.new,
+            });
+        }
+        result
+    }
+
+    /// Executes `instruction` as if it had just been fetched, with the
+    /// program counter already pointing at the next instruction.
+    pub fn execute(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
+        let next_address = self.program_counter.value;
+        match *instruction {
+            Instruction::System { .. } => {
+                // no-op
+            }
+            Instruction::Clear => {
+                Interpreter::clear(&mut self.display_screen);
+            }
+            Instruction::Return => {
+                Interpreter::return_subroutine(&mut self.program_counter, &mut self.stack)?;
+            }
+            Instruction::ScrollDown { rows } => {
+                Interpreter::scroll_down(&mut self.display_screen, rows as usize);
+            }
+            Instruction::ScrollRight => {
+                Interpreter::scroll_right(&mut self.display_screen);
+            }
+            Instruction::ScrollLeft => {
+                Interpreter::scroll_left(&mut self.display_screen);
+            }
+            Instruction::Exit => {
+                self.halted = true;
+            }
+            Instruction::LowResolution => {
+                Interpreter::low_resolution(&mut self.display_screen);
+            }
+            Instruction::HighResolution => {
+                Interpreter::high_resolution(&mut self.display_screen);
+            }
+            Instruction::Jump { address } => {
+                Interpreter::jump_location_address(
+                    &Address::from(address),
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::Call { address } => {
+                Interpreter::call_address(
+                    &mut self.stack,
+                    &mut self.program_counter,
+                    &Address::from(address),
+                )?;
+            }
+            Instruction::SkipIfEqualByte { x, byte } => {
+                Interpreter::skip_if_equal_byte(
+                    &self.registers[usize::from(x)],
+                    byte,
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::SkipIfNotEqualByte { x, byte } => {
+                Interpreter::skip_if_not_equal_byte(
+                    &self.registers[usize::from(x)],
+                    byte,
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::SkipIfEqual { x, y } => {
+                Interpreter::skip_if_register_equal(
+                    &self.registers[usize::from(x)],
+                    &self.registers[usize::from(y)],
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::SaveRange { x, y } => {
+                Interpreter::save_register_range(
+                    &self.registers,
+                    usize::from(x),
+                    usize::from(y),
+                    &self.address_register,
+                    &mut self.memory,
+                )?;
+            }
+            Instruction::LoadRange { x, y } => {
+                Interpreter::load_register_range(
+                    &mut self.registers,
+                    usize::from(x),
+                    usize::from(y),
+                    &self.address_register,
+                    &self.memory,
+                )?;
+            }
+            Instruction::LoadByte { x, byte } => {
+                Interpreter::load_byte(&mut self.registers[usize::from(x)], byte);
+            }
+            Instruction::AddByte { x, byte } => {
+                Interpreter::add(&mut self.registers[usize::from(x)], byte);
+            }
+            Instruction::Load { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                Interpreter::load_register(&mut self.registers[usize::from(x)], &register_y);
+            }
+            Instruction::Or { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                Interpreter::bitwise_or_register(&mut self.registers[usize::from(x)], &register_y);
+                if self.quirks.vf_reset {
+                    self.registers[0xF] = Register::from(0);
+                }
+            }
+            Instruction::And { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                Interpreter::bitwise_and_register(&mut self.registers[usize::from(x)], &register_y);
+                if self.quirks.vf_reset {
+                    self.registers[0xF] = Register::from(0);
+                }
+            }
+            Instruction::Xor { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                Interpreter::bitwise_xor_register(&mut self.registers[usize::from(x)], &register_y);
+                if self.quirks.vf_reset {
+                    self.registers[0xF] = Register::from(0);
+                }
+            }
+            Instruction::Add { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                self.registers[0xF] =
+                    Interpreter::add_carry(&mut self.registers[usize::from(x)], &register_y);
+            }
+            Instruction::Subtract { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                self.registers[0xF] = Interpreter::subtract_register(
+                    &mut self.registers[usize::from(x)],
+                    &register_y,
+                );
+            }
+            Instruction::ShiftRight { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                let register_x = &mut self.registers[usize::from(x)];
+                if !self.quirks.shift {
+                    Interpreter::load_register(register_x, &register_y);
+                }
+                self.registers[0xF] = Interpreter::shift_right(register_x);
+            }
+            Instruction::SubtractReversed { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                self.registers[0xF] = Interpreter::subtract_register_n(
+                    &mut self.registers[usize::from(x)],
+                    &register_y,
+                );
+            }
+            Instruction::ShiftLeft { x, y } => {
+                let register_y = self.registers[usize::from(y)];
+                let register_x = &mut self.registers[usize::from(x)];
+                if !self.quirks.shift {
+                    Interpreter::load_register(register_x, &register_y);
+                }
+                self.registers[0xF] = Interpreter::shift_left(register_x);
+            }
+            Instruction::SkipIfNotEqual { x, y } => {
+                Interpreter::skip_if_register_not_equal(
+                    &self.registers[usize::from(x)],
+                    &self.registers[usize::from(y)],
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::LoadAddress { address } => {
+                Interpreter::set_i_address(&mut self.address_register, &Address::from(address));
+            }
+            Instruction::JumpOffset { address } => {
+                let register = if self.quirks.jump {
+                    usize::from(address >> 8)
+                } else {
+                    0x0
+                };
+                Interpreter::jump_location_address_register(
+                    &self.registers[register],
+                    &Address::from(address),
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::Random { x, byte } => {
+                Interpreter::random_and(
+                    &mut self.registers[usize::from(x)],
+                    byte,
+                    &mut self.rng,
+                    &self.memory.bytes()[..0x100],
+                );
+            }
+            Instruction::Draw { rows: 0, .. } if self.platform == Platform::Chip8 => {
+                // without SUPER-CHIP's 16x16 sprites there is nothing to draw
+                self.registers[0xF] = Register::from(0);
+                self.waiting_for_display = self.quirks.display_wait;
+            }
+            Instruction::Draw { x, y, rows } => {
+                let register_x = self.registers[usize::from(x)];
+                let register_y = self.registers[usize::from(y)];
+                self.registers[0xF] = Interpreter::display(
+                    &register_x,
+                    &register_y,
+                    rows,
+                    &self.address_register,
+                    &self.memory,
+                    &mut self.display_screen,
+                    self.quirks.clipping,
+                )?;
+                self.waiting_for_display = self.quirks.display_wait;
+            }
+            Instruction::SkipIfKey { x } => {
+                Interpreter::skip_if_key(
+                    &self.registers[usize::from(x)],
+                    &mut self.program_counter,
+                    self.keyboard_device.as_ref(),
+                );
+            }
+            Instruction::SkipIfNotKey { x } => {
+                Interpreter::skip_if_not_key(
+                    &self.registers[usize::from(x)],
+                    &mut self.program_counter,
+                    self.keyboard_device.as_ref(),
+                );
+            }
+            Instruction::LoadLongAddress => {
+                Interpreter::load_long_address(
+                    &mut self.address_register,
+                    &mut self.program_counter,
+                    &self.memory,
+                )?;
+            }
+            Instruction::SelectPlanes { planes } => {
+                Interpreter::select_planes(&mut self.display_screen, planes);
+            }
+            Instruction::LoadAudioPattern => {
+                Interpreter::load_audio_pattern(
+                    &mut self.audio_pattern,
+                    &self.address_register,
+                    &self.memory,
+                )?;
+                self.buzzer
+                    .set_pattern(&self.audio_pattern, audio::playback_rate(self.pitch));
+            }
+            Instruction::LoadDelayTimer { x } => {
+                Interpreter::load_delay_timer(
+                    &mut self.registers[usize::from(x)],
+                    &self.delay_timer,
+                );
+            }
+            Instruction::WaitForKey { x } => {
+                Interpreter::load_on_key(
+                    &mut self.registers[usize::from(x)],
+                    self.keyboard_device.as_ref(),
+                    &mut self.program_counter,
+                );
+            }
+            Instruction::SetDelayTimer { x } => {
+                Interpreter::store_delay_timer(
+                    &mut self.delay_timer,
+                    &self.registers[usize::from(x)],
+                );
+            }
+            Instruction::SetSoundTimer { x } => {
+                Interpreter::store_sound_timer(
+                    &mut self.sound_timer,
+                    &self.registers[usize::from(x)],
+                    self.buzzer.as_ref(),
+                );
+            }
+            Instruction::AddAddress { x } => {
+                Interpreter::add_address(
+                    &mut self.address_register,
+                    &self.registers[usize::from(x)],
+                );
+            }
+            Instruction::LoadFont { x } => {
+                Interpreter::set_i_sprite(
+                    &mut self.address_register,
+                    &self.registers[usize::from(x)],
+                );
+            }
+            Instruction::LoadLargeFont { x } => {
+                Interpreter::set_i_large_sprite(
+                    &mut self.address_register,
+                    &self.registers[usize::from(x)],
+                );
+            }
+            Instruction::StoreBcd { x } => {
+                Interpreter::load_bcd(
+                    &self.registers[usize::from(x)],
+                    &self.address_register,
+                    &mut self.memory,
+                )?;
+            }
+            Instruction::SetPitch { x } => {
+                Interpreter::set_pitch(&mut self.pitch, &self.registers[usize::from(x)]);
+                self.buzzer
+                    .set_pattern(&self.audio_pattern, audio::playback_rate(self.pitch));
+            }
+            Instruction::StoreRegisters { x } => {
+                let vx = usize::from(x);
+                Interpreter::load_range(
+                    &self.registers[0..=vx],
+                    &self.address_register,
+                    &mut self.memory,
+                )?;
+                self.increment_address_register(x);
+            }
+            Instruction::LoadRegisters { x } => {
+                let vx = usize::from(x);
+                Interpreter::load_range_registers(
+                    &mut self.registers[0..=vx],
+                    &self.address_register,
+                    &self.memory,
+                )?;
+                self.increment_address_register(x);
+            }
+            Instruction::SaveFlags { x } => {
+                Interpreter::save_flags(&self.registers[0..=usize::from(x)], &mut self.rpl_flags);
+            }
+            Instruction::LoadFlags { x } => {
+                Interpreter::load_flags(&mut self.registers[0..=usize::from(x)], &self.rpl_flags);
+            }
+        }
+
+        if self.platform == Platform::XoChip && instruction.is_skip() {
+            self.skip_long_instruction(next_address);
+        }
+
+        if self.sound_timer.get() == 0 {
+            self.buzzer.pause();
+        }
+        Ok(())
+    }
+
+    /// Finishes a skip over the instruction at `skipped` that landed in the
+    /// middle of a four byte F000 NNNN by stepping over its second half, as
+    /// XO-CHIP requires.
+    fn skip_long_instruction(&mut self, skipped: Address) {
+        let skipped = ProgramCounter::from(&skipped);
+        let mut next = ProgramCounter::from(&skipped.value);
+        next.increment();
+        // read the skipped instruction without setting off a watchpoint
+        let start = usize::from(&skipped);
+        let long = self.memory.bytes().get(start..start + 2) == Some(&[0xF0, 0x00][..]);
+        if self.program_counter.value == next.value && long {
+            self.program_counter.increment();
+        }
+    }
+
+    // 0nnn - SYS addr
+    // Jump to a machine code routine at nnn.
+    #[allow(dead_code)]
+    fn jump_address(self, _: &Address) {}
+
+    // 00E0 - CLS
+    // Clear the display
+    fn clear(display: &mut Box<dyn Display>) {
+        display.clear();
+    }
+
+    // 00Cn - SCD nibble
+    // Scroll the display down n pixels. (SUPER-CHIP)
+    fn scroll_down(display: &mut Box<dyn Display>, rows: usize) {
+        display.scroll_down(rows);
+    }
+
+    // 00FB - SCR
+    // Scroll the display right 4 pixels. (SUPER-CHIP)
+    fn scroll_right(display: &mut Box<dyn Display>) {
+        display.scroll_right(4);
+    }
+
+    // 00FC - SCL
+    // Scroll the display left 4 pixels. (SUPER-CHIP)
+    fn scroll_left(display: &mut Box<dyn Display>) {
+        display.scroll_left(4);
+    }
+
+    // 00FE - LOW
+    // Switch to the 64x32 display. (SUPER-CHIP)
+    fn low_resolution(display: &mut Box<dyn Display>) {
+        display.set_high_resolution(false);
+    }
+
+    // 00FF - HIGH
+    // Switch to the 128x64 display. (SUPER-CHIP)
+    fn high_resolution(display: &mut Box<dyn Display>) {
+        display.set_high_resolution(true);
+    }
+
+    // FN01 - PLANE n
+    // Select the bit planes that drawing, clearing and scrolling affect. (XO-CHIP)
+    fn select_planes(display: &mut Box<dyn Display>, planes: u8) {
+        display.select_planes(planes);
+    }
+
+    // 00EE - RET
+    // Return from a subroutine
+    fn return_subroutine(
+        program_counter: &mut ProgramCounter,
+        stack: &mut Stack,
+    ) -> Result<(), Chip8Error> {
+        program_counter.set(stack.pop().ok_or(Chip8Error::StackUnderflow)?);
+        Ok(())
+    }
+
+    // 1nnn - JP addr
+    // Jump to location nnn.
+    fn jump_location_address(address: &Address, program_counter: &mut ProgramCounter) {
+        program_counter.set(*address);
+    }
+
+    // 2nnn - CALL addr
+    // Call subroutine at nnn.
+    fn call_address(
+        stack: &mut Stack,
+        program_counter: &mut ProgramCounter,
+        address: &Address,
+    ) -> Result<(), Chip8Error> {
+        let program_counter_address: Address = program_counter.value;
+        stack.push(&program_counter_address)?;
+        program_counter.set(*address);
+        Ok(())
+    }
+
+    // 3xkk - SE Vx, byte
+    // Skip next instruction if Vx = kk
+    fn skip_if_equal_byte(vx: &Register, byte: u8, program_counter: &mut ProgramCounter) {
+        if vx == &byte {
+            program_counter.increment();
+        }
+    }
+
+    // 4xkk - SNE Vx, byte
+    // Skip next instruction if Vx != kk
+    fn skip_if_not_equal_byte(vx: &Register, byte: u8, program_counter: &mut ProgramCounter) {
+        if vx != &byte {
+            program_counter.increment();
+        }
+    }
+
+    // 5xy0 - SE Vx, Vy
+    // Skip next instruction if Vx = Vy
+    fn skip_if_register_equal(vx: &Register, vy: &Register, program_counter: &mut ProgramCounter) {
+        if vx == vy {
+            program_counter.increment();
+        }
+    }
+
+    // 5xy2 - SAVE Vx - Vy
+    // Store registers Vx through Vy in memory starting at location I. (XO-CHIP)
+    fn save_register_range(
+        registers: &[Register],
+        x: usize,
+        y: usize,
+        i: &AddressRegister,
+        memory: &mut memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        let bytes: Vec<u8> = if x <= y {
+            registers[x..=y]
+                .iter()
+                .map(|register| u8::from(*register))
+                .collect()
+        } else {
+            registers[y..=x]
+                .iter()
+                .rev()
+                .map(|register| u8::from(*register))
+                .collect()
+        };
+        memory.store(i, &bytes[..])
+    }
+
+    // 5xy3 - LOAD Vx - Vy
+    // Read registers Vx through Vy from memory starting at location I. (XO-CHIP)
+    fn load_register_range(
+        registers: &mut [Register],
+        x: usize,
+        y: usize,
+        i: &AddressRegister,
+        memory: &memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        let bytes = memory.load(i, (x.max(y) - x.min(y) + 1) as u16)?;
+        for (offset, byte) in bytes.iter().enumerate() {
+            let register = if x <= y { x + offset } else { x - offset };
+            registers[register] = Register::from(*byte);
+        }
+        Ok(())
+    }
+
+    // 6xkk - LD Vx, byte
+    // Set Vx = kk
+    fn load_byte(vx: &mut Register, byte: u8) {
+        //*vx = Register::from(byte);
+        vx.set(byte);
+    }
+
+    // 7xkk - ADD Vx, byte
+    // Set Vx = Vx + kk
+    fn add(vx: &mut Register, byte: u8) {
+        *vx += byte;
+    }
+
+    // 8xy0 - LD Vx, Vy
+    // Set Vx = Vy
+    fn load_register(vx: &mut Register, vy: &Register) {
+        *vx = *vy;
+    }
+
+    // 8xy1 - OR Vx, Vy
+    // Set Vx = Vx OR Vy
+    fn bitwise_or_register(vx: &mut Register, vy: &Register) {
+        *vx |= *vy;
+    }
+
+    // 8xy2 - AND Vx, Vy
+    // Set Vx = Vx AND Vy
+    fn bitwise_and_register(vx: &mut Register, vy: &Register) {
+        *vx &= *vy;
+    }
+
+    // 8xy3 - XOR Vx, Vy
+    // Set Vx = Vx XOR Vy
+    fn bitwise_xor_register(vx: &mut Register, vy: &Register) {
+        *vx ^= *vy;
+    }
+
+    // 8xy4 - ADD Vx, Vy
+    // Set Vx = Vx + Vy, set VF = carry
+    fn add_carry(vx: &mut Register, vy: &Register) -> Register {
+        let temp: u16 = u16::from(*vx) + u16::from(*vy);
+        let result = if temp > u8::MAX as u16 {
+            Register::from(1)
+        } else {
+            Register::from(0)
+        };
+        *vx = Register::from((temp & (u8::MAX as u16)) as u8);
+        result
+    }
+
+    // 8xy5 - SUB Vx, Vy
+    // Set Vx = Vx - Vy, set VF = NOT borrow
+    fn subtract_register(vx: &mut Register, vy: &Register) -> Register {
+        let result = if *vx >= *vy {
+            Register::from(1)
+        } else {
+            Register::from(0)
+        };
+        *vx -= *vy;
+        result
+    }
+
+    // 8xy6 - SHR Vx {, Vy}
+    // Set Vx = Vx SHR 1
+    fn shift_right(vx: &mut Register) -> Register {
+        let result = if (*vx & 0b00000001) == 1 {
+            Register::from(1)
+        } else {
+            Register::from(0)
+        };
+        *vx >>= 1;
+        result
+    }
+
+    // 8xy7 - SUBN Vx, Vy
+    // Set Vx = Vy - Vx, set VF = NOT borrow.
+    fn subtract_register_n(vx: &mut Register, vy: &Register) -> Register {
+        let result = if *vy >= *vx {
+            Register::from(1)
+        } else {
+            Register::from(0)
+        };
+        //*vx = *vy - *vx;
+        vx.set((*vy - *vx).into());
+        result
+    }
+
+    // 8xyE - SHL Vx {, Vy}
+    // Set Vx = Vx SHL 1.
+    fn shift_left(vx: &mut Register) -> Register {
+        let result = if (*vx & 0b10000000) == 0b10000000 {
+            Register::from(1)
+        } else {
+            Register::from(0)
+        };
+        *vx <<= 1;
+        result
+    }
+
+    // 9xy0 - SNE Vx, Vy
+    // Skip next instruction if Vx != Vy.
+    fn skip_if_register_not_equal(
+        vx: &Register,
+        vy: &Register,
+        program_counter: &mut ProgramCounter,
+    ) {
+        if *vx != *vy {
+            program_counter.increment();
+        }
+    }
+
+    // Annn - LD I, addr
+    // Set I = nnn.
+    fn set_i_address(i: &mut AddressRegister, address: &Address) {
+        i.set(*address);
+    }
+
+    // Bnnn - JP V0, addr
+    // Jump to location nnn + V0.
+    fn jump_location_address_register(
+        v0: &Register,
+        address: &Address,
+        program_counter: &mut ProgramCounter,
+    ) {
+        *program_counter = ProgramCounter {
+            value: *address + *v0,
+        };
+    }
+
+    // Cxkk - RND Vx, byte
+    // Set Vx = random byte AND kk.
+    fn random_and(vx: &mut Register, byte: u8, rng: &mut Rng, page: &[u8]) {
+        let random_value: u8 = rng.next_byte(page);
+        *vx = Register::from(byte & random_value);
+    }
+
+    // Dxyn - DRW Vx, Vy, nibble
+    // Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
+    // Dxy0 - DRW Vx, Vy, 0
+    // Display 16x16 sprite starting at memory location I at (Vx, Vy), set VF = collision. (SUPER-CHIP)
+    fn display(
+        vx: &Register,
+        vy: &Register,
+        number_of_bytes: u8,
+        address: &AddressRegister,
+        memory: &memory::Memory,
+        display: &mut Box<dyn Display>,
+        clip: bool,
+    ) -> Result<Register, Chip8Error> {
+        let planes = display.frame_buffer().plane_count() as u16;
+        let sprite = if number_of_bytes == 0 {
+            graphics::Sprite::wide(memory.load(address, 32 * planes)?)
+        } else {
+            graphics::Sprite::from(memory.load(address, number_of_bytes as u16 * planes)?)
+        };
+        let collision = display.draw(u8::from(*vx), u8::from(*vy), &sprite, clip);
+        Ok(Register::from(collision))
+    }
+
+    // Ex9E - SKP Vx
+    // Skip next instruction if key with the value of Vx is pressed.
+    fn skip_if_key(vx: &Register, program_counter: &mut ProgramCounter, keyboard: &dyn Keyboard) {
+        if keyboard.is_key_down(u8::from(*vx) & 0xF) {
+            program_counter.increment();
+        }
+    }
+
+    // ExA1 - SKNP Vx
+    // Skip next instruction if key with the value of Vx is not pressed.
+    fn skip_if_not_key(
+        vx: &Register,
+        program_counter: &mut ProgramCounter,
+        keyboard: &dyn Keyboard,
+    ) {
+        if !keyboard.is_key_down(u8::from(*vx) & 0xF) {
+            program_counter.increment();
+        }
+    }
+
+    // Fx07 - LD Vx, DT
+    // Set Vx = delay timer value.
+    fn load_delay_timer(vx: &mut Register, delay_timer: &DelayTimer) {
+        *vx = Register::from(*delay_timer);
+    }
+
+    // Fx0A - LD Vx, K
+    // Wait for a key press, store the value of the key in Vx.
+    fn load_on_key(
+        vx: &mut Register,
+        keyboard: &dyn Keyboard,
+        program_counter: &mut ProgramCounter,
+    ) {
+        match keyboard.get_pressed_key() {
+            Some(k) => *vx = Register::from(k),
+            _ => program_counter.decrement(),
+        }
+    }
+
+    // Fx15 - LD DT, Vx
+    // Set delay timer = Vx.
+    fn store_delay_timer(delay_timer: &mut DelayTimer, vx: &Register) {
+        *delay_timer = DelayTimer::from(*vx);
+    }
+
+    // Fx18 - LD ST, Vx
+    // Set sound timer = Vx.
+    fn store_sound_timer(sound_timer: &mut SoundTimer, vx: &Register, buzzer: &dyn Buzzer) {
+        sound_timer.set(u8::from(*vx));
+        buzzer.play();
+    }
+    // Fx1E - ADD I, Vx
+    // Set I = I + Vx.
+    fn add_address(i: &mut AddressRegister, vx: &Register) {
+        *i += *vx;
+    }
+
+    // Fx29 - LD F, Vx
+    // Set I = location of sprite for digit Vx.
+    fn set_i_sprite(i: &mut AddressRegister, vx: &Register) {
+        i.set(Address::from(5 * u16::from(*vx)));
+    }
+
+    // Fx30 - LD HF, Vx
+    // Set I = location of large sprite for digit Vx. (SUPER-CHIP)
+    fn set_i_large_sprite(i: &mut AddressRegister, vx: &Register) {
+        i.set(Address::from(
+            memory::LARGE_FONT_ADDRESS + 10 * u16::from(*vx),
+        ));
+    }
+
+    // F000 nnnn - LD I, long nnnn
+    // Set I = the 16 bit address in the next two bytes, and skip them. (XO-CHIP)
+    fn load_long_address(
+        i: &mut AddressRegister,
+        program_counter: &mut ProgramCounter,
+        memory: &memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        let mut address = &memory.fetch(program_counter)?[..];
+        i.set(Address::from(address.read_u16::<BigEndian>().unwrap()));
+        program_counter.increment();
+        Ok(())
+    }
+
+    // F002 - AUDIO
+    // Load the 16 byte audio pattern from memory starting at location I. (XO-CHIP)
+    fn load_audio_pattern(
+        pattern: &mut [u8; 16],
+        i: &AddressRegister,
+        memory: &memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        pattern.clone_from_slice(memory.load(i, 16)?);
+        Ok(())
+    }
+
+    // Fx3A - PITCH Vx
+    // Set the audio pattern playback rate from Vx. (XO-CHIP)
+    fn set_pitch(pitch: &mut u8, vx: &Register) {
+        *pitch = u8::from(*vx);
+    }
+
+    // Fx33 - LD B, Vx
+    // Store BCD representation of Vx in memory locations I, I+1, and I+2.
+    fn load_bcd(
+        vx: &Register,
+        i: &AddressRegister,
+        memory: &mut memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        memory.store(
+            i,
+            &[
+                (u8::from(*vx) / 100) % 10,
+                (u8::from(*vx) / 10) % 10,
+                u8::from(*vx) % 10,
+            ],
+        )
+    }
+
+    /// Moves I on after Fx55 or Fx65 stored or loaded V0 to Vx, as far as
+    /// the quirks say.
+    fn increment_address_register(&mut self, x: u8) {
+        if self.quirks.memory_increment {
+            let increment = match self.quirks.memory_increment_by_x {
+                true => x,
+                false => x + 1,
+            };
+            self.address_register += Register::from(increment);
+        }
+    }
+
+    // Fx55 - LD [I], Vx
+    // Store registers V0 through Vx in memory starting at location I.
+    fn load_range(
+        registers: &[Register],
+        i: &AddressRegister,
+        memory: &mut memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        let mut bytes = Vec::new();
+        for register in registers {
+            bytes.push(u8::from(*register));
+        }
+        memory.store(i, &bytes[..])
+    }
+
+    // Fx65 - LD Vx, [I]
+    // Read registers V0 through Vx from memory starting at location I.
+    fn load_range_registers(
+        registers: &mut [Register],
+        i: &AddressRegister,
+        memory: &memory::Memory,
+    ) -> Result<(), Chip8Error> {
+        let bytes = memory.load(i, registers.len() as u16)?;
+        for (index, byte) in bytes.iter().enumerate() {
+            let x = &mut registers[index];
+            *x = Register::from(*byte);
+        }
+        Ok(())
+    }
+
+    // Fx75 - LD R, Vx
+    // Store registers V0 through Vx in the RPL user flags. (SUPER-CHIP)
+    fn save_flags(registers: &[Register], flags: &mut [u8; 16]) {
+        for (flag, register) in flags.iter_mut().zip(registers) {
+            *flag = u8::from(*register);
+        }
+    }
+
+    // Fx85 - LD Vx, R
+    // Read registers V0 through Vx from the RPL user flags. (SUPER-CHIP)
+    fn load_flags(registers: &mut [Register], flags: &[u8; 16]) {
+        for (register, flag) in registers.iter_mut().zip(flags) {
+            *register = Register::from(*flag);
+        }
+    }
+}
+
+impl MachineState for Interpreter {
+    fn register(&self, x: u8) -> u8 {
+        Interpreter::register(self, x)
+    }
+
+    fn address_register(&self) -> u16 {
+        Interpreter::address_register(self)
+    }
+
+    fn program_counter(&self) -> u16 {
+        Interpreter::program_counter(self)
+    }
+
+    fn delay_timer(&self) -> u8 {
+        Interpreter::delay_timer(self)
+    }
+
+    fn sound_timer(&self) -> u8 {
+        Interpreter::sound_timer(self)
+    }
+
+    fn stack_pointer(&self) -> u8 {
+        self.stack.stack_pointer()
+    }
+
+    fn memory_byte(&self, address: u16) -> Option<u8> {
+        self.memory.bytes().get(usize::from(address)).copied()
+    }
+
+    fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    fn describe_address(&self, address: u16) -> String {
+        self.symbols.describe(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio::Buzzer;
+    use crate::audio::NullBuzzer;
+    use crate::chip8::Interpreter;
+    use crate::clock::ManualClock;
+    use crate::debugger::Breakpoint;
+    use crate::debugger::MemoryAccess;
+    use crate::debugger::PauseReason;
+    use crate::debugger::WatchKind;
+    use crate::debugger::Watchpoint;
+    use crate::error::Chip8Error;
+    use crate::expression::Expression;
+    use crate::expression::Template;
+    use crate::graphics::Display;
+    use crate::graphics::FrameBuffer;
+    use crate::graphics::HIGH_RESOLUTION_HEIGHT;
+    use crate::graphics::HIGH_RESOLUTION_WIDTH;
+    use crate::instruction::Instruction;
+    use crate::keyboard::Keyboard;
+    use crate::memory::Memory;
+    use crate::memory::Stack;
+    use crate::movie::Movie;
+    use crate::movie::PlaybackKeyboard;
+    use crate::movie::RecordingKeyboard;
+    use crate::platform::Platform;
+    use crate::quirks::Quirks;
+    use crate::registers::Address;
+    use crate::registers::AddressRegister;
+    use crate::registers::DelayTimer;
+    use crate::registers::ProgramCounter;
+    use crate::registers::Register;
+    use crate::registers::SoundTimer;
+    use crate::rewind::RewindBuffer;
+    use crate::rng::Rng;
+    use crate::rng::RngMode;
+    use crate::snapshot::Snapshot;
+    use crate::snapshot::SNAPSHOT_VERSION;
+    use crate::symbols::Symbols;
+    use byteorder::BigEndian;
+    use byteorder::ReadBytesExt;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn memory_fetch() {
+        let mut program_counter = ProgramCounter::new();
+        program_counter.set(Address::from(0x0000));
+        let memory = Memory::new();
+        let instruction_code = memory.fetch(&program_counter).unwrap();
+        let mut instruction_code = &instruction_code[..];
+        let instruction_code = instruction_code.read_u16::<BigEndian>().unwrap();
+        assert_eq!(instruction_code, 0xF090);
+    }
+
+    #[test]
+    fn stack_push() {
+        let mut stack = Stack::new();
+        assert_eq!(stack.peek(), None);
+        stack.push(&Address::from(0x200)).unwrap();
+        assert_eq!(stack.peek(), Some(Address::from(0x200)));
+    }
+    #[test]
+    fn stack_pointer_pop() {
+        let mut stack = Stack::new();
+        stack.push(&Address::from(0x200)).unwrap();
+        assert_eq!(stack.peek(), Some(Address::from(0x200)));
+        stack.pop();
+        assert_eq!(stack.peek(), None);
+    }
+
+    #[test]
+    fn test_return_subroutine() {
+        let mut program_counter = ProgramCounter::new();
+        assert_eq!(program_counter.value, Address::from(0x200));
+        let mut stack = Stack::new();
+        stack.push(&Address::from(0xAAAA)).unwrap();
+        Interpreter::return_subroutine(&mut program_counter, &mut stack).unwrap();
+        assert_eq!(program_counter.value, Address::from(0xAAAA));
+    }
+    #[test]
+    fn test_jump_location_address() {
+        let mut program_counter = ProgramCounter::new();
+        let address = Address::from(0xBBBB);
+        assert_eq!(program_counter.value, Address::from(0x200));
+        Interpreter::jump_location_address(&address, &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0xBBBB));
+    }
+    #[test]
+    fn test_call_address() {
+        let mut stack = Stack::new();
+        let mut program_counter = ProgramCounter::new();
+        let address = Address::from(0xCCCC);
+        assert_eq!(program_counter.value, Address::from(0x200));
+        program_counter.set(Address::from(0xDDDD));
+        assert_eq!(stack.peek(), None);
+        Interpreter::call_address(&mut stack, &mut program_counter, &address).unwrap();
+        assert_eq!(stack.peek(), Some(Address::from(0xDDDD)));
+        assert_eq!(program_counter.value, Address::from(0xCCCC));
+    }
+    #[test]
+    fn test_skip_if_equal_byte() {
+        let vx = Register::from(0xAB);
+        let byte = 0xAB;
+        let mut program_counter = ProgramCounter::new();
+        assert_eq!(program_counter.value, Address::from(0x200));
+        Interpreter::skip_if_equal_byte(&vx, byte, &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0x202));
+    }
+    #[test]
+    fn test_skip_if_not_equal_byte() {
+        let vx = Register::from(0xAB);
+        let byte = 0xAA;
+        let mut program_counter = ProgramCounter::new();
+        assert_eq!(program_counter.value, Address::from(0x200));
+        Interpreter::skip_if_not_equal_byte(&vx, byte, &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0x202));
+    }
+    #[test]
+    fn test_skip_if_register_equal() {
+        let vx = Register::from(0xAA);
+        let vy = Register::from(0xAA);
+        let mut program_counter = ProgramCounter::new();
+        assert_eq!(program_counter.value, Address::from(0x200));
+        Interpreter::skip_if_register_equal(&vx, &vy, &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0x202));
+    }
+    #[test]
+    fn test_load_byte() {
+        let mut vx = Register::from(0);
+        let byte = 0xEE;
+        Interpreter::load_byte(&mut vx, byte);
+        assert_eq!(vx, byte);
+    }
+    #[test]
+    fn test_add() {
+        let mut vx = Register::from(0);
+        let byte = 5;
+        Interpreter::add(&mut vx, byte);
+        assert_eq!(vx, 5);
+    }
+    #[test]
+    fn test_load_register() {
+        let mut vx = Register::from(0);
+        let vy = Register::from(5);
+        Interpreter::load_register(&mut vx, &vy);
+        assert_eq!(vx, 5);
+    }
+    #[test]
+    fn test_bitwise_or_register() {
+        let mut vx = Register::from(0b11110000);
+        let vy = Register::from(0b00001111);
+        Interpreter::bitwise_or_register(&mut vx, &vy);
+        assert_eq!(vx, 0b11111111);
+    }
+    #[test]
+    fn test_bitwise_and_register() {
+        let mut vx = Register::from(0b11111110);
+        let vy = Register::from(0b10000001);
+        Interpreter::bitwise_and_register(&mut vx, &vy);
+        assert_eq!(vx, 0b10000000);
+    }
+    #[test]
+    fn test_bitwise_xor_register() {
+        let mut vx = Register::from(0b11110000);
+        let vy = Register::from(0b00001111);
+        Interpreter::bitwise_xor_register(&mut vx, &vy);
+        assert_eq!(vx, 0b11111111);
+    }
+    #[test]
+    fn test_add_carry() {
+        let mut vx = Register::from(0xFF);
+        let vy = Register::from(0x01);
+        assert_eq!(Interpreter::add_carry(&mut vx, &vy), 1);
+    }
+    #[test]
+    fn test_subtract_register() {
+        let mut vx = Register::from(10);
+        let vy = Register::from(9);
+        assert_eq!(Interpreter::subtract_register(&mut vx, &vy), 1);
+    }
+    #[test]
+    fn test_shift_right() {
+        let mut vx = Register::from(4);
+        Interpreter::shift_right(&mut vx);
+        assert_eq!(vx, 2);
+    }
+    #[test]
+    fn test_subtract_register_n() {
+        let mut vx = Register::from(10);
+        let vy = Register::from(9);
+        assert_eq!(Interpreter::subtract_register_n(&mut vx, &vy), 0);
+    }
+    #[test]
+    fn test_shift_left() {
+        let mut vx = Register::from(2);
+        Interpreter::shift_left(&mut vx);
+        assert_eq!(vx, 4);
+    }
+    #[test]
+    fn test_skip_if_register_not_equal() {
+        let vx = Register::from(0);
+        let vy = Register::from(1);
+        let mut program_counter = ProgramCounter::new();
+        assert_eq!(program_counter.value, Address::from(0x200));
+        Interpreter::skip_if_register_not_equal(&vx, &vy, &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0x202));
+    }
+    #[test]
+    fn test_set_i_address() {
+        let mut i = AddressRegister::new();
+        let mut test_i = AddressRegister::new();
+        test_i.set(Address::from(0));
+        assert_eq!(i, test_i);
+        Interpreter::set_i_address(&mut i, &Address::from(0xBBBB));
+        test_i.set(Address::from(0xBBBB));
+        assert_eq!(i, test_i);
+    }
+    #[test]
+    fn test_jump_location_address_register() {
+        let v0 = Register::from(0xF1);
+        let address = Address::from(0x400);
+        let mut program_counter = ProgramCounter::new();
+        Interpreter::jump_location_address_register(&v0, &address, &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0x4F1));
+    }
+    #[test]
+    fn test_random_and() {
+        let mut vx = Register::from(0xCD);
+        Interpreter::random_and(&mut vx, 0xA1, &mut Rng::from_seed(1), &[]);
+        assert_ne!(vx, 0xCD);
+    }
+
+    struct TestKeyboard {
+        keys: [bool; 16],
+    }
+
+    impl TestKeyboard {
+        fn new() -> Self {
+            Self { keys: [false; 16] }
+        }
+    }
+
+    impl Keyboard for TestKeyboard {
+        fn is_key_down(&self, key: u8) -> bool {
+            self.keys[usize::from(key)]
+        }
+
+        fn get_pressed_key(&self) -> Option<u8> {
+            for (i, key) in self.keys.iter().enumerate() {
+                if *key {
+                    return Some(i as u8);
+                }
+            }
+            None
+        }
+    }
+    #[test]
+    fn test_skip_if_key() {
+        let vx = Register::from(15);
+        let mut program_counter = ProgramCounter::new();
+        let mut keyboard = Box::new(TestKeyboard::new());
+        let value: u8 = vx.into();
+        keyboard.keys[usize::from(value)] = true;
+        let keyboard: Box<dyn Keyboard> = keyboard;
+        Interpreter::skip_if_key(&vx, &mut program_counter, keyboard.as_ref());
+        assert_eq!(program_counter.value, Address::from(0x202));
+    }
+    #[test]
+    fn test_skip_if_not_key() {
+        let vx = Register::from(15);
+        let mut program_counter = ProgramCounter::new();
+        let keyboard: Box<dyn Keyboard> = Box::new(TestKeyboard::new());
+        Interpreter::skip_if_not_key(&vx, &mut program_counter, keyboard.as_ref());
+        assert_eq!(program_counter.value, Address::from(0x202));
+    }
+    #[test]
+    fn test_load_delay_timer() {
+        let mut vx = Register::from(66);
+        let delay_timer = DelayTimer::new();
+        Interpreter::load_delay_timer(&mut vx, &delay_timer);
+        let value: u8 = vx.into();
+        assert_eq!(value, delay_timer.get());
+    }
+
+    #[test]
+    fn test_load_on_key() {
+        let mut vx = Register::from(0);
+        let mut keyboard = TestKeyboard::new();
+        keyboard.keys[7] = true;
+        let mut program_counter = ProgramCounter::new();
+        let keyboard: Box<dyn Keyboard> = Box::new(keyboard);
+        Interpreter::load_on_key(&mut vx, keyboard.as_ref(), &mut program_counter);
+        assert_eq!(program_counter.value, Address::from(0x200));
+        assert_eq!(vx, 7);
+    }
+    #[test]
+    fn test_set_delay_timer() {
+        let mut delay_timer = DelayTimer::new();
+        let vx = Register::from(6);
+        Interpreter::store_delay_timer(&mut delay_timer, &vx);
+        assert_eq!(delay_timer.get(), 6);
+    }
+
+    struct TestDisplay {
+        buffer: FrameBuffer,
+    }
+
+    impl Display for TestDisplay {
+        fn frame_buffer(&self) -> &FrameBuffer {
+            &self.buffer
+        }
+
+        fn frame_buffer_mut(&mut self) -> &mut FrameBuffer {
+            &mut self.buffer
+        }
+
+        fn refresh(&mut self) {}
+    }
+
+    fn test_display() -> Box<TestDisplay> {
+        Box::new(TestDisplay {
+            buffer: FrameBuffer::new(),
+        })
+    }
+
+    fn test_interpreter(rom: &[u8]) -> Interpreter {
+        Interpreter::new(
+            test_display(),
+            Box::new(NullBuzzer),
+            Box::new(TestKeyboard::new()),
+            rom,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_run_iteration_invalid_opcode() {
+        let mut interpreter = test_interpreter(&[0x60, 0x01, 0x8A, 0xB8]);
+        assert_eq!(interpreter.run_iteration(), Ok(()));
+        assert_eq!(
+            interpreter.run_iteration(),
+            Err(Chip8Error::InvalidOpcode {
+                opcode: 0x8AB8,
+                address: Address::from(0x202)
+            })
+        );
+        // only 9xy0 is SNE
+        let mut interpreter = test_interpreter(&[0x9A, 0xB1]);
+        assert!(matches!(
+            interpreter.run_iteration(),
+            Err(Chip8Error::InvalidOpcode { opcode: 0x9AB1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut interpreter = test_interpreter(&[]);
+        interpreter
+            .execute(&Instruction::LoadByte { x: 0x3, byte: 0x1F })
+            .unwrap();
+        interpreter
+            .execute(&Instruction::Add { x: 0x3, y: 0x3 })
+            .unwrap();
+        assert_eq!(interpreter.registers[0x3], 0x3E);
+        interpreter
+            .execute(&Instruction::Jump { address: 0x300 })
+            .unwrap();
+        assert_eq!(interpreter.program_counter(), 0x300);
+    }
+
+    #[test]
+    fn test_run_iteration_stack_underflow() {
+        let mut interpreter = test_interpreter(&[0x00, 0xEE]);
+        assert_eq!(interpreter.run_iteration(), Err(Chip8Error::StackUnderflow));
+    }
+
+    #[test]
+    fn test_run_iteration_stack_overflow() {
+        // 0x200: CALL 0x200
+        let mut interpreter = test_interpreter(&[0x22, 0x00]);
+        for _ in 0..16 {
+            assert_eq!(interpreter.run_iteration(), Ok(()));
+        }
+        assert_eq!(interpreter.run_iteration(), Err(Chip8Error::StackOverflow));
+    }
+
+    #[test]
+    fn test_new_rom_too_large() {
+        let rom = vec![0; 4096];
+        let result = Interpreter::new(
+            test_display(),
+            Box::new(NullBuzzer),
+            Box::new(TestKeyboard::new()),
+            &rom,
+        );
+        assert!(matches!(result, Err(Chip8Error::RomTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_skip_if_key_uses_low_nibble() {
+        // LD V0, 0x20; SKP V0; JP 0x204
+        let mut interpreter = test_interpreter(&[0x60, 0x20, 0xE0, 0x9E, 0x12, 0x04]);
+        interpreter.set_keyboard(Box::new(PlaybackKeyboard::from_frames(vec![0x0001])));
+        interpreter.set_instructions_per_frame(2);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x206);
+        // LD V0, 0x21; SKNP V0; JP 0x204
+        let mut interpreter = test_interpreter(&[0x60, 0x21, 0xE0, 0xA1, 0x12, 0x04]);
+        interpreter.set_keyboard(Box::new(PlaybackKeyboard::from_frames(vec![0x0002])));
+        interpreter.set_instructions_per_frame(2);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x204);
+    }
+
+    fn run_with_quirks(rom: &[u8], quirks: Quirks, instructions: usize) -> Interpreter {
+        let mut interpreter = test_interpreter(rom);
+        interpreter.set_quirks(quirks);
+        for _ in 0..instructions {
+            interpreter.run_iteration().unwrap();
+        }
+        interpreter
+    }
+
+    #[test]
+    fn test_shift_quirk() {
+        // LD V1, 0x03; LD V2, 0x10; SHR V1, V2
+        let rom = [0x61, 0x03, 0x62, 0x10, 0x81, 0x26];
+        let interpreter = run_with_quirks(&rom, Quirks::CHIP_48, 3);
+        assert_eq!(interpreter.registers[0x1], 0x01);
+        assert_eq!(interpreter.registers[0xF], 1);
+        let interpreter = run_with_quirks(&rom, Quirks::COSMAC_VIP, 3);
+        assert_eq!(interpreter.registers[0x1], 0x08);
+        assert_eq!(interpreter.registers[0xF], 0);
+    }
+
+    #[test]
+    fn test_vf_reset_quirk() {
+        // LD VF, 0x05; OR V0, V1
+        let rom = [0x6F, 0x05, 0x80, 0x11];
+        let interpreter = run_with_quirks(&rom, Quirks::COSMAC_VIP, 2);
+        assert_eq!(interpreter.registers[0xF], 0);
+        let interpreter = run_with_quirks(&rom, Quirks::SUPER_CHIP, 2);
+        assert_eq!(interpreter.registers[0xF], 5);
+    }
+
+    #[test]
+    fn test_memory_increment_quirk() {
+        // LD I, 0x300; LD [I], V2
+        let rom = [0xA3, 0x00, 0xF2, 0x55];
+        let interpreter = run_with_quirks(&rom, Quirks::OCTO, 2);
+        let mut expected = AddressRegister::new();
+        expected.set(Address::from(0x303));
+        assert_eq!(interpreter.address_register, expected);
+        let interpreter = run_with_quirks(&rom, Quirks::SUPER_CHIP, 2);
+        expected.set(Address::from(0x300));
+        assert_eq!(interpreter.address_register, expected);
+        let interpreter = run_with_quirks(&rom, Quirks::CHIP_48, 2);
+        expected.set(Address::from(0x302));
+        assert_eq!(interpreter.address_register, expected);
+    }
+
+    #[test]
+    fn test_jump_quirk() {
+        // LD V0, 0x01; LD V3, 0x02; JP V0, 0x300
+        let rom = [0x60, 0x01, 0x63, 0x02, 0xB3, 0x00];
+        let interpreter = run_with_quirks(&rom, Quirks::COSMAC_VIP, 3);
+        assert_eq!(interpreter.program_counter.value, Address::from(0x301));
+        let interpreter = run_with_quirks(&rom, Quirks::SUPER_CHIP, 3);
+        assert_eq!(interpreter.program_counter.value, Address::from(0x302));
+    }
+
+    #[test]
+    fn test_display_wait_quirk() {
+        // DRW V0, V0, 1; ADD V1, 1; JP 0x200
+        let rom = [0xD0, 0x01, 0x71, 0x01, 0x12, 0x00];
+        let mut interpreter = test_interpreter(&rom);
+        interpreter.set_instructions_per_frame(10);
+        interpreter.set_quirks(Quirks::COSMAC_VIP);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter.value, Address::from(0x202));
+        assert_eq!(interpreter.registers[0x1], 0);
+        let mut interpreter = test_interpreter(&rom);
+        interpreter.set_instructions_per_frame(10);
+        interpreter.set_quirks(Quirks::OCTO);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.registers[0x1], 3);
+    }
+
+    fn super_chip_interpreter(rom: &[u8]) -> Interpreter {
+        Interpreter::with_platform(
+            test_display(),
+            Box::new(NullBuzzer),
+            Box::new(TestKeyboard::new()),
+            rom,
+            Platform::SuperChip,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_super_chip_high_resolution() {
+        // HIGH; LD I, 0x300; DRW V0, V0, 0; LOW
+        let rom = [0x00, 0xFF, 0xA3, 0x00, 0xD0, 0x00, 0x00, 0xFE];
+        let mut interpreter = super_chip_interpreter(&rom);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x300));
+        interpreter.memory.store(&i, &[0xFF; 32]).unwrap();
+        for _ in 0..3 {
+            interpreter.run_iteration().unwrap();
+        }
+        let buffer = interpreter.display_screen.frame_buffer();
+        assert_eq!(buffer.width(), HIGH_RESOLUTION_WIDTH);
+        assert_eq!(buffer.height(), HIGH_RESOLUTION_HEIGHT);
+        assert!(buffer.pixel(15, 15));
+        assert!(!buffer.pixel(16, 0));
+        assert!(!buffer.pixel(0, 16));
+        interpreter.run_iteration().unwrap();
+        assert!(!interpreter
+            .display_screen
+            .frame_buffer()
+            .is_high_resolution());
+        // plain CHIP-8 stays in low resolution and draws nothing for Dxy0
+        let mut interpreter = test_interpreter(&rom);
+        interpreter.memory.store(&i, &[0xFF; 32]).unwrap();
+        for _ in 0..3 {
+            interpreter.run_iteration().unwrap();
+        }
+        let buffer = interpreter.display_screen.frame_buffer();
+        assert!(!buffer.is_high_resolution());
+        assert!(!buffer.pixel(0, 0));
+    }
+
+    #[test]
+    fn test_super_chip_scroll() {
+        // LD I, 0x300; DRW V0, V0, 1; SCD 2; SCR; SCL
+        let rom = [0xA3, 0x00, 0xD0, 0x01, 0x00, 0xC2, 0x00, 0xFB, 0x00, 0xFC];
+        let mut interpreter = super_chip_interpreter(&rom);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x300));
+        interpreter.memory.store(&i, &[0x80]).unwrap();
+        for _ in 0..3 {
+            interpreter.run_iteration().unwrap();
+        }
+        assert!(interpreter.display_screen.frame_buffer().pixel(0, 2));
+        interpreter.run_iteration().unwrap();
+        assert!(interpreter.display_screen.frame_buffer().pixel(4, 2));
+        interpreter.run_iteration().unwrap();
+        assert!(interpreter.display_screen.frame_buffer().pixel(0, 2));
+    }
+
+    #[test]
+    fn test_super_chip_exit() {
+        // EXIT; LD V0, 0x01
+        let rom = [0x00, 0xFD, 0x60, 0x01];
+        let mut interpreter = super_chip_interpreter(&rom);
+        assert!(!interpreter.is_halted());
+        interpreter.run_frame().unwrap();
+        assert!(interpreter.is_halted());
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.registers[0x0], 0);
+        assert_eq!(interpreter.program_counter.value, Address::from(0x202));
+        // plain CHIP-8 reads 00FD as a SYS call
+        let mut interpreter = test_interpreter(&rom);
+        interpreter.run_frame().unwrap();
+        assert!(!interpreter.is_halted());
+        assert_eq!(interpreter.registers[0x0], 1);
+    }
+
+    #[test]
+    fn test_super_chip_flags() {
+        // LD V0, 0x11; LD V1, 0x22; LD R, V1; LD V0, 0; LD V1, 0; LD V1, R
+        let rom = [
+            0x60, 0x11, 0x61, 0x22, 0xF1, 0x75, 0x60, 0x00, 0x61, 0x00, 0xF1, 0x85,
+        ];
+        let mut interpreter = super_chip_interpreter(&rom);
+        interpreter.set_quirks(Quirks::SUPER_CHIP);
+        for _ in 0..6 {
+            interpreter.run_iteration().unwrap();
+        }
+        assert_eq!(interpreter.registers[0x0], 0x11);
+        assert_eq!(interpreter.registers[0x1], 0x22);
+    }
+
+    #[test]
+    fn test_set_i_large_sprite() {
+        let mut i = AddressRegister::new();
+        Interpreter::set_i_large_sprite(&mut i, &Register::from(2));
+        let mut test_i = AddressRegister::new();
+        test_i.set(Address::from(0x50 + 20));
+        assert_eq!(i, test_i);
+        let memory = Memory::new();
+        assert_eq!(memory.load(&i, 2).unwrap(), &[0xFF, 0xFF]);
+    }
+
+    fn xo_chip_interpreter(rom: &[u8]) -> Interpreter {
+        Interpreter::with_platform(
+            test_display(),
+            Box::new(NullBuzzer),
+            Box::new(TestKeyboard::new()),
+            rom,
+            Platform::XoChip,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_xo_chip_long_load() {
+        // LD I, long 0xF000; LD V0, 0xAA; LD [I], V0
+        let mut rom = vec![0xF0, 0x00, 0xF0, 0x00, 0x60, 0xAA, 0xF0, 0x55];
+        rom.resize(0x2000, 0);
+        let mut interpreter = xo_chip_interpreter(&rom);
+        for _ in 0..3 {
+            interpreter.run_iteration().unwrap();
+        }
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0xF000));
+        assert_eq!(interpreter.address_register, i);
+        assert_eq!(interpreter.memory.load(&i, 1).unwrap(), &[0xAA]);
+    }
+
+    #[test]
+    fn test_xo_chip_skip_long_load() {
+        // SE V0, 0x00; LD I, long 0x1234; LD V1, 0x01
+        let rom = [0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x61, 0x01];
+        let mut interpreter = xo_chip_interpreter(&rom);
+        interpreter.run_iteration().unwrap();
+        assert_eq!(interpreter.program_counter.value, Address::from(0x206));
+        let mut interpreter = test_interpreter(&rom);
+        interpreter.run_iteration().unwrap();
+        assert_eq!(interpreter.program_counter.value, Address::from(0x204));
+    }
+
+    #[test]
+    fn test_xo_chip_register_range() {
+        // LD V1, 1; LD V2, 2; LD V3, 3; LD I, 0x300; SAVE V1 - V3; LOAD V3 - V1
+        let rom = [
+            0x61, 0x01, 0x62, 0x02, 0x63, 0x03, 0xA3, 0x00, 0x51, 0x32, 0x53, 0x13,
+        ];
+        let mut interpreter = xo_chip_interpreter(&rom);
+        for _ in 0..5 {
+            interpreter.run_iteration().unwrap();
+        }
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x300));
+        assert_eq!(interpreter.memory.load(&i, 3).unwrap(), &[1, 2, 3]);
+        interpreter.run_iteration().unwrap();
+        assert_eq!(interpreter.registers[0x1], 3);
+        assert_eq!(interpreter.registers[0x2], 2);
+        assert_eq!(interpreter.registers[0x3], 1);
+        assert_eq!(interpreter.address_register, i);
+    }
+
+    #[test]
+    fn test_xo_chip_planes() {
+        // PLANE 2; LD I, 0x0; DRW V0, V0, 1; PLANE 3; DRW V0, V0, 1
+        let rom = [0xF2, 0x01, 0xA0, 0x00, 0xD0, 0x01, 0xF3, 0x01, 0xD0, 0x01];
+        let mut interpreter = xo_chip_interpreter(&rom);
+        for _ in 0..3 {
+            interpreter.run_iteration().unwrap();
+        }
+        // the first row of the 0 glyph, 0xF0
+        assert_eq!(interpreter.display_screen.frame_buffer().colour(0, 0), 2);
+        for _ in 0..2 {
+            interpreter.run_iteration().unwrap();
+        }
+        // 0xF0 to plane 1 and the second row of the 0 glyph, 0x90, to plane 2
+        let buffer = interpreter.display_screen.frame_buffer();
+        assert_eq!(buffer.colour(0, 0), 1);
+        assert_eq!(buffer.colour(1, 0), 3);
+        assert_eq!(interpreter.registers[0xF], 1);
+    }
+
+    #[test]
+    fn test_xo_chip_audio() {
+        // LD I, 0x0; AUDIO; LD V0, 0x70; PITCH V0
+        let rom = [0xA0, 0x00, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A];
+        let mut interpreter = xo_chip_interpreter(&rom);
+        assert_eq!(interpreter.pitch(), 64);
+        for _ in 0..4 {
+            interpreter.run_iteration().unwrap();
+        }
+        assert_eq!(
+            interpreter.audio_pattern()[0..5],
+            [0xF0, 0x90, 0x90, 0x90, 0xF0]
+        );
+        assert_eq!(interpreter.pitch(), 0x70);
+    }
+
+    #[test]
+    fn test_xo_chip_instructions_need_platform() {
+        for rom in [[0xF0, 0x00], [0x51, 0x22], [0xF1, 0x01], [0xF0, 0x3A]] {
+            let mut interpreter = test_interpreter(&rom);
+            assert!(matches!(
+                interpreter.run_iteration(),
+                Err(Chip8Error::InvalidOpcode { .. })
+            ));
+        }
+        let rom = vec![0; 0x1000];
+        let result = Interpreter::new(
+            test_display(),
+            Box::new(NullBuzzer),
+            Box::new(TestKeyboard::new()),
+            &rom,
+        );
+        assert!(result.is_err());
+        assert!(xo_chip_interpreter(&rom).platform() == Platform::XoChip);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        // LD V0, 0x3C; LD DT, V0; CALL 0x208; JP 0x206; RND V1, 0xFF; DRW V0, V0, 1
+        let rom = [
+            0x60, 0x3C, 0xF0, 0x15, 0x22, 0x08, 0x12, 0x06, 0xC1, 0xFF, 0xD0, 0x01,
+        ];
+        let mut interpreter = test_interpreter(&rom);
+        for _ in 0..3 {
+            interpreter.run_iteration().unwrap();
+        }
+        let snapshot = interpreter.snapshot();
+        let saved = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+        assert_eq!(saved, snapshot);
+        interpreter.run_iteration().unwrap();
+        interpreter.run_iteration().unwrap();
+        let random = interpreter.registers[0x1];
+        let buffer = interpreter.display_screen.frame_buffer().clone();
+
+        let mut restored = test_interpreter(&[]);
+        restored.restore(&saved).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(restored.delay_timer(), 0x3C);
+        assert_eq!(restored.stack.peek(), Some(Address::from(0x206)));
+        restored.run_iteration().unwrap();
+        restored.run_iteration().unwrap();
+        assert_eq!(restored.registers[0x1], random);
+        assert_eq!(restored.display_screen.frame_buffer(), &buffer);
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_snapshots() {
+        let mut interpreter = test_interpreter(&[0x60, 0x01]);
+        let original = interpreter.snapshot();
+
+        let mut snapshot = original.clone();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        assert!(matches!(
+            interpreter.restore(&snapshot),
+            Err(Chip8Error::InvalidSnapshot { .. })
+        ));
+        let mut snapshot = original.clone();
+        snapshot.stack_pointer = 17;
+        assert!(interpreter.restore(&snapshot).is_err());
+        let mut snapshot = original.clone();
+        snapshot.memory.truncate(0x800);
+        assert!(interpreter.restore(&snapshot).is_err());
+        let mut snapshot = original.clone();
+        snapshot.platform = Platform::SuperChip;
+        assert!(interpreter.restore(&snapshot).is_err());
+        assert!(Snapshot::from_bytes(b"{\"version\": 1}").is_err());
+        assert_eq!(interpreter.snapshot(), original);
+    }
+
+    #[test]
+    fn test_rewind_frames() {
+        // ADD V1, 1; JP 0x200
+        let rom = [0x71, 0x01, 0x12, 0x00];
+        let mut interpreter = test_interpreter(&rom);
+        interpreter.set_instructions_per_frame(2);
+        let mut rewind = RewindBuffer::default();
+        for _ in 0..10 {
+            rewind.record(&interpreter);
+            interpreter.run_frame().unwrap();
+        }
+        assert_eq!(interpreter.registers[0x1], 10);
+        for _ in 0..4 {
+            assert!(rewind.rewind(&mut interpreter).unwrap());
+        }
+        assert_eq!(interpreter.registers[0x1], 6);
+        rewind.record(&interpreter);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.registers[0x1], 7);
+        while rewind.rewind(&mut interpreter).unwrap() {}
+        assert_eq!(interpreter.registers[0x1], 0);
+        assert_eq!(interpreter.program_counter.value, Address::from(0x200));
+    }
+
+    #[test]
+    fn test_seed_reproduces_run() {
+        // RND V0, 0xFF; RND V1, 0xFF; RND V2, 0xFF; RND V3, 0xFF
+        let rom = [0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF, 0xC3, 0xFF];
+        let run = |seed: u64, mode: RngMode| {
+            let mut interpreter = test_interpreter(&rom);
+            interpreter.set_rng_mode(mode);
+            interpreter.set_seed(seed);
+            for _ in 0..4 {
+                interpreter.run_iteration().unwrap();
+            }
+            assert_eq!(interpreter.seed(), seed);
+            assert_eq!(interpreter.rng_mode(), mode);
+            interpreter.registers.clone()
+        };
+        assert_eq!(run(1234, RngMode::Xorshift), run(1234, RngMode::Xorshift));
+        assert_ne!(run(1234, RngMode::Xorshift), run(4321, RngMode::Xorshift));
+        assert_eq!(run(1234, RngMode::CosmacVip), run(1234, RngMode::CosmacVip));
+    }
+
+    #[test]
+    fn test_movie_replays_run() {
+        // RND V0, 0xFF; SKNP V5; ADD V2, 1; JP 0x200
+        let rom = [0xC0, 0xFF, 0xE5, 0xA1, 0x72, 0x01, 0x12, 0x00];
+        let mut keyboard = TestKeyboard::new();
+        keyboard.keys[0] = true;
+        let mut recorded = test_interpreter(&rom);
+        let movie = Arc::new(Mutex::new(Movie::new(&recorded)));
+        recorded.set_keyboard(Box::new(RecordingKeyboard::new(
+            Box::new(keyboard),
+            movie.clone(),
+        )));
+        for _ in 0..5 {
+            recorded.run_frame().unwrap();
+        }
+        assert_ne!(recorded.registers[0x2], 0);
+        let movie = Movie::from_bytes(&movie.lock().unwrap().to_bytes()).unwrap();
+        assert_eq!(movie.frames.len(), 5);
+
+        let mut replayed = Interpreter::new(
+            test_display(),
+            Box::new(NullBuzzer),
+            Box::new(PlaybackKeyboard::new(&movie)),
+            &rom,
+        )
+        .unwrap();
+        movie.configure(&mut replayed).unwrap();
+        for _ in 0..5 {
+            replayed.run_frame().unwrap();
+        }
+        assert_eq!(replayed.snapshot(), recorded.snapshot());
+        assert!(movie
+            .configure(&mut test_interpreter(&[0x12, 0x00]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_debugger() {
+        // 0x200: CALL 0x208
+        // 0x202: ADD V1, 1
+        // 0x204: JP 0x202
+        // 0x208: ADD V0, 1
+        // 0x20A: RET
+        let mut interpreter = test_interpreter(&[
+            0x22, 0x08, 0x71, 0x01, 0x12, 0x02, 0x00, 0x00, 0x70, 0x01, 0x00, 0xEE,
+        ]);
+        interpreter.debugger_mut().add_breakpoint(0x202);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x202);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Breakpoint { address: 0x202 })
+        );
+        // a paused machine stands still
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x202);
+        interpreter.step().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x204);
+        assert_eq!(interpreter.registers[0x1], 1);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Step)
+        );
+        interpreter.run_to(0x200);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x202);
+
+        let mut interpreter = test_interpreter(&[
+            0x22, 0x08, 0x71, 0x01, 0x12, 0x02, 0x00, 0x00, 0x70, 0x01, 0x00, 0xEE,
+        ]);
+        interpreter.pause();
+        interpreter.step_over().unwrap();
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x202);
+        assert_eq!(interpreter.registers[0x0], 1);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Step)
+        );
+
+        let mut interpreter = test_interpreter(&[
+            0x22, 0x08, 0x71, 0x01, 0x12, 0x02, 0x00, 0x00, 0x70, 0x01, 0x00, 0xEE,
+        ]);
+        interpreter.pause();
+        interpreter.step().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x208);
+        interpreter.step_out();
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x202);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::StepOut)
+        );
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        // 0x200: ADD V0, 1
+        // 0x202: DRW V0, V0, 1
+        // 0x204: JP 0x200
+        let mut interpreter = test_interpreter(&[0x70, 0x01, 0xD0, 0x01, 0x12, 0x00]);
+        interpreter.debugger_mut().add(
+            Breakpoint::everywhere()
+                .with_condition(Expression::parse("is DRW && V0 == 3").unwrap()),
+        );
+        interpreter.set_instructions_per_frame(100);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x202);
+        assert_eq!(interpreter.registers[0x0], 3);
+    }
+
+    #[test]
+    fn test_step_back() {
+        // 0x200: LD V0, 5
+        // 0x202: LD I, 0x300
+        // 0x204: CALL 0x20A
+        // 0x206: ADD V0, 1
+        // 0x208: JP 0x208
+        // 0x20A: LD [I], V0
+        // 0x20C: RET
+        let mut interpreter = test_interpreter(&[
+            0x60, 0x05, 0xA3, 0x00, 0x22, 0x0A, 0x70, 0x01, 0x12, 0x08, 0xF0, 0x55, 0x00, 0xEE,
+        ]);
+        for _ in 0..5 {
+            interpreter.run_iteration().unwrap();
+        }
+        assert_eq!(interpreter.program_counter(), 0x206);
+        assert_eq!(interpreter.memory().bytes()[0x300], 5);
+        assert!(interpreter.step_back());
+        assert_eq!(interpreter.program_counter(), 0x20C);
+        assert_eq!(interpreter.stack.stack_pointer(), 1);
+        assert!(interpreter.step_back());
+        assert_eq!(interpreter.program_counter(), 0x20A);
+        assert_eq!(interpreter.memory().bytes()[0x300], 0);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Step)
+        );
+
+        interpreter.debugger_mut().add_breakpoint(0x202);
+        assert!(interpreter.reverse_continue());
+        assert_eq!(interpreter.program_counter(), 0x202);
+        assert_eq!(interpreter.register(0), 5);
+        assert!(!interpreter.reverse_continue());
+        assert_eq!(interpreter.program_counter(), 0x200);
+        assert_eq!(interpreter.register(0), 0);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::HistoryStart)
+        );
+
+        // running forward again records afresh, within the limit
+        interpreter.set_history_limit(2);
+        interpreter.step().unwrap();
+        interpreter.resume();
+        for _ in 0..4 {
+            interpreter.run_iteration().unwrap();
+        }
+        assert_eq!(interpreter.program_counter(), 0x206);
+        assert_eq!(interpreter.history().len(), 2);
+        interpreter.set_history_limit(0);
+        interpreter.resume();
+        interpreter.run_iteration().unwrap();
+        assert!(!interpreter.step_back());
+    }
+
+    #[test]
+    fn test_symbols_and_backtraces() {
+        // 0x200: CALL 0x204
+        // 0x202: JP 0x202
+        // 0x204: LD V0, 1
+        // 0x206: RET
+        let mut interpreter = test_interpreter(&[0x22, 0x04, 0x12, 0x02, 0x60, 0x01, 0x00, 0xEE]);
+        interpreter.set_symbols(Symbols::parse("main 0x200\ndraw 0x204\n").unwrap());
+        interpreter
+            .debugger_mut()
+            .add(Breakpoint::at(0x206).with_log(Template::parse("{PC:s} from {I:s}").unwrap()));
+        for _ in 0..2 {
+            interpreter.run_iteration().unwrap();
+        }
+        assert_eq!(interpreter.backtrace(), vec![0x206, 0x200]);
+        assert_eq!(
+            interpreter.format_backtrace(),
+            "#0 0x206 draw+2\n#1 0x200 main\n"
+        );
+        interpreter.run_iteration().unwrap();
+        assert_eq!(
+            interpreter.debugger_mut().take_log(),
+            vec!["draw+2 from 0x000"]
+        );
+        assert_eq!(interpreter.backtrace(), vec![0x202]);
+        assert_eq!(interpreter.max_stack_depth(), 1);
+        interpreter.set_symbols(Symbols::new());
+        assert_eq!(interpreter.format_backtrace(), "#0 0x202\n");
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        // 0x200: LD I, 0x300
+        // 0x202: LD V0, 0x7B
+        // 0x204: LD B, V0
+        // 0x206: LD [I], V2
+        // 0x208: JP 0x208
+        let mut interpreter =
+            test_interpreter(&[0xA3, 0x00, 0x60, 0x7B, 0xF0, 0x33, 0xF2, 0x55, 0x12, 0x08]);
+        interpreter.add_watchpoint(Watchpoint::new(0x302, 0x302, WatchKind::Write));
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x206);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Watchpoint {
+                instruction_address: 0x204,
+                address: 0x302,
+                access: MemoryAccess::Write,
+                old: 0,
+                new: 3,
+            })
+        );
+        interpreter.resume();
+        interpreter.run_frame().unwrap();
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Watchpoint {
+                instruction_address: 0x206,
+                address: 0x302,
+                access: MemoryAccess::Write,
+                old: 3,
+                new: 0,
+            })
+        );
+        // restoring a snapshot keeps the watchpoints
+        interpreter.restore(&interpreter.snapshot()).unwrap();
+        assert_eq!(interpreter.watchpoints().len(), 1);
+    }
+
+    #[test]
+    fn test_step_reports_watchpoint() {
+        // 0x200: LD I, 0x300
+        // 0x202: LD V0, 0x7B
+        // 0x204: LD [I], V0
+        // 0x206: JP 0x206
+        let mut interpreter = test_interpreter(&[0xA3, 0x00, 0x60, 0x7B, 0xF0, 0x55, 0x12, 0x06]);
+        interpreter.add_watchpoint(Watchpoint::new(0x300, 0x300, WatchKind::Write));
+        interpreter.pause();
+        interpreter.step().unwrap();
+        interpreter.step().unwrap();
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Step)
+        );
+        interpreter.step().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x206);
+        assert_eq!(
+            interpreter.debugger().pause_reason(),
+            Some(PauseReason::Watchpoint {
+                instruction_address: 0x204,
+                address: 0x300,
+                access: MemoryAccess::Write,
+                old: 0,
+                new: 0x7B,
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_sound_timer() {
+        let mut sound_timer = SoundTimer::new();
+        let vx = Register::from(6);
+        let buzzer: Box<dyn Buzzer> = Box::new(NullBuzzer);
+        Interpreter::store_sound_timer(&mut sound_timer, &vx, buzzer.as_ref());
+        assert_eq!(sound_timer.get(), 6);
+    }
+
+    // 0x200: LD V0, 0x3C
+    // 0x202: LD DT, V0
+    // 0x204: JP 0x204
+    const DELAY_ROM: [u8; 6] = [0x60, 0x3C, 0xF0, 0x15, 0x12, 0x04];
+
+    #[test]
+    fn test_run_frame_ticks_timers_once() {
+        let mut interpreter = test_interpreter(&DELAY_ROM);
+        interpreter.set_instructions_per_frame(2);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.delay_timer(), 59);
+        interpreter.set_instructions_per_frame(10);
+        for _ in 0..59 {
+            interpreter.run_frame().unwrap();
+        }
+        assert_eq!(interpreter.delay_timer(), 0);
+    }
+
+    #[test]
+    fn test_update_timers_follows_clock() {
+        let mut interpreter = test_interpreter(&DELAY_ROM);
+        let clock = ManualClock::new();
+        clock.set(Duration::from_secs(100));
+        interpreter.set_clock(Box::new(clock.clone()));
+        interpreter.run_iteration().unwrap();
+        interpreter.run_iteration().unwrap();
+        interpreter.update_timers();
+        assert_eq!(interpreter.delay_timer(), 60);
+        clock.advance(Duration::from_millis(500));
+        interpreter.update_timers();
+        assert_eq!(interpreter.delay_timer(), 30);
+        clock.advance(Duration::from_secs(1));
+        interpreter.update_timers();
+        assert_eq!(interpreter.delay_timer(), 0);
+    }
+
+    #[test]
+    fn test_run_frame_follows_clock() {
+        let mut interpreter = test_interpreter(&DELAY_ROM);
+        let clock = ManualClock::new();
+        interpreter.set_clock(Box::new(clock.clone()));
+        interpreter.set_instructions_per_frame(2);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.delay_timer(), 60);
+        clock.advance(Duration::from_millis(500));
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.delay_timer(), 30);
+        interpreter.update_timers();
+        assert_eq!(interpreter.delay_timer(), 30);
+    }
+
+    #[test]
+    fn test_add_address() {
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x0100));
+        let vx = Register::from(0x20);
+        Interpreter::add_address(&mut i, &vx);
+        let mut test_i = AddressRegister::new();
+        test_i.set(Address::from(0x0120));
+        assert_eq!(i, test_i);
+    }
+    #[test]
+    fn test_set_i_sprite() {
+        let mut i = AddressRegister::new();
+        let vx = Register::from(5);
+        Interpreter::set_i_sprite(&mut i, &vx);
+        let mut test_i = AddressRegister::new();
+        test_i.set(Address::from(25));
+        assert_eq!(i, test_i);
+    }
+    #[test]
+    fn test_load_bcd() {
+        let mut memory = Memory::new();
+        let vx = Register::from(123);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x200));
+        Interpreter::load_bcd(&vx, &i, &mut memory).unwrap();
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 1);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x201));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 2);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x202));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 3);
+    }
+    #[test]
+    fn test_load_range() {
+        let registers = [Register::from(9); 16];
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x500));
+        let mut memory = Memory::new();
+        Interpreter::load_range(&registers[..], &i, &mut memory).unwrap();
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x501));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x502));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x503));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x504));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x505));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x506));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x507));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x508));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x509));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x50A));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x50B));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x50C));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x50D));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x50E));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+        let mut i = AddressRegister::new();
+        i.set(Address::from(0x50F));
+        assert_eq!(memory.load(&i, 1).unwrap()[0], 9);
+    }
+    #[test]
+    fn test_load_range_registers() {
+        let mut registers = [Register::from(0); 16];
+        let i = AddressRegister::new();
+        let memory = Memory::new();
+        Interpreter::load_range_registers(&mut registers[0..5], &i, &memory).unwrap();
+        assert_eq!(registers[0], 0xF0);
+        assert_eq!(registers[1], 0x90);
+        assert_eq!(registers[2], 0x90);
+        assert_eq!(registers[3], 0x90);
+        assert_eq!(registers[4], 0xF0);
+    }
+}
@@ -0,0 +1,245 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The rate at which the delay and sound timers count down.
+pub const TIMER_FREQUENCY: u32 = 60;
+
+/// A source of monotonic time for the interpreter.
+///
+/// Only differences between readings matter, so `now` may count from any
+/// fixed starting point.
+pub trait Clock: Send {
+    fn now(&self) -> Duration;
+}
+
+/// A `Clock` that follows the wall clock.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A `Clock` that only moves when told to, for tests and replays.
+///
+/// Clones share the same time, so a caller can keep one handle and give
+/// another to the interpreter.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    nanoseconds: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanoseconds
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.nanoseconds
+            .store(now.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanoseconds.load(Ordering::Relaxed))
+    }
+}
+
+/// Returns the number of whole timer periods that fit in `elapsed`.
+pub fn timer_ticks(elapsed: Duration) -> u64 {
+    (elapsed.as_nanos() * TIMER_FREQUENCY as u128 / 1_000_000_000) as u64
+}
+
+/// The most frames `FrameScheduler::frames_due` will ask for at once. A host
+/// that falls further behind than this, for example after being suspended,
+/// skips the missed frames instead of racing to catch up.
+pub const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+/// Paces emulation at `TIMER_FREQUENCY` frames per second, scaled by a speed
+/// multiplier.
+///
+/// Frame deadlines are kept on a fixed grid rather than measured from the
+/// end of the previous frame, so time lost to oversleeping or slow frames is
+/// made up on the following frames instead of accumulating.
+#[derive(Debug)]
+pub struct FrameScheduler {
+    speed: f64,
+    frame_period: Duration,
+    next_frame: Duration,
+}
+
+impl FrameScheduler {
+    /// Creates a scheduler at normal speed whose first frame is due at
+    /// `now`.
+    pub fn new(now: Duration) -> Self {
+        Self {
+            speed: 1.0,
+            frame_period: Self::period(1.0),
+            next_frame: now,
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets the speed multiplier: 2.0 fast-forwards at twice the normal
+    /// frame rate and 0.5 plays in slow motion at half of it. The next frame
+    /// is due one new frame period after `now`.
+    pub fn set_speed(&mut self, speed: f64, now: Duration) {
+        assert!(
+            speed.is_finite() && speed > 0.0,
+            "speed must be a positive number"
+        );
+        self.speed = speed;
+        self.frame_period = Self::period(speed);
+        self.next_frame = now + self.frame_period;
+    }
+
+    /// Returns how many frames should be run at `now` and moves the next
+    /// deadline past them.
+    pub fn frames_due(&mut self, now: Duration) -> u32 {
+        let mut frames = 0;
+        while self.next_frame <= now {
+            if frames == MAX_CATCH_UP_FRAMES {
+                self.next_frame = now + self.frame_period;
+                break;
+            }
+            frames += 1;
+            self.next_frame += self.frame_period;
+        }
+        frames
+    }
+
+    /// Returns how long the host can sleep before the next frame is due.
+    pub fn time_until_next_frame(&self, now: Duration) -> Duration {
+        self.next_frame.saturating_sub(now)
+    }
+
+    fn period(speed: f64) -> Duration {
+        Duration::from_secs_f64(1.0 / (TIMER_FREQUENCY as f64 * speed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::timer_ticks;
+    use crate::clock::Clock;
+    use crate::clock::FrameScheduler;
+    use crate::clock::ManualClock;
+    use crate::clock::MAX_CATCH_UP_FRAMES;
+    use std::time::Duration;
+
+    #[test]
+    fn manual_clock_advance() {
+        let clock = ManualClock::new();
+        let handle = clock.clone();
+        handle.advance(Duration::from_millis(5));
+        handle.advance(Duration::from_millis(5));
+        assert_eq!(clock.now(), Duration::from_millis(10));
+        handle.set(Duration::from_secs(1));
+        assert_eq!(clock.now(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_timer_ticks() {
+        assert_eq!(timer_ticks(Duration::from_millis(16)), 0);
+        assert_eq!(timer_ticks(Duration::from_millis(17)), 1);
+        assert_eq!(timer_ticks(Duration::from_secs(1)), 60);
+        assert_eq!(timer_ticks(Duration::from_secs(10)), 600);
+    }
+
+    #[test]
+    fn frame_scheduler_runs_sixty_frames_a_second() {
+        let mut scheduler = FrameScheduler::new(Duration::ZERO);
+        let mut frames = 0;
+        for millisecond in 0..1000 {
+            frames += scheduler.frames_due(Duration::from_millis(millisecond));
+        }
+        assert_eq!(frames, 60);
+    }
+
+    #[test]
+    fn frame_scheduler_compensates_for_drift() {
+        let mut scheduler = FrameScheduler::new(Duration::ZERO);
+        assert_eq!(scheduler.frames_due(Duration::ZERO), 1);
+        // Oversleeping by a few milliseconds every frame must not push later
+        // deadlines back.
+        let mut now = Duration::ZERO;
+        let mut frames = 1;
+        while now < Duration::from_secs(1) {
+            now += scheduler.time_until_next_frame(now) + Duration::from_millis(3);
+            frames += scheduler.frames_due(now);
+        }
+        assert_eq!(frames, 61);
+    }
+
+    #[test]
+    fn frame_scheduler_limits_catch_up() {
+        let mut scheduler = FrameScheduler::new(Duration::ZERO);
+        assert_eq!(
+            scheduler.frames_due(Duration::from_secs(10)),
+            MAX_CATCH_UP_FRAMES
+        );
+        assert_eq!(scheduler.frames_due(Duration::from_secs(10)), 0);
+    }
+
+    #[test]
+    fn frame_scheduler_speed() {
+        let mut scheduler = FrameScheduler::new(Duration::ZERO);
+        scheduler.set_speed(2.0, Duration::ZERO);
+        let mut frames = 0;
+        for millisecond in 0..=1000 {
+            frames += scheduler.frames_due(Duration::from_millis(millisecond));
+        }
+        assert_eq!(frames, 120);
+        scheduler.set_speed(0.5, Duration::from_secs(1));
+        for millisecond in 1001..=3000 {
+            frames += scheduler.frames_due(Duration::from_millis(millisecond));
+        }
+        assert_eq!(frames, 180);
+    }
+}
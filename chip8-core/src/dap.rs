@@ -0,0 +1,914 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A Debug Adapter Protocol server, so editors can launch and debug a ROM
+//! or the Octo source it is assembled from.
+//!
+//! Messages are JSON with a `Content-Length` header, read and written over
+//! standard input and output or a socket. A launch request's `program` is a
+//! ROM or a `.8o` file, with optional `platform`, `quirks`, `symbols` and
+//! `stopOnEntry`. With source, breakpoints go on lines and stack frames
+//! point back at them through the assembler's line map. Frames are named by
+//! the source's labels, the `symbols` file, or the symbol file kept next to
+//! the ROM. There is a single thread and every step is one instruction.
+
+use crate::assembler;
+use crate::chip8::Interpreter;
+use crate::clock::Clock;
+use crate::clock::FrameScheduler;
+use crate::clock::SystemClock;
+use crate::debugger::Breakpoint;
+use crate::debugger::PauseReason;
+use crate::error::Chip8Error;
+use crate::expression::Expression;
+use crate::expression::MachineState;
+use crate::expression::Template;
+use crate::movie;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::symbols::Symbols;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+/// Creates the interpreter for a launched ROM, with the front end's
+/// display, buzzer and keyboard.
+pub type Launcher = Box<dyn FnMut(&[u8], Platform) -> Result<Interpreter, Chip8Error>>;
+
+const THREAD_ID: u64 = 1;
+const REGISTERS_REFERENCE: u64 = 1;
+const STACK_REFERENCE: u64 = 2;
+
+/// Reads one message, or `None` at the end of the input.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let length: usize = length.ok_or_else(|| invalid_data("a message has no Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|error| invalid_data(&error.to_string()))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A launched ROM and what is known about where it came from.
+struct Program {
+    interpreter: Interpreter,
+    /// The source it was assembled from, if any.
+    source: Option<PathBuf>,
+    /// The source line of each instruction, by address.
+    lines: BTreeMap<u16, usize>,
+    /// The first instruction on each source line.
+    addresses: BTreeMap<usize, u16>,
+}
+
+impl Program {
+    /// Describes the frame executing `address`.
+    fn frame(&self, id: usize, address: u16) -> Value {
+        let name = self.interpreter.symbols().describe(address);
+        let mut frame = json!({
+            "id": id,
+            "name": name,
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": format!("0x{:04X}", address),
+        });
+        let line = self.lines.range(..=address).next_back();
+        if let (Some(path), Some((_, &line))) = (&self.source, line) {
+            frame["source"] = source(path);
+            frame["line"] = json!(line);
+            frame["column"] = json!(1);
+        }
+        frame
+    }
+}
+
+fn source(path: &Path) -> Value {
+    json!({
+        "name": path.file_name().map(|name| name.to_string_lossy()),
+        "path": path,
+    })
+}
+
+/// The state of one debugging session, answering requests and reporting
+/// events as messages to send back.
+pub struct Session {
+    launcher: Launcher,
+    program: Option<Program>,
+    stop_on_entry: bool,
+    running: bool,
+    finished: bool,
+    seq: u64,
+    /// Messages ready to send.
+    outgoing: Vec<Value>,
+    /// Events waiting for the response to the request that caused them.
+    events: Vec<Value>,
+}
+
+impl Session {
+    pub fn new(launcher: Launcher) -> Self {
+        Session {
+            launcher,
+            program: None,
+            stop_on_entry: false,
+            running: false,
+            finished: false,
+            seq: 0,
+            outgoing: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Whether the program is running, and so wants `run_frames` called.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Whether the editor has disconnected.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Takes the responses and events to send, oldest first.
+    pub fn take_messages(&mut self) -> Vec<Value> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Answers a request from the editor.
+    pub fn handle(&mut self, message: &Value) {
+        if message["type"] != "request" {
+            return;
+        }
+        let command = message["command"].as_str().unwrap_or_default();
+        let arguments = &message["arguments"];
+        let result = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsConditionalBreakpoints": true,
+                "supportsHitConditionalBreakpoints": true,
+                "supportsLogPoints": true,
+                "supportsEvaluateForHovers": true,
+                "supportsReadMemoryRequest": true,
+                "supportsStepBack": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "configurationDone" => self.configuration_done(),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => Ok(json!({ "scopes": [
+                { "name": "Registers", "variablesReference": REGISTERS_REFERENCE },
+                { "name": "Stack", "variablesReference": STACK_REFERENCE },
+            ]})),
+            "variables" => self.variables(arguments),
+            "readMemory" => self.read_memory(arguments),
+            "evaluate" => self.evaluate(arguments),
+            "continue" | "next" | "stepIn" | "stepOut" | "stepBack" | "reverseContinue"
+            | "pause" => self.control(command),
+            "disconnect" | "terminate" => {
+                self.finished = true;
+                self.running = false;
+                Ok(Value::Null)
+            }
+            _ => Err(format!("'{}' is not supported", command)),
+        };
+        let mut response = json!({
+            "type": "response",
+            "request_seq": message["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => response["body"] = body,
+            Err(error) => response["message"] = json!(error),
+        }
+        self.send(response);
+        let events = std::mem::take(&mut self.events);
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    /// Runs `frames` frames of a running program, stopping early if it
+    /// pauses, halts or fails.
+    pub fn run_frames(&mut self, frames: u32) {
+        for _ in 0..frames {
+            let result = match &mut self.program {
+                Some(program) if self.running => program.interpreter.run_frame(),
+                _ => break,
+            };
+            self.report(result);
+        }
+        let events = std::mem::take(&mut self.events);
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    fn send(&mut self, mut message: Value) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        self.outgoing.push(message);
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        let mut event = json!({ "type": "event", "event": event });
+        if !body.is_null() {
+            event["body"] = body;
+        }
+        self.events.push(event);
+    }
+
+    fn program(&mut self) -> Result<&mut Program, String> {
+        self.program
+            .as_mut()
+            .ok_or_else(|| "no program has been launched".to_string())
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String> {
+        let path = PathBuf::from(
+            arguments["program"]
+                .as_str()
+                .ok_or("launch needs a program")?,
+        );
+        let file_error = |error: io::Error| format!("{}: {}", path.display(), error);
+        let assembly = match assembler::is_source(&path) {
+            true => {
+                let source = std::fs::read_to_string(&path).map_err(file_error)?;
+                let assembly = assembler::assemble(&source)
+                    .map_err(|error| format!("{}: {}", path.display(), error))?;
+                Some(assembly)
+            }
+            false => None,
+        };
+        let rom = match &assembly {
+            Some(assembly) => assembly.rom.clone(),
+            None => std::fs::read(&path).map_err(file_error)?,
+        };
+        let platform = match arguments["platform"].as_str() {
+            Some(name) => {
+                Platform::from_name(name).ok_or_else(|| format!("unknown platform '{}'", name))?
+            }
+            None => Platform::from_path(&path),
+        };
+        let mut interpreter = (self.launcher)(&rom, platform).map_err(|error| error.to_string())?;
+        if let Some(name) = arguments["quirks"].as_str() {
+            let quirks =
+                Quirks::preset(name).ok_or_else(|| format!("unknown quirks preset '{}'", name))?;
+            interpreter.set_quirks(quirks);
+        }
+        let mut program = Program {
+            interpreter,
+            source: None,
+            lines: BTreeMap::new(),
+            addresses: BTreeMap::new(),
+        };
+        let symbols = match arguments["symbols"].as_str() {
+            Some(symbols) => {
+                let text = std::fs::read_to_string(symbols)
+                    .map_err(|error| format!("{}: {}", symbols, error))?;
+                Symbols::parse(&text).map_err(|error| format!("{}: {}", symbols, error))?
+            }
+            None => match &assembly {
+                Some(assembly) => assembly.symbol_table(),
+                None => Symbols::find(&path, &movie::rom_hash(&rom)).unwrap_or_default(),
+            },
+        };
+        program.interpreter.set_symbols(symbols);
+        if let Some(assembly) = assembly {
+            for (&address, &line) in &assembly.lines {
+                program.addresses.entry(line).or_insert(address);
+            }
+            program.lines = assembly.lines;
+            program.source = Some(path);
+        }
+        self.program = Some(program);
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.event("initialized", Value::Null);
+        Ok(Value::Null)
+    }
+
+    /// Replaces the breakpoints with those on the lines asked for, each
+    /// moved to the first instruction on or after its line.
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        let program = self.program()?;
+        let path = arguments["source"]["path"].as_str().map(Path::new);
+        let in_program = match (path, &program.source) {
+            (Some(path), Some(source)) => same_file(path, source),
+            _ => false,
+        };
+        let debugger = program.interpreter.debugger_mut();
+        debugger.clear_breakpoints();
+        let requested = arguments["breakpoints"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut breakpoints = Vec::new();
+        for requested in requested {
+            let line = requested["line"].as_u64().unwrap_or(0) as usize;
+            let found = program
+                .addresses
+                .range(line..)
+                .next()
+                .filter(|_| in_program);
+            let (&line, &address) = match found {
+                Some(found) => found,
+                None => {
+                    breakpoints.push(json!({
+                        "verified": false,
+                        "line": line,
+                        "message": "no code on or after this line",
+                    }));
+                    continue;
+                }
+            };
+            match breakpoint(address, &requested) {
+                Ok(breakpoint) => {
+                    let id = debugger.add(breakpoint);
+                    breakpoints.push(json!({
+                        "id": id,
+                        "verified": true,
+                        "line": line,
+                        "instructionReference": format!("0x{:04X}", address),
+                    }));
+                }
+                Err(error) => breakpoints.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": error,
+                })),
+            }
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn configuration_done(&mut self) -> Result<Value, String> {
+        let stop_on_entry = self.stop_on_entry;
+        let program = self.program()?;
+        if stop_on_entry {
+            program.interpreter.pause();
+            self.event(
+                "stopped",
+                json!({ "reason": "entry", "threadId": THREAD_ID, "allThreadsStopped": true }),
+            );
+        } else {
+            // not resume, which would skip a breakpoint on the first
+            // instruction
+            self.running = true;
+        }
+        Ok(Value::Null)
+    }
+
+    /// Carries out `continue`, `next`, `stepIn`, `stepOut`, `stepBack`,
+    /// `reverseContinue` or `pause`.
+    fn control(&mut self, command: &str) -> Result<Value, String> {
+        let interpreter = &mut self.program()?.interpreter;
+        let result = match command {
+            "continue" => {
+                interpreter.resume();
+                Ok(())
+            }
+            "next" => interpreter.step_over(),
+            "stepIn" => interpreter.step(),
+            "stepOut" => {
+                interpreter.step_out();
+                Ok(())
+            }
+            "stepBack" => {
+                interpreter.step_back();
+                Ok(())
+            }
+            "reverseContinue" => {
+                interpreter.reverse_continue();
+                Ok(())
+            }
+            _ => {
+                interpreter.pause();
+                Ok(())
+            }
+        };
+        self.report(result);
+        match command {
+            "continue" => Ok(json!({ "allThreadsContinued": true })),
+            _ => Ok(Value::Null),
+        }
+    }
+
+    /// Queues the events for what running the program led to, and notes
+    /// whether it is still running.
+    fn report(&mut self, result: Result<(), Chip8Error>) {
+        let program = match &mut self.program {
+            Some(program) => program,
+            None => return,
+        };
+        let interpreter = &mut program.interpreter;
+        let log = interpreter.debugger_mut().take_log();
+        let stopped = |reason: &str, description: String| {
+            json!({
+                "reason": reason,
+                "description": description,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            })
+        };
+        let stop = match result {
+            Err(error) => {
+                interpreter.pause();
+                Some(("stopped", stopped("exception", error.to_string())))
+            }
+            Ok(()) if interpreter.is_halted() => Some(("terminated", Value::Null)),
+            Ok(()) => interpreter.debugger().pause_reason().map(|reason| {
+                let name = match reason {
+                    PauseReason::Requested => "pause",
+                    PauseReason::Breakpoint { .. } => "breakpoint",
+                    PauseReason::Watchpoint { .. } => "data breakpoint",
+                    PauseReason::Step
+                    | PauseReason::StepOut
+                    | PauseReason::RunTo { .. }
+                    | PauseReason::HistoryStart => "step",
+                };
+                ("stopped", stopped(name, format!("Paused on {}", name)))
+            }),
+        };
+        for message in log {
+            self.event(
+                "output",
+                json!({ "category": "console", "output": format!("{}\n", message) }),
+            );
+        }
+        self.running = stop.is_none();
+        if let Some((event, body)) = stop {
+            if event == "terminated" {
+                self.event("exited", json!({ "exitCode": 0 }));
+            }
+            self.event(event, body);
+        }
+    }
+
+    fn stack_trace(&mut self) -> Result<Value, String> {
+        let program = self.program()?;
+        let frames: Vec<Value> = program
+            .interpreter
+            .backtrace()
+            .into_iter()
+            .enumerate()
+            .map(|(id, address)| program.frame(id, address))
+            .collect();
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    fn variables(&mut self, arguments: &Value) -> Result<Value, String> {
+        let program = self.program()?;
+        let interpreter = &program.interpreter;
+        let variable = |name: String, value: String| json!({ "name": name, "value": value, "variablesReference": 0 });
+        let variables: Vec<Value> = match arguments["variablesReference"].as_u64() {
+            Some(REGISTERS_REFERENCE) => {
+                let mut variables: Vec<Value> = (0..16)
+                    .map(|x| {
+                        variable(
+                            format!("V{:X}", x),
+                            format!("0x{:02X}", interpreter.register(x)),
+                        )
+                    })
+                    .collect();
+                let i = interpreter.address_register();
+                let mut i_variable = variable("I".to_string(), format!("0x{:03X}", i));
+                i_variable["memoryReference"] = json!(format!("0x{:04X}", i));
+                variables.push(i_variable);
+                variables.extend([
+                    variable(
+                        "PC".to_string(),
+                        format!("0x{:03X}", interpreter.program_counter()),
+                    ),
+                    variable("SP".to_string(), interpreter.stack_pointer().to_string()),
+                    variable("DT".to_string(), interpreter.delay_timer().to_string()),
+                    variable("ST".to_string(), interpreter.sound_timer().to_string()),
+                ]);
+                variables
+            }
+            Some(STACK_REFERENCE) => {
+                let returns = interpreter.stack().return_addresses();
+                returns
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, &address)| {
+                        variable(
+                            format!("[{}]", index),
+                            format!("0x{:03X}", u16::from(address)),
+                        )
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn read_memory(&mut self, arguments: &Value) -> Result<Value, String> {
+        let program = self.program()?;
+        let reference = arguments["memoryReference"].as_str().unwrap_or_default();
+        let start = parse_address(reference)
+            .ok_or_else(|| format!("'{}' is not an address", reference))?
+            .checked_add(arguments["offset"].as_i64().unwrap_or(0))
+            .ok_or_else(|| format!("the offset from '{}' is out of range", reference))?;
+        let count = arguments["count"].as_u64().unwrap_or(0) as usize;
+        let memory = program.interpreter.memory().bytes();
+        let start = usize::try_from(start)
+            .unwrap_or(memory.len())
+            .min(memory.len());
+        let end = start.saturating_add(count).min(memory.len());
+        Ok(json!({
+            "address": format!("0x{:04X}", start),
+            "data": base64(&memory[start..end]),
+            "unreadableBytes": count - (end - start),
+        }))
+    }
+
+    /// Evaluates a breakpoint condition style expression, for the watch
+    /// list and hovers.
+    fn evaluate(&mut self, arguments: &Value) -> Result<Value, String> {
+        let program = self.program()?;
+        let expression = Expression::parse(arguments["expression"].as_str().unwrap_or_default())
+            .map_err(|error| error.to_string())?;
+        let value = expression.evaluate(&program.interpreter);
+        Ok(json!({
+            "result": format!("{} (0x{:X})", value, value),
+            "variablesReference": 0,
+        }))
+    }
+}
+
+/// Builds the breakpoint a `SourceBreakpoint` asks for at `address`.
+fn breakpoint(address: u16, requested: &Value) -> Result<Breakpoint, String> {
+    let mut breakpoint = Breakpoint::at(address);
+    if let Some(condition) = requested["condition"].as_str() {
+        let condition = Expression::parse(condition).map_err(|error| error.to_string())?;
+        breakpoint = breakpoint.with_condition(condition);
+    }
+    if let Some(hits) = requested["hitCondition"].as_str() {
+        let hits = hits
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not a hit count", hits))?;
+        breakpoint = breakpoint.with_hit_count(hits);
+    }
+    if let Some(message) = requested["logMessage"].as_str() {
+        let log = Template::parse(message).map_err(|error| error.to_string())?;
+        breakpoint = breakpoint.with_log(log);
+    }
+    Ok(breakpoint)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Reads a memory reference, in hexadecimal with a `0x` prefix or in
+/// decimal.
+fn parse_address(reference: &str) -> Option<i64> {
+    match reference.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => reference.parse().ok(),
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let word = chunk.iter().enumerate().fold(0u32, |word, (index, byte)| {
+            word | (u32::from(*byte) << (16 - 8 * index))
+        });
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => text.push(ALPHABET[((word >> (18 - 6 * index)) & 0x3F) as usize] as char),
+                false => text.push('='),
+            }
+        }
+    }
+    text
+}
+
+/// Debugs with one editor, reading requests from `reader` on a thread of
+/// their own and writing to `writer`, until it disconnects. A running
+/// program is paced at normal speed.
+pub fn serve(
+    reader: impl BufRead + Send + 'static,
+    mut writer: impl Write,
+    launcher: Launcher,
+) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = reader;
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    let mut session = Session::new(launcher);
+    let clock = SystemClock::new();
+    let mut scheduler = FrameScheduler::new(clock.now());
+    while !session.is_finished() {
+        if session.is_running() {
+            match receiver.try_recv() {
+                Ok(message) => session.handle(&message),
+                Err(mpsc::TryRecvError::Empty) => {
+                    session.run_frames(scheduler.frames_due(clock.now()));
+                    thread::sleep(scheduler.time_until_next_frame(clock.now()));
+                }
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        } else {
+            match receiver.recv() {
+                Ok(message) => session.handle(&message),
+                Err(_) => break,
+            }
+            scheduler = FrameScheduler::new(clock.now());
+        }
+        for message in session.take_messages() {
+            write_message(&mut writer, &message)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio::NullBuzzer;
+    use crate::dap::base64;
+    use crate::dap::read_message;
+    use crate::dap::write_message;
+    use crate::dap::Session;
+    use crate::graphics::ConsoleDisplay;
+    use crate::movie::PlaybackKeyboard;
+    use crate::Interpreter;
+    use serde_json::json;
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    fn session() -> Session {
+        Session::new(Box::new(|rom, platform| {
+            Interpreter::with_platform(
+                Box::new(ConsoleDisplay::quiet()),
+                Box::new(NullBuzzer),
+                Box::new(PlaybackKeyboard::from_frames(vec![])),
+                rom,
+                platform,
+            )
+        }))
+    }
+
+    /// Sends a request and returns the response followed by any events.
+    fn request(session: &mut Session, command: &str, arguments: Value) -> Vec<Value> {
+        session.handle(&json!({
+            "seq": 1,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        }));
+        let messages = session.take_messages();
+        assert_eq!(messages[0]["command"], command);
+        assert_eq!(messages[0]["success"], true, "{}", messages[0]);
+        messages
+    }
+
+    fn write_source(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("chip8-dap-{}-{}", std::process::id(), name));
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn messages() {
+        let mut bytes = Vec::new();
+        write_message(&mut bytes, &json!({ "seq": 1 })).unwrap();
+        assert_eq!(bytes, b"Content-Length: 9\r\n\r\n{\"seq\":1}");
+        let mut reader = &bytes[..];
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(json!({ "seq": 1 }))
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+        assert_eq!(base64(b"CHIP-8"), "Q0hJUC04");
+        assert_eq!(base64(&[0xFF, 0x00]), "/wA=");
+    }
+
+    #[test]
+    fn breakpoints_stepping_and_variables() {
+        let path = write_source(
+            "game.8o",
+            ": main
+  v0 := 1
+  draw
+  v2 := 3
+: spin
+  jump spin
+: draw
+  i := 0x300
+  save v0
+  return
+",
+        );
+        let mut session = session();
+        request(&mut session, "initialize", json!({}));
+        let messages = request(&mut session, "launch", json!({ "program": path }));
+        assert_eq!(messages[1]["event"], "initialized");
+        let messages = request(
+            &mut session,
+            "setBreakpoints",
+            json!({ "source": { "path": path }, "breakpoints": [{ "line": 5 }, { "line": 9 }] }),
+        );
+        let breakpoints = &messages[0]["body"]["breakpoints"];
+        assert_eq!(breakpoints[0]["line"], 6);
+        assert_eq!(breakpoints[0]["instructionReference"], "0x0206");
+        assert_eq!(breakpoints[1]["line"], 9);
+        request(&mut session, "configurationDone", json!({}));
+        assert!(session.is_running());
+
+        session.run_frames(1);
+        let messages = session.take_messages();
+        assert_eq!(messages[0]["event"], "stopped");
+        assert_eq!(messages[0]["body"]["reason"], "breakpoint");
+        let messages = request(&mut session, "stackTrace", json!({ "threadId": 1 }));
+        let frames = &messages[0]["body"]["stackFrames"];
+        assert_eq!(frames[0]["name"], "draw+2");
+        assert_eq!(frames[0]["line"], 9);
+        assert_eq!(frames[1]["name"], "main+2");
+        assert_eq!(frames[1]["line"], 3);
+
+        let messages = request(
+            &mut session,
+            "variables",
+            json!({ "variablesReference": 1 }),
+        );
+        let registers = &messages[0]["body"]["variables"];
+        assert_eq!(registers[0]["value"], "0x01");
+        assert_eq!(registers[16]["name"], "I");
+        assert_eq!(registers[16]["memoryReference"], "0x0300");
+        let messages = request(
+            &mut session,
+            "variables",
+            json!({ "variablesReference": 2 }),
+        );
+        assert_eq!(
+            messages[0]["body"]["variables"],
+            json!([{ "name": "[0]", "value": "0x204", "variablesReference": 0 }])
+        );
+
+        let messages = request(&mut session, "stepIn", json!({ "threadId": 1 }));
+        assert_eq!(messages[1]["body"]["reason"], "step");
+        let messages = request(
+            &mut session,
+            "readMemory",
+            json!({ "memoryReference": "0x300", "count": 2 }),
+        );
+        assert_eq!(messages[0]["body"]["data"], base64(&[0x01, 0x00]));
+        session.handle(&json!({
+            "seq": 1,
+            "type": "request",
+            "command": "readMemory",
+            "arguments": { "memoryReference": "0x300", "offset": i64::MAX, "count": 2 },
+        }));
+        assert_eq!(session.take_messages()[0]["success"], false);
+        let messages = request(
+            &mut session,
+            "evaluate",
+            json!({ "expression": "V0 + mem[0x300]" }),
+        );
+        assert_eq!(messages[0]["body"]["result"], "2 (0x2)");
+
+        request(&mut session, "stepOut", json!({ "threadId": 1 }));
+        session.run_frames(1);
+        let messages = session.take_messages();
+        assert_eq!(messages[0]["body"]["reason"], "step");
+        let messages = request(&mut session, "stackTrace", json!({ "threadId": 1 }));
+        assert_eq!(messages[0]["body"]["stackFrames"][0]["line"], 4);
+
+        request(&mut session, "continue", json!({ "threadId": 1 }));
+        session.run_frames(1);
+        assert_eq!(session.take_messages()[0]["body"]["reason"], "breakpoint");
+        request(&mut session, "disconnect", json!({}));
+        assert!(session.is_finished());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn logpoints_and_halting() {
+        let path = write_source(
+            "halt.8o",
+            ": main
+  v0 := 7
+  exit
+",
+        );
+        let mut session = session();
+        request(
+            &mut session,
+            "launch",
+            json!({ "program": path, "platform": "super-chip" }),
+        );
+        let messages = request(
+            &mut session,
+            "setBreakpoints",
+            json!({ "source": { "path": path }, "breakpoints": [
+                { "line": 3, "logMessage": "V0 is {V0}" },
+                { "line": 1 },
+            ]}),
+        );
+        assert_eq!(messages[0]["body"]["breakpoints"][1]["verified"], true);
+        request(&mut session, "configurationDone", json!({}));
+        session.run_frames(1);
+        let messages = session.take_messages();
+        assert_eq!(messages[0]["body"]["reason"], "breakpoint");
+        request(&mut session, "continue", json!({ "threadId": 1 }));
+        session.run_frames(1);
+        let events: Vec<Value> = session
+            .take_messages()
+            .into_iter()
+            .map(|message| message["event"].clone())
+            .collect();
+        assert_eq!(events, vec!["output", "exited", "terminated"]);
+        assert!(!session.is_running());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn symbols_next_to_a_rom() {
+        // 0x200: CALL 0x204
+        // 0x202: JP 0x202
+        // 0x204: RET
+        let rom = [0x22, 0x04, 0x12, 0x02, 0x00, 0xEE];
+        let path = std::env::temp_dir().join(format!("chip8-dap-{}-rom.ch8", std::process::id()));
+        std::fs::write(&path, rom).unwrap();
+        let symbols = path.with_extension("sym");
+        std::fs::write(&symbols, "main 0x200\nblink 0x204\n").unwrap();
+        let mut session = session();
+        request(
+            &mut session,
+            "launch",
+            json!({ "program": path, "stopOnEntry": true }),
+        );
+        request(&mut session, "configurationDone", json!({}));
+        session.run_frames(1);
+        request(&mut session, "stepIn", json!({ "threadId": 1 }));
+        session.run_frames(1);
+        let messages = request(&mut session, "stackTrace", json!({ "threadId": 1 }));
+        let frames = &messages[0]["body"]["stackFrames"];
+        assert_eq!(frames[0]["name"], "blink");
+        assert_eq!(frames[1]["name"], "main");
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&symbols).unwrap();
+    }
+}
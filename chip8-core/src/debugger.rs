@@ -0,0 +1,549 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Breakpoints and stepping.
+//!
+//! Every [`Interpreter`](crate::Interpreter) has a [`Debugger`], which it
+//! asks before fetching each instruction whether to carry on. A paused
+//! interpreter executes nothing and its timers stand still until it is
+//! resumed or stepped.
+//!
+//! Watchpoints are kept by [`Memory`](crate::Memory) instead, which notes
+//! the first watched access an instruction makes so that the interpreter
+//! can pause after it.
+
+use crate::expression::Expression;
+use crate::expression::MachineState;
+use crate::expression::Template;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Why the interpreter paused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "reason")]
+pub enum PauseReason {
+    /// The host asked it to.
+    Requested,
+    /// The program counter reached a breakpoint.
+    Breakpoint { address: u16 },
+    /// A step or step over finished.
+    Step,
+    /// A step out returned from the subroutine.
+    StepOut,
+    /// The program counter reached the address of a run to.
+    RunTo { address: u16 },
+    /// Stepping back reached the oldest instruction recorded.
+    HistoryStart,
+    /// The instruction at `instruction_address` accessed a watched byte,
+    /// which held `old` and was read or overwritten with `new`.
+    Watchpoint {
+        instruction_address: u16,
+        address: u16,
+        access: MemoryAccess,
+        old: u8,
+        new: u8,
+    },
+}
+
+/// Which accesses a watchpoint stops on, as in GDB's `watch`, `rwatch` and
+/// `awatch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchKind {
+    Write,
+    Read,
+    Access,
+}
+
+impl WatchKind {
+    pub fn from_name(name: &str) -> Option<WatchKind> {
+        match name {
+            "write" => Some(WatchKind::Write),
+            "read" => Some(WatchKind::Read),
+            "access" => Some(WatchKind::Access),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MemoryAccess {
+    Read,
+    Write,
+}
+
+/// Watches the bytes from `start` to `end` inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16,
+    pub kind: WatchKind,
+}
+
+impl Watchpoint {
+    pub fn new(start: u16, end: u16, kind: WatchKind) -> Self {
+        Self { start, end, kind }
+    }
+
+    pub fn matches(&self, address: u16, access: MemoryAccess) -> bool {
+        let kind_matches = matches!(
+            (self.kind, access),
+            (WatchKind::Access, _)
+                | (WatchKind::Read, MemoryAccess::Read)
+                | (WatchKind::Write, MemoryAccess::Write)
+        );
+        kind_matches && (self.start..=self.end).contains(&address)
+    }
+}
+
+/// A watched access noted by [`Memory`](crate::Memory).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WatchHit {
+    pub address: u16,
+    pub access: MemoryAccess,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// A place, or a condition, to pause at.
+///
+/// A breakpoint with no address is checked before every instruction, which
+/// with a condition such as `is DRW` stops on every sprite drawn. One with a
+/// `log` message is a tracepoint: it adds the message to the debugger's log
+/// and carries on instead of pausing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// Where it applies, or `None` for everywhere.
+    pub address: Option<u16>,
+    /// Only counts as hit when this holds.
+    pub condition: Option<Expression>,
+    /// Only pauses, or logs, from this hit on.
+    pub hit_count: Option<u32>,
+    pub log: Option<Template>,
+    id: u32,
+    hits: u32,
+}
+
+impl Breakpoint {
+    /// A breakpoint that always pauses at `address`.
+    pub fn at(address: u16) -> Self {
+        Breakpoint {
+            address: Some(address),
+            ..Breakpoint::default()
+        }
+    }
+
+    /// A breakpoint checked before every instruction, which wants a
+    /// condition.
+    pub fn everywhere() -> Self {
+        Breakpoint::default()
+    }
+
+    pub fn with_condition(mut self, condition: Expression) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    pub fn with_hit_count(mut self, hit_count: u32) -> Self {
+        self.hit_count = Some(hit_count);
+        self
+    }
+
+    /// Makes the breakpoint a tracepoint.
+    pub fn with_log(mut self, log: Template) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    /// Identifies the breakpoint once added to a debugger.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// How many times it has been reached with its condition holding.
+    pub fn hits(&self) -> u32 {
+        self.hits
+    }
+
+    fn is_plain(&self) -> bool {
+        self.condition.is_none() && self.hit_count.is_none() && self.log.is_none()
+    }
+}
+
+/// Where a step over, step out or run to ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Target {
+    /// The instruction after a call, once the call has returned.
+    StepOver {
+        address: u16,
+        depth: u8,
+    },
+    /// Anywhere the stack is shallower than `depth`.
+    StepOut {
+        depth: u8,
+    },
+    RunTo {
+        address: u16,
+    },
+}
+
+/// The breakpoints and execution state of an interpreter's debugger.
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    next_id: u32,
+    /// Messages from tracepoints, oldest first.
+    log: Vec<String>,
+    pause: Option<PauseReason>,
+    target: Option<Target>,
+    /// Set on resuming so that a breakpoint on the instruction execution
+    /// resumes from does not stop it straight away.
+    resuming: bool,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a breakpoint at `address`, returning false if there already was
+    /// one.
+    pub fn add_breakpoint(&mut self, address: u16) -> bool {
+        let exists = self
+            .breakpoints
+            .iter()
+            .any(|breakpoint| breakpoint.address == Some(address) && breakpoint.is_plain());
+        if !exists {
+            self.add(Breakpoint::at(address));
+        }
+        !exists
+    }
+
+    /// Removes every breakpoint at `address`, returning false if there
+    /// weren't any.
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints
+            .retain(|breakpoint| breakpoint.address != Some(address));
+        self.breakpoints.len() != count
+    }
+
+    /// Adds a breakpoint of any kind, returning its id.
+    pub fn add(&mut self, mut breakpoint: Breakpoint) -> u32 {
+        self.next_id += 1;
+        breakpoint.id = self.next_id;
+        breakpoint.hits = 0;
+        self.breakpoints.push(breakpoint);
+        self.next_id
+    }
+
+    /// Removes the breakpoint with `id`, returning false if there is none.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+        self.breakpoints.len() != count
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// The breakpoints, in the order they were added.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Takes the messages logged by tracepoints since the last call.
+    pub fn take_log(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_some()
+    }
+
+    /// Why the interpreter is paused, or `None` while it runs.
+    pub fn pause_reason(&self) -> Option<PauseReason> {
+        self.pause
+    }
+
+    pub(crate) fn pause(&mut self, reason: PauseReason) {
+        self.pause = Some(reason);
+        self.target = None;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.pause = None;
+        self.resuming = true;
+    }
+
+    pub(crate) fn resume_until_return_to(&mut self, address: u16, depth: u8) {
+        self.resume();
+        self.target = Some(Target::StepOver { address, depth });
+    }
+
+    pub(crate) fn resume_until_return(&mut self, depth: u8) {
+        self.resume();
+        self.target = Some(Target::StepOut { depth });
+    }
+
+    pub(crate) fn resume_until(&mut self, address: u16) {
+        self.resume();
+        self.target = Some(Target::RunTo { address });
+    }
+
+    /// Decides whether to stop before the instruction at `machine`'s
+    /// program counter. Returns true when paused.
+    pub(crate) fn should_pause(&mut self, machine: &dyn MachineState) -> bool {
+        if self.pause.is_some() {
+            return true;
+        }
+        let address = machine.program_counter();
+        let depth = machine.stack_pointer();
+        let resuming = std::mem::take(&mut self.resuming);
+        let reached = match self.target {
+            Some(Target::StepOver {
+                address: target,
+                depth: target_depth,
+            }) if address == target && depth <= target_depth => Some(PauseReason::Step),
+            Some(Target::StepOut {
+                depth: target_depth,
+            }) if depth < target_depth => Some(PauseReason::StepOut),
+            Some(Target::RunTo { address: target }) if address == target => {
+                Some(PauseReason::RunTo { address })
+            }
+            _ => None,
+        };
+        let reached = match reached {
+            None if !resuming && self.check_breakpoints(machine) => {
+                Some(PauseReason::Breakpoint { address })
+            }
+            reached => reached,
+        };
+        if let Some(reason) = reached {
+            self.pause(reason);
+        }
+        self.pause.is_some()
+    }
+
+    /// Whether a breakpoint that pauses, rather than logs, applies at
+    /// `machine`'s program counter, ignoring hit counts. Used when running
+    /// backwards, which counts no hits.
+    pub(crate) fn has_breakpoint(&self, machine: &dyn MachineState) -> bool {
+        let address = machine.program_counter();
+        self.breakpoints.iter().any(|breakpoint| {
+            breakpoint.log.is_none()
+                && breakpoint.address.is_none_or(|at| at == address)
+                && breakpoint
+                    .condition
+                    .as_ref()
+                    .is_none_or(|condition| condition.is_true(machine))
+        })
+    }
+
+    /// Counts a hit on every breakpoint that applies here and logs the
+    /// messages of tracepoints. Returns true if one of them pauses.
+    fn check_breakpoints(&mut self, machine: &dyn MachineState) -> bool {
+        let address = machine.program_counter();
+        let mut pause = false;
+        for breakpoint in &mut self.breakpoints {
+            if breakpoint.address.is_some_and(|at| at != address) {
+                continue;
+            }
+            if let Some(condition) = &breakpoint.condition {
+                if !condition.is_true(machine) {
+                    continue;
+                }
+            }
+            breakpoint.hits += 1;
+            if breakpoint.hits < breakpoint.hit_count.unwrap_or(0) {
+                continue;
+            }
+            match &breakpoint.log {
+                Some(message) => self.log.push(message.format(machine)),
+                None => pause = true,
+            }
+        }
+        pause
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debugger::Breakpoint;
+    use crate::debugger::Debugger;
+    use crate::debugger::MemoryAccess;
+    use crate::debugger::PauseReason;
+    use crate::debugger::WatchKind;
+    use crate::debugger::Watchpoint;
+    use crate::expression::Expression;
+    use crate::expression::MachineState;
+    use crate::expression::Template;
+    use crate::platform::Platform;
+
+    /// A machine about to execute the instruction at `program_counter`.
+    struct Position {
+        program_counter: u16,
+        stack_pointer: u8,
+        v0: u8,
+    }
+
+    impl MachineState for Position {
+        fn register(&self, x: u8) -> u8 {
+            if x == 0 {
+                self.v0
+            } else {
+                0
+            }
+        }
+        fn address_register(&self) -> u16 {
+            0
+        }
+        fn program_counter(&self) -> u16 {
+            self.program_counter
+        }
+        fn delay_timer(&self) -> u8 {
+            0
+        }
+        fn sound_timer(&self) -> u8 {
+            0
+        }
+        fn stack_pointer(&self) -> u8 {
+            self.stack_pointer
+        }
+        fn memory_byte(&self, _: u16) -> Option<u8> {
+            None
+        }
+        fn platform(&self) -> Platform {
+            Platform::Chip8
+        }
+    }
+
+    fn at(program_counter: u16, stack_pointer: u8) -> Position {
+        Position {
+            program_counter,
+            stack_pointer,
+            v0: 0,
+        }
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut debugger = Debugger::new();
+        assert!(debugger.add_breakpoint(0x204));
+        assert!(!debugger.add_breakpoint(0x204));
+        assert!(debugger.add_breakpoint(0x200));
+        let addresses: Vec<_> = debugger
+            .breakpoints()
+            .iter()
+            .map(|breakpoint| breakpoint.address)
+            .collect();
+        assert_eq!(addresses, vec![Some(0x204), Some(0x200)]);
+        assert!(!debugger.should_pause(&at(0x202, 0)));
+        assert!(debugger.should_pause(&at(0x204, 0)));
+        assert_eq!(
+            debugger.pause_reason(),
+            Some(PauseReason::Breakpoint { address: 0x204 })
+        );
+        // resuming executes the instruction under the breakpoint
+        debugger.resume();
+        assert!(!debugger.should_pause(&at(0x204, 0)));
+        assert!(debugger.should_pause(&at(0x204, 0)));
+        assert!(debugger.remove_breakpoint(0x204));
+        debugger.resume();
+        assert!(!debugger.should_pause(&at(0x204, 0)));
+        assert!(!debugger.should_pause(&at(0x204, 0)));
+    }
+
+    #[test]
+    fn test_targets() {
+        let mut debugger = Debugger::new();
+        debugger.resume_until_return_to(0x202, 1);
+        assert!(!debugger.should_pause(&at(0x202, 2)));
+        assert!(debugger.should_pause(&at(0x202, 1)));
+        assert_eq!(debugger.pause_reason(), Some(PauseReason::Step));
+        debugger.resume_until_return(1);
+        assert!(!debugger.should_pause(&at(0x300, 1)));
+        assert!(debugger.should_pause(&at(0x204, 0)));
+        assert_eq!(debugger.pause_reason(), Some(PauseReason::StepOut));
+        // a breakpoint on the way stops a run to early
+        debugger.add_breakpoint(0x206);
+        debugger.resume_until(0x208);
+        assert!(!debugger.should_pause(&at(0x204, 0)));
+        assert!(debugger.should_pause(&at(0x206, 0)));
+        assert_eq!(
+            debugger.pause_reason(),
+            Some(PauseReason::Breakpoint { address: 0x206 })
+        );
+        debugger.resume_until(0x208);
+        assert!(!debugger.should_pause(&at(0x206, 0)));
+        assert!(debugger.should_pause(&at(0x208, 0)));
+        assert_eq!(
+            debugger.pause_reason(),
+            Some(PauseReason::RunTo { address: 0x208 })
+        );
+    }
+
+    #[test]
+    fn test_conditional_breakpoints() {
+        let mut debugger = Debugger::new();
+        let id = debugger.add(
+            Breakpoint::everywhere()
+                .with_condition(Expression::parse("V0 == 3").unwrap())
+                .with_hit_count(2),
+        );
+        let mut position = at(0x200, 0);
+        assert!(!debugger.should_pause(&position));
+        position.v0 = 3;
+        assert!(!debugger.should_pause(&position));
+        assert_eq!(debugger.breakpoints()[0].hits(), 1);
+        position.program_counter = 0x202;
+        assert!(debugger.should_pause(&position));
+        assert_eq!(
+            debugger.pause_reason(),
+            Some(PauseReason::Breakpoint { address: 0x202 })
+        );
+        assert!(debugger.remove(id));
+        assert!(!debugger.remove(id));
+    }
+
+    #[test]
+    fn test_tracepoints() {
+        let mut debugger = Debugger::new();
+        debugger.add(Breakpoint::at(0x204).with_log(Template::parse("at {PC:x}").unwrap()));
+        assert!(!debugger.should_pause(&at(0x204, 0)));
+        assert!(!debugger.should_pause(&at(0x206, 0)));
+        assert!(!debugger.should_pause(&at(0x204, 0)));
+        assert_eq!(debugger.take_log(), vec!["at 0x204", "at 0x204"]);
+        assert!(debugger.take_log().is_empty());
+    }
+
+    #[test]
+    fn test_watchpoint_matches() {
+        let watchpoint = Watchpoint::new(0x300, 0x30F, WatchKind::Write);
+        assert!(watchpoint.matches(0x300, MemoryAccess::Write));
+        assert!(watchpoint.matches(0x30F, MemoryAccess::Write));
+        assert!(!watchpoint.matches(0x310, MemoryAccess::Write));
+        assert!(!watchpoint.matches(0x300, MemoryAccess::Read));
+        let watchpoint = Watchpoint::new(0x300, 0x300, WatchKind::Access);
+        assert!(watchpoint.matches(0x300, MemoryAccess::Read));
+        assert!(watchpoint.matches(0x300, MemoryAccess::Write));
+        assert_eq!(WatchKind::from_name("read"), Some(WatchKind::Read));
+        assert_eq!(WatchKind::from_name("execute"), None);
+    }
+}
@@ -0,0 +1,215 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::error::Chip8Error;
+use crate::instruction::Instruction;
+use crate::memory::Memory;
+use crate::platform::Platform;
+use crate::symbols::Symbols;
+use std::fmt;
+
+/// One line of a listing: an instruction, or bytes that aren't one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    /// The instruction, such as `LD V3, 0x1F`, or `DW`/`DB` and the bytes
+    /// when they don't decode.
+    pub text: String,
+    /// The symbol naming `address`, if any.
+    pub label: Option<String>,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes: Vec<String> = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        write!(
+            f,
+            "0x{:03X}  {:<11}  {}",
+            self.address,
+            bytes.join(" "),
+            self.text
+        )
+    }
+}
+
+/// Lists `bytes` as instructions for `platform`, taking the first byte to
+/// be at `address`. Words that aren't instructions are listed as data.
+pub fn disassemble(bytes: &[u8], address: u16, platform: Platform) -> Vec<Line> {
+    disassemble_with_symbols(bytes, address, platform, &Symbols::new())
+}
+
+/// Lists `bytes` like `disassemble`, labelling lines and naming the targets
+/// of jumps, calls and `LD I` with `symbols` where they have a name.
+pub fn disassemble_with_symbols(
+    bytes: &[u8],
+    address: u16,
+    platform: Platform,
+    symbols: &Symbols,
+) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let line_address = address.wrapping_add(offset as u16);
+        let word = |at: usize| {
+            bytes
+                .get(at..at + 2)
+                .map(|word| u16::from_be_bytes([word[0], word[1]]))
+        };
+        let code = match word(offset) {
+            Some(code) => code,
+            None => {
+                lines.push(Line {
+                    address: line_address,
+                    bytes: vec![bytes[offset]],
+                    text: format!("DB 0x{:02X}", bytes[offset]),
+                    label: symbols.name(line_address).map(str::to_string),
+                });
+                break;
+            }
+        };
+        let name = |address: u16| symbols.name(address);
+        let (size, text) = match Instruction::decode(code, platform) {
+            Ok(Instruction::LoadLongAddress) => match word(offset + 2) {
+                Some(address) => match name(address) {
+                    Some(name) => (4, format!("LD I, long {}", name)),
+                    None => (4, format!("LD I, 0x{:04X}", address)),
+                },
+                None => (2, format!("DW 0x{:04X}", code)),
+            },
+            Ok(Instruction::Jump { address }) if name(address).is_some() => {
+                (2, format!("JP {}", symbols.describe(address)))
+            }
+            Ok(Instruction::Call { address }) if name(address).is_some() => {
+                (2, format!("CALL {}", symbols.describe(address)))
+            }
+            Ok(Instruction::LoadAddress { address }) if name(address).is_some() => {
+                (2, format!("LD I, {}", symbols.describe(address)))
+            }
+            Ok(Instruction::JumpOffset { address }) if name(address).is_some() => {
+                (2, format!("JP V0, {}", symbols.describe(address)))
+            }
+            Ok(instruction) => (2, instruction.to_string()),
+            Err(_) => (2, format!("DW 0x{:04X}", code)),
+        };
+        lines.push(Line {
+            address: line_address,
+            bytes: bytes[offset..offset + size].to_vec(),
+            text,
+            label: symbols.name(line_address).map(str::to_string),
+        });
+        offset += size;
+    }
+    lines
+}
+
+/// Lists the `length` bytes of `memory` from `address`.
+pub fn disassemble_memory(
+    memory: &Memory,
+    address: u16,
+    length: usize,
+    platform: Platform,
+) -> Result<Vec<Line>, Chip8Error> {
+    let start = usize::from(address);
+    let bytes = memory
+        .bytes()
+        .get(start..)
+        .and_then(|rest| rest.get(..length))
+        .ok_or(Chip8Error::MemoryOutOfBounds {
+            address: start,
+            length,
+        })?;
+    Ok(disassemble(bytes, address, platform))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disasm::disassemble;
+    use crate::disasm::disassemble_memory;
+    use crate::disasm::disassemble_with_symbols;
+    use crate::memory::Memory;
+    use crate::platform::Platform;
+    use crate::symbols::Symbols;
+
+    fn listing(bytes: &[u8], platform: Platform) -> Vec<String> {
+        disassemble(bytes, 0x200, platform)
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(
+            listing(&[0x63, 0x1F, 0xD0, 0x15, 0x5A, 0xB1, 0x12], Platform::Chip8),
+            vec![
+                "0x200  63 1F        LD V3, 0x1F",
+                "0x202  D0 15        DRW V0, V1, 5",
+                "0x204  5A B1        DW 0x5AB1",
+                "0x206  12           DB 0x12",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_long_instruction() {
+        assert_eq!(
+            listing(&[0xF0, 0x00, 0x12, 0x34, 0x00, 0xE0], Platform::XoChip),
+            vec![
+                "0x200  F0 00 12 34  LD I, 0x1234",
+                "0x204  00 E0        CLS"
+            ]
+        );
+        assert_eq!(
+            listing(&[0xF0, 0x00], Platform::XoChip),
+            vec!["0x200  F0 00        DW 0xF000"]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_memory() {
+        let memory = Memory::new();
+        let lines = disassemble_memory(&memory, 0x200, 4, Platform::Chip8).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].address, 0x202);
+        assert!(disassemble_memory(&memory, 0xFFE, 4, Platform::Chip8).is_err());
+        assert!(disassemble_memory(&memory, 0x200, usize::MAX, Platform::Chip8).is_err());
+    }
+
+    #[test]
+    fn test_disassemble_with_symbols() {
+        let symbols = Symbols::parse("main 0x200\ndraw 0x206\nsprite 0x208\n").unwrap();
+        let lines = disassemble_with_symbols(
+            &[0x22, 0x06, 0x12, 0x00, 0x12, 0x04, 0xA2, 0x08, 0x00, 0xEE],
+            0x200,
+            Platform::Chip8,
+            &symbols,
+        );
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["CALL draw", "JP main", "JP 0x204", "LD I, sprite", "RET"]
+        );
+        assert_eq!(lines[0].label.as_deref(), Some("main"));
+        assert_eq!(lines[1].label, None);
+        assert_eq!(lines[4].label.as_deref(), Some("sprite"));
+    }
+}
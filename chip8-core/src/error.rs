@@ -0,0 +1,73 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::registers::Address;
+use std::fmt;
+
+/// The ways loading or running a program can fail.
+///
+/// Errors are returned from `Interpreter::run_iteration` instead of stopping
+/// the machine, so a host may report the error and halt, or carry on with the
+/// next instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Chip8Error {
+    /// The word at `address` is not a known instruction.
+    InvalidOpcode { opcode: u16, address: Address },
+    /// A CALL was made with all 16 stack frames in use.
+    StackOverflow,
+    /// A RET was made with no stack frames in use.
+    StackUnderflow,
+    /// An access of `length` bytes starting at `address` runs past the end of
+    /// memory.
+    MemoryOutOfBounds { address: usize, length: usize },
+    /// A ROM of `size` bytes does not fit in the `capacity` bytes available to
+    /// programs.
+    RomTooLarge { size: usize, capacity: usize },
+    /// A save state could not be read or describes an impossible machine.
+    InvalidSnapshot { reason: String },
+    /// An input movie could not be read or was recorded with another ROM.
+    InvalidMovie { reason: String },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::InvalidOpcode { opcode, address } => write!(
+                f,
+                "invalid opcode {:#06X} at {:#05X}",
+                opcode,
+                u16::from(*address)
+            ),
+            Chip8Error::StackOverflow => write!(f, "stack overflow"),
+            Chip8Error::StackUnderflow => write!(f, "return with an empty stack"),
+            Chip8Error::MemoryOutOfBounds { address, length } => write!(
+                f,
+                "memory access of {} bytes at {:#05X} is out of bounds",
+                length, address
+            ),
+            Chip8Error::RomTooLarge { size, capacity } => write!(
+                f,
+                "ROM is {} bytes but only {} bytes are available",
+                size, capacity
+            ),
+            Chip8Error::InvalidSnapshot { reason } => write!(f, "invalid save state: {}", reason),
+            Chip8Error::InvalidMovie { reason } => write!(f, "invalid movie: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
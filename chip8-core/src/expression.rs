@@ -0,0 +1,617 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Breakpoint conditions and tracepoint messages.
+//!
+//! Conditions are C-like expressions over the machine:
+//!
+//! ```text
+//! V3 == 0x10 && I > 0x300
+//! DT == 0
+//! mem[0x2F0] != 5
+//! is DRW              the next instruction is a DRW
+//! is 8xy6             the next instruction matches 8xy6
+//! ```
+//!
+//! `V0` to `VF`, `I`, `PC`, `DT`, `ST` and `SP` name the registers and
+//! `mem[address]` a byte of memory, which is 0 outside of it. Operators, from
+//! loosest to tightest, are `||`, `&&`, comparisons, `|`, `^`, `&`, `+` and
+//! `-`, then the prefixes `!` and `-`. Anything but 0 is true.
+
+use crate::instruction::Instruction;
+use crate::platform::Platform;
+use std::fmt;
+
+/// The parts of a machine an expression can look at.
+pub trait MachineState {
+    fn register(&self, x: u8) -> u8;
+    fn address_register(&self) -> u16;
+    fn program_counter(&self) -> u16;
+    fn delay_timer(&self) -> u8;
+    fn sound_timer(&self) -> u8;
+    fn stack_pointer(&self) -> u8;
+    /// The byte at `address`, or `None` outside of memory.
+    fn memory_byte(&self, address: u16) -> Option<u8>;
+    fn platform(&self) -> Platform;
+
+    /// The word at the program counter.
+    fn opcode(&self) -> u16 {
+        let address = self.program_counter();
+        let high = self.memory_byte(address).unwrap_or(0);
+        let low = self.memory_byte(address.wrapping_add(1)).unwrap_or(0);
+        u16::from_be_bytes([high, low])
+    }
+
+    /// Names `address` for messages, by default in hexadecimal.
+    fn describe_address(&self, address: u16) -> String {
+        format!("0x{:03X}", address)
+    }
+}
+
+/// Why a breakpoint condition or tracepoint message could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpressionError {
+    pub reason: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid expression: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// A parsed condition, which remembers its source for display.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expression {
+    source: String,
+    node: Node,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Number(i64),
+    Register(u8),
+    AddressRegister,
+    ProgramCounter,
+    DelayTimer,
+    SoundTimer,
+    StackPointer,
+    Memory(Box<Node>),
+    /// The next instruction's mnemonic is this one.
+    Mnemonic(String),
+    /// The next instruction's opcode matches, with `mask` selecting the
+    /// nibbles that are not placeholders.
+    Pattern {
+        mask: u16,
+        value: u16,
+    },
+    Not(Box<Node>),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Subtract,
+}
+
+/// The binary operators, loosest first.
+const PRECEDENCE: [&[(&str, Operator)]; 7] = [
+    &[("||", Operator::Or)],
+    &[("&&", Operator::And)],
+    &[
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ],
+    &[("|", Operator::BitOr)],
+    &[("^", Operator::BitXor)],
+    &[("&", Operator::BitAnd)],
+    &[("+", Operator::Add), ("-", Operator::Subtract)],
+];
+
+/// How deeply brackets and prefixes can nest, which keeps a hostile
+/// condition from overflowing the stack.
+const MAX_NESTING: usize = 64;
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Expression, ExpressionError> {
+        let mut parser = Parser {
+            source,
+            position: 0,
+            nesting: 0,
+        };
+        let node = parser.binary(0)?;
+        parser.skip_whitespace();
+        if parser.position < source.len() {
+            return Err(parser.error("unexpected text"));
+        }
+        Ok(Expression {
+            source: source.trim().to_string(),
+            node,
+        })
+    }
+
+    pub fn evaluate(&self, machine: &dyn MachineState) -> i64 {
+        evaluate(&self.node, machine)
+    }
+
+    pub fn is_true(&self, machine: &dyn MachineState) -> bool {
+        self.evaluate(machine) != 0
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn evaluate(node: &Node, machine: &dyn MachineState) -> i64 {
+    let truth = |value: bool| value as i64;
+    match node {
+        Node::Number(value) => *value,
+        Node::Register(x) => i64::from(machine.register(*x)),
+        Node::AddressRegister => i64::from(machine.address_register()),
+        Node::ProgramCounter => i64::from(machine.program_counter()),
+        Node::DelayTimer => i64::from(machine.delay_timer()),
+        Node::SoundTimer => i64::from(machine.sound_timer()),
+        Node::StackPointer => i64::from(machine.stack_pointer()),
+        Node::Memory(address) => u16::try_from(evaluate(address, machine))
+            .ok()
+            .and_then(|address| machine.memory_byte(address))
+            .map_or(0, i64::from),
+        Node::Mnemonic(mnemonic) => {
+            let instruction = Instruction::decode(machine.opcode(), machine.platform());
+            truth(instruction.is_ok_and(|instruction| {
+                instruction.to_string().split(' ').next() == Some(mnemonic.as_str())
+            }))
+        }
+        Node::Pattern { mask, value } => truth(machine.opcode() & mask == *value),
+        Node::Not(operand) => truth(evaluate(operand, machine) == 0),
+        Node::Negate(operand) => evaluate(operand, machine).wrapping_neg(),
+        Node::Binary(operator, left, right) => {
+            let left = evaluate(left, machine);
+            // the logical operators short-circuit
+            match operator {
+                Operator::Or if left != 0 => return 1,
+                Operator::And if left == 0 => return 0,
+                _ => {}
+            }
+            let right = evaluate(right, machine);
+            match operator {
+                Operator::Or | Operator::And => truth(right != 0),
+                Operator::Equal => truth(left == right),
+                Operator::NotEqual => truth(left != right),
+                Operator::Less => truth(left < right),
+                Operator::LessOrEqual => truth(left <= right),
+                Operator::Greater => truth(left > right),
+                Operator::GreaterOrEqual => truth(left >= right),
+                Operator::BitOr => left | right,
+                Operator::BitXor => left ^ right,
+                Operator::BitAnd => left & right,
+                Operator::Add => left.wrapping_add(right),
+                Operator::Subtract => left.wrapping_sub(right),
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+    /// How many calls of `unary` are in progress.
+    nesting: usize,
+}
+
+impl Parser<'_> {
+    fn binary(&mut self, level: usize) -> Result<Node, ExpressionError> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            self.skip_whitespace();
+            for (symbol, operator) in PRECEDENCE[level] {
+                // don't take the & of && or the | of ||
+                let doubled = symbol.len() == 1 && self.rest().starts_with(&symbol.repeat(2));
+                if self.rest().starts_with(symbol) && !doubled {
+                    self.position += symbol.len();
+                    let right = self.binary(level + 1)?;
+                    left = Node::Binary(*operator, Box::new(left), Box::new(right));
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, ExpressionError> {
+        if self.nesting == MAX_NESTING {
+            return Err(self.error("the expression is nested too deeply"));
+        }
+        self.nesting += 1;
+        let node = self.operand();
+        self.nesting -= 1;
+        node
+    }
+
+    /// A value with any prefixes, or a bracketed expression.
+    fn operand(&mut self) -> Result<Node, ExpressionError> {
+        self.skip_whitespace();
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("-") {
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let node = self.binary(0)?;
+            self.expect(")")?;
+            return Ok(node);
+        }
+        let word = self.word();
+        if word.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+        if word.starts_with(|c: char| c.is_ascii_digit()) {
+            return parse_number(&word)
+                .map(Node::Number)
+                .ok_or_else(|| self.error(&format!("'{}' is not a number", word)));
+        }
+        let node = match word.to_ascii_uppercase().as_str() {
+            "I" => Node::AddressRegister,
+            "PC" => Node::ProgramCounter,
+            "DT" => Node::DelayTimer,
+            "ST" => Node::SoundTimer,
+            "SP" => Node::StackPointer,
+            "MEM" => {
+                self.expect("[")?;
+                let address = self.binary(0)?;
+                self.expect("]")?;
+                Node::Memory(Box::new(address))
+            }
+            "IS" => {
+                self.skip_whitespace();
+                let operand = self.word();
+                parse_pattern(&operand)
+                    .or_else(|| {
+                        let mnemonic = operand.to_ascii_uppercase();
+                        (!mnemonic.is_empty()).then_some(Node::Mnemonic(mnemonic))
+                    })
+                    .ok_or_else(|| self.error("expected a mnemonic or opcode pattern"))?
+            }
+            register if register.len() == 2 && register.starts_with('V') => {
+                let x = u8::from_str_radix(&register[1..], 16)
+                    .map_err(|_| self.error(&format!("unknown register '{}'", word)))?;
+                Node::Register(x)
+            }
+            _ => return Err(self.error(&format!("unknown name '{}'", word))),
+        };
+        Ok(node)
+    }
+
+    /// The letters and digits from here on.
+    fn word(&mut self) -> String {
+        let length = self
+            .rest()
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(self.rest().len());
+        let word = self.rest()[..length].to_string();
+        self.position += length;
+        word
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(symbol) {
+            self.position += symbol.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ExpressionError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", symbol)))
+        }
+    }
+
+    fn error(&self, message: &str) -> ExpressionError {
+        ExpressionError {
+            reason: format!(
+                "{} at column {} of '{}'",
+                message,
+                self.position + 1,
+                self.source
+            ),
+        }
+    }
+}
+
+fn parse_number(word: &str) -> Option<i64> {
+    if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = word.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()
+    } else {
+        word.parse().ok()
+    }
+}
+
+/// Reads an opcode pattern such as `8xy6` or `Dxyn`, where `x`, `y`, `n` and
+/// `k` stand for any nibble. No mnemonic is a valid pattern.
+fn parse_pattern(word: &str) -> Option<Node> {
+    if word.len() != 4 {
+        return None;
+    }
+    let (mut mask, mut value) = (0, 0);
+    for c in word.chars() {
+        mask <<= 4;
+        value <<= 4;
+        if !matches!(c.to_ascii_lowercase(), 'x' | 'y' | 'n' | 'k') {
+            value |= c.to_digit(16)? as u16;
+            mask |= 0xF;
+        }
+    }
+    Some(Node::Pattern { mask, value })
+}
+
+/// A tracepoint message, with `{expression}` replaced by the expression's
+/// value in decimal, `{expression:x}` in hexadecimal, or `{expression:s}` as
+/// an address named by the nearest symbol. `{{` and `}}` stand for braces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Value {
+        expression: Expression,
+        format: Format,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Decimal,
+    Hex,
+    Symbol,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template, ExpressionError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => {
+                                return Err(ExpressionError {
+                                    reason: format!("unclosed '{{' in '{}'", source),
+                                })
+                            }
+                        }
+                    }
+                    let (inner, format) = match inner.rsplit_once(':') {
+                        Some((inner, "x")) => (inner, Format::Hex),
+                        Some((inner, "s")) => (inner, Format::Symbol),
+                        _ => (inner.as_str(), Format::Decimal),
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Value {
+                        expression: Expression::parse(inner)?,
+                        format,
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    pub fn format(&self, machine: &dyn MachineState) -> String {
+        let mut message = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => message.push_str(text),
+                Part::Value { expression, format } => {
+                    let value = expression.evaluate(machine);
+                    match format {
+                        Format::Decimal => message.push_str(&value.to_string()),
+                        Format::Hex => message.push_str(&format!("0x{:X}", value)),
+                        Format::Symbol => message.push_str(&machine.describe_address(value as u16)),
+                    }
+                }
+            }
+        }
+        message
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+    use crate::expression::ExpressionError;
+    use crate::expression::MachineState;
+    use crate::expression::Template;
+    use crate::platform::Platform;
+
+    struct TestMachine {
+        registers: [u8; 16],
+        memory: Vec<u8>,
+    }
+
+    impl MachineState for TestMachine {
+        fn register(&self, x: u8) -> u8 {
+            self.registers[usize::from(x)]
+        }
+        fn address_register(&self) -> u16 {
+            0x320
+        }
+        fn program_counter(&self) -> u16 {
+            0x200
+        }
+        fn delay_timer(&self) -> u8 {
+            0
+        }
+        fn sound_timer(&self) -> u8 {
+            4
+        }
+        fn stack_pointer(&self) -> u8 {
+            1
+        }
+        fn memory_byte(&self, address: u16) -> Option<u8> {
+            self.memory.get(usize::from(address)).copied()
+        }
+        fn platform(&self) -> Platform {
+            Platform::Chip8
+        }
+    }
+
+    fn machine() -> TestMachine {
+        let mut machine = TestMachine {
+            registers: [0; 16],
+            memory: vec![0; 0x1000],
+        };
+        machine.registers[3] = 0x10;
+        // 0x200: SHR V1, V2
+        machine.memory[0x200] = 0x81;
+        machine.memory[0x201] = 0x26;
+        machine.memory[0x2F0] = 5;
+        machine
+    }
+
+    fn evaluate(source: &str) -> i64 {
+        Expression::parse(source).unwrap().evaluate(&machine())
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("V3 == 0x10 && I > 0x300"), 1);
+        assert_eq!(evaluate("v3 == 0x10 && I > 0x400"), 0);
+        assert_eq!(evaluate("DT == 0"), 1);
+        assert_eq!(evaluate("mem[0x2F0] != 5"), 0);
+        assert_eq!(evaluate("mem[0x2E0 + 0x10] + 1"), 6);
+        assert_eq!(evaluate("mem[0xFFFFF]"), 0);
+        assert_eq!(evaluate("ST - SP - 3"), 0);
+        assert_eq!(evaluate("(V3 | 1) ^ 0b11"), 0x12);
+        assert_eq!(evaluate("!(PC == 0x200) || -1 < 0"), 1);
+    }
+
+    #[test]
+    fn test_instruction_matches() {
+        assert_eq!(evaluate("is SHR"), 1);
+        assert_eq!(evaluate("is drw"), 0);
+        assert_eq!(evaluate("is 8xy6"), 1);
+        assert_eq!(evaluate("is 8xy7"), 0);
+        assert_eq!(evaluate("is 8126"), 1);
+        assert_eq!(evaluate("is 00E0"), 0);
+        assert_eq!(evaluate("is 812n && V3 == 0x10"), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for source in ["", "V3 ==", "VG", "mem[1", "foo", "1 2", "is"] {
+            assert!(
+                matches!(Expression::parse(source), Err(ExpressionError { .. })),
+                "{}",
+                source
+            );
+        }
+        assert_eq!(
+            Expression::parse(" DT == 0 ").unwrap().to_string(),
+            "DT == 0"
+        );
+        assert_eq!(
+            evaluate(&format!("{}1{}", "(".repeat(20), ")".repeat(20))),
+            1
+        );
+        assert!(Expression::parse(&"(".repeat(200_000)).is_err());
+        assert!(Expression::parse(&format!("{}1", "!-".repeat(100_000))).is_err());
+    }
+
+    #[test]
+    fn test_template() {
+        let template = Template::parse("V3={V3:x} sum={V3 + 1} {{I}}").unwrap();
+        assert_eq!(template.format(&machine()), "V3=0x10 sum=17 {I}");
+        let template = Template::parse("at {PC:s}").unwrap();
+        assert_eq!(template.format(&machine()), "at 0x200");
+        assert!(Template::parse("{V3").is_err());
+        assert!(Template::parse("{V3 ==}").is_err());
+    }
+}
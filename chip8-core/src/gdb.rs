@@ -0,0 +1,661 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A GDB remote serial protocol stub, so GDB and front ends that speak its
+//! protocol can debug a running ROM over TCP.
+//!
+//! Registers are numbered V0 to VF (0 to 15), I (16), PC (17), SP (18), DT
+//! (19) and ST (20), as described by the `target.xml` the stub serves. I and
+//! PC are two bytes, big-endian like everything else on CHIP-8, and the rest
+//! are one byte. SP can be read but not written.
+
+use crate::chip8::Interpreter;
+use crate::clock::Clock;
+use crate::clock::FrameScheduler;
+use crate::clock::SystemClock;
+use crate::debugger::MemoryAccess;
+use crate::debugger::PauseReason;
+use crate::debugger::WatchKind;
+use crate::debugger::Watchpoint;
+use crate::error::Chip8Error;
+use crate::expression::MachineState;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpStream;
+use std::thread;
+
+/// The port GDB examples conventionally use.
+pub const DEFAULT_PORT: u16 = 1234;
+
+/// The byte GDB sends to interrupt a running target.
+const INTERRUPT: u8 = 0x03;
+
+/// The reply to `qSupported`.
+const SUPPORTED: &str =
+    "PacketSize=1000;qXfer:features:read+;QStartNoAckMode+;ReverseStep+;ReverseContinue+";
+
+const REGISTER_COUNT: usize = 21;
+const I: usize = 16;
+const PC: usize = 17;
+const SP: usize = 18;
+const DT: usize = 19;
+const ST: usize = 20;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.chip8.core">
+    <reg name="v0" bitsize="8" type="uint8"/>
+    <reg name="v1" bitsize="8" type="uint8"/>
+    <reg name="v2" bitsize="8" type="uint8"/>
+    <reg name="v3" bitsize="8" type="uint8"/>
+    <reg name="v4" bitsize="8" type="uint8"/>
+    <reg name="v5" bitsize="8" type="uint8"/>
+    <reg name="v6" bitsize="8" type="uint8"/>
+    <reg name="v7" bitsize="8" type="uint8"/>
+    <reg name="v8" bitsize="8" type="uint8"/>
+    <reg name="v9" bitsize="8" type="uint8"/>
+    <reg name="va" bitsize="8" type="uint8"/>
+    <reg name="vb" bitsize="8" type="uint8"/>
+    <reg name="vc" bitsize="8" type="uint8"/>
+    <reg name="vd" bitsize="8" type="uint8"/>
+    <reg name="ve" bitsize="8" type="uint8"/>
+    <reg name="vf" bitsize="8" type="uint8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="dt" bitsize="8" type="uint8"/>
+    <reg name="st" bitsize="8" type="uint8"/>
+  </feature>
+</target>
+"#;
+
+/// Something GDB sent.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A packet whose checksum matched, without its framing.
+    Packet(String),
+    /// A packet whose checksum didn't match, which GDB will resend.
+    BadChecksum,
+    /// A request to stop the running target.
+    Interrupt,
+}
+
+/// Splits the bytes GDB sends into packets, acknowledgements being
+/// skipped.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    state: DecoderState,
+    data: Vec<u8>,
+    checksum: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+enum DecoderState {
+    #[default]
+    Idle,
+    Data,
+    Checksum,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the next byte, returning an event once one is complete.
+    pub fn push(&mut self, byte: u8) -> Option<Event> {
+        match self.state {
+            DecoderState::Idle => match byte {
+                b'$' => {
+                    self.data.clear();
+                    self.checksum.clear();
+                    self.state = DecoderState::Data;
+                    None
+                }
+                INTERRUPT => Some(Event::Interrupt),
+                _ => None,
+            },
+            DecoderState::Data => {
+                match byte {
+                    b'#' => self.state = DecoderState::Checksum,
+                    _ => self.data.push(byte),
+                }
+                None
+            }
+            DecoderState::Checksum => {
+                self.checksum.push(byte);
+                if self.checksum.len() < 2 {
+                    return None;
+                }
+                self.state = DecoderState::Idle;
+                let expected = std::str::from_utf8(&self.checksum)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                if expected != Some(checksum(&self.data)) {
+                    return Some(Event::BadChecksum);
+                }
+                Some(Event::Packet(
+                    String::from_utf8_lossy(&self.data).into_owned(),
+                ))
+            }
+        }
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Frames `data` as a packet.
+pub fn encode(data: &str) -> Vec<u8> {
+    format!("${}#{:02x}", data, checksum(data.as_bytes())).into_bytes()
+}
+
+/// What to do after a packet.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Send this reply.
+    Reply(String),
+    /// Run until the machine stops, then send a stop reply.
+    Continue,
+    /// Reply OK and end the session, leaving the machine running.
+    Detach,
+    /// End the session.
+    Kill,
+}
+
+/// Carries out a packet on `interpreter`, which should be paused.
+pub fn handle(packet: &str, interpreter: &mut Interpreter) -> Action {
+    let reply = |reply: &str| Action::Reply(reply.to_string());
+    let (command, arguments) = match packet.char_indices().nth(1) {
+        Some((index, _)) => packet.split_at(index),
+        None => (packet, ""),
+    };
+    match command {
+        "?" => Action::Reply(stop_reply(interpreter, &Ok(()))),
+        "g" => Action::Reply(read_registers(interpreter)),
+        "G" => reply_result(write_registers(interpreter, arguments)),
+        "p" => match number(arguments).and_then(|register| read_register(interpreter, register)) {
+            Some(value) => Action::Reply(value),
+            None => reply("E01"),
+        },
+        "P" => reply_result(write_register(interpreter, arguments)),
+        "m" => match read_memory(interpreter, arguments) {
+            Some(bytes) => Action::Reply(bytes),
+            None => reply("E01"),
+        },
+        "M" => reply_result(write_memory(interpreter, arguments)),
+        "Z" | "z" => reply_result(set_stop_point(interpreter, arguments, command == "Z")),
+        "c" | "s" => {
+            if !arguments.is_empty() {
+                match number(arguments) {
+                    Some(address) => interpreter.set_program_counter(address as u16),
+                    None => return reply("E01"),
+                }
+            }
+            if command == "s" {
+                let result = interpreter.step();
+                Action::Reply(stop_reply(interpreter, &result))
+            } else {
+                interpreter.resume();
+                Action::Continue
+            }
+        }
+        "b" => {
+            match arguments {
+                "s" => interpreter.step_back(),
+                "c" => interpreter.reverse_continue(),
+                _ => return reply(""),
+            };
+            Action::Reply(stop_reply(interpreter, &Ok(())))
+        }
+        "D" => Action::Detach,
+        "k" => Action::Kill,
+        "H" => reply("OK"),
+        _ => match packet {
+            "qSupported" => reply(SUPPORTED),
+            "QStartNoAckMode" => reply("OK"),
+            "qAttached" => reply("1"),
+            "qfThreadInfo" => reply("m1"),
+            "qsThreadInfo" => reply("l"),
+            "qC" => reply("QC1"),
+            _ if packet.starts_with("qSupported:") => reply(SUPPORTED),
+            _ => match packet.strip_prefix("qXfer:features:read:target.xml:") {
+                Some(range) => match read_target_xml(range) {
+                    Some(chunk) => Action::Reply(chunk),
+                    None => reply("E01"),
+                },
+                // an empty reply tells GDB the packet isn't supported
+                None => reply(""),
+            },
+        },
+    }
+}
+
+fn reply_result(result: Option<()>) -> Action {
+    match result {
+        Some(()) => Action::Reply("OK".to_string()),
+        None => Action::Reply("E01".to_string()),
+    }
+}
+
+/// The reply telling GDB why the machine stopped, `result` being what the
+/// last step or frame returned.
+pub fn stop_reply(interpreter: &Interpreter, result: &Result<(), Chip8Error>) -> String {
+    if interpreter.is_halted() {
+        return "W00".to_string();
+    }
+    match result {
+        Err(Chip8Error::InvalidOpcode { .. }) => return "S04".to_string(),
+        Err(Chip8Error::MemoryOutOfBounds { .. }) => return "S0b".to_string(),
+        Err(_) => return "S06".to_string(),
+        Ok(()) => {}
+    }
+    match interpreter.debugger().pause_reason() {
+        Some(PauseReason::Requested) => "S02".to_string(),
+        Some(PauseReason::HistoryStart) => "T05replaylog:begin;".to_string(),
+        Some(PauseReason::Watchpoint {
+            address, access, ..
+        }) => {
+            let kind = interpreter
+                .watchpoints()
+                .iter()
+                .find(|watchpoint| watchpoint.matches(address, access))
+                .map(|watchpoint| watchpoint.kind);
+            let name = match (kind, access) {
+                (Some(WatchKind::Access), _) => "awatch",
+                (_, MemoryAccess::Read) => "rwatch",
+                (_, MemoryAccess::Write) => "watch",
+            };
+            format!("T05{}:{:04x};", name, address)
+        }
+        _ => "S05".to_string(),
+    }
+}
+
+/// Reads a hexadecimal number.
+fn number(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// The bytes of a register as GDB expects them.
+fn register_bytes(interpreter: &Interpreter, register: usize) -> Option<Vec<u8>> {
+    match register {
+        0..=15 => Some(vec![interpreter.register(register as u8)]),
+        I => Some(interpreter.address_register().to_be_bytes().to_vec()),
+        PC => Some(interpreter.program_counter().to_be_bytes().to_vec()),
+        SP => Some(vec![interpreter.stack_pointer()]),
+        DT => Some(vec![interpreter.delay_timer()]),
+        ST => Some(vec![interpreter.sound_timer()]),
+        _ => None,
+    }
+}
+
+fn register_size(register: usize) -> usize {
+    match register {
+        I | PC => 2,
+        _ => 1,
+    }
+}
+
+fn set_register(interpreter: &mut Interpreter, register: usize, bytes: &[u8]) -> Option<()> {
+    let word = || match bytes {
+        &[high, low] => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    };
+    match (register, bytes) {
+        (0..=15, &[value]) => interpreter.set_register(register as u8, value),
+        (I, _) => interpreter.set_address_register(word()?),
+        (PC, _) => interpreter.set_program_counter(word()?),
+        (SP, &[value]) if value == interpreter.stack_pointer() => {}
+        (DT, &[value]) => interpreter.set_delay_timer(value),
+        (ST, &[value]) => interpreter.set_sound_timer(value),
+        _ => return None,
+    }
+    Some(())
+}
+
+fn read_registers(interpreter: &Interpreter) -> String {
+    (0..REGISTER_COUNT)
+        .filter_map(|register| register_bytes(interpreter, register))
+        .map(|bytes| hex(&bytes))
+        .collect()
+}
+
+fn write_registers(interpreter: &mut Interpreter, arguments: &str) -> Option<()> {
+    let bytes = unhex(arguments)?;
+    let mut rest = &bytes[..];
+    for register in 0..REGISTER_COUNT {
+        let size = register_size(register);
+        if rest.len() < size {
+            return None;
+        }
+        let (value, tail) = rest.split_at(size);
+        set_register(interpreter, register, value)?;
+        rest = tail;
+    }
+    Some(())
+}
+
+fn read_register(interpreter: &Interpreter, register: usize) -> Option<String> {
+    register_bytes(interpreter, register).map(|bytes| hex(&bytes))
+}
+
+fn write_register(interpreter: &mut Interpreter, arguments: &str) -> Option<()> {
+    let (register, value) = arguments.split_once('=')?;
+    set_register(interpreter, number(register)?, &unhex(value)?)
+}
+
+/// Reads `ADDRESS,LENGTH`.
+fn range(arguments: &str) -> Option<(usize, usize)> {
+    let (address, length) = arguments.split_once(',')?;
+    Some((number(address)?, number(length)?))
+}
+
+/// Reads memory without setting off watchpoints, stopping at the end of
+/// memory.
+fn read_memory(interpreter: &Interpreter, arguments: &str) -> Option<String> {
+    let (address, length) = range(arguments)?;
+    let bytes = interpreter.memory().bytes();
+    let end = address.saturating_add(length).min(bytes.len());
+    bytes.get(address..end).map(hex)
+}
+
+fn write_memory(interpreter: &mut Interpreter, arguments: &str) -> Option<()> {
+    let (range_text, data) = arguments.split_once(':')?;
+    let (address, length) = range(range_text)?;
+    let bytes = unhex(data)?;
+    if bytes.len() != length {
+        return None;
+    }
+    interpreter
+        .write_memory(u16::try_from(address).ok()?, &bytes)
+        .ok()
+}
+
+/// Adds (`Z`) or removes (`z`) a breakpoint or watchpoint from
+/// `TYPE,ADDRESS,KIND`. Software and hardware breakpoints are the same
+/// thing here, and a watchpoint's kind is the number of bytes it covers.
+fn set_stop_point(interpreter: &mut Interpreter, arguments: &str, add: bool) -> Option<()> {
+    let mut fields = arguments.splitn(3, ',');
+    let kind = fields.next()?;
+    let address = u16::try_from(number(fields.next()?)?).ok()?;
+    let length = number(fields.next()?.split(';').next()?)?;
+    let watch_kind = match kind {
+        "0" | "1" => {
+            let debugger = interpreter.debugger_mut();
+            match add {
+                true => debugger.add_breakpoint(address),
+                false => debugger.remove_breakpoint(address),
+            };
+            return Some(());
+        }
+        "2" => WatchKind::Write,
+        "3" => WatchKind::Read,
+        "4" => WatchKind::Access,
+        _ => return None,
+    };
+    let end = address.checked_add(u16::try_from(length.max(1) - 1).ok()?)?;
+    let watchpoint = Watchpoint::new(address, end, watch_kind);
+    match add {
+        true => interpreter.add_watchpoint(watchpoint),
+        false => {
+            interpreter.remove_watchpoint(&watchpoint);
+        }
+    }
+    Some(())
+}
+
+/// Answers `OFFSET,LENGTH` of a `qXfer:features:read` request.
+fn read_target_xml(arguments: &str) -> Option<String> {
+    let (offset, length) = range(arguments)?;
+    let rest = TARGET_XML.get(offset..)?;
+    match rest.len() > length {
+        true => Some(format!("m{}", &rest[..length])),
+        false => Some(format!("l{}", rest)),
+    }
+}
+
+/// Debugs `interpreter` over `stream` until GDB detaches, kills the target
+/// or disconnects. The machine is paused while GDB is in control and runs
+/// at normal speed after a continue, until it stops or GDB interrupts it.
+pub fn serve(mut stream: TcpStream, interpreter: &mut Interpreter) -> io::Result<()> {
+    interpreter.pause();
+    let mut decoder = Decoder::new();
+    let mut acknowledge = true;
+    let mut buffer = [0; 4096];
+    loop {
+        let count = stream.read(&mut buffer)?;
+        if count == 0 {
+            return Ok(());
+        }
+        for &byte in &buffer[..count] {
+            let packet = match decoder.push(byte) {
+                Some(Event::Packet(packet)) => packet,
+                Some(Event::BadChecksum) => {
+                    if acknowledge {
+                        stream.write_all(b"-")?;
+                    }
+                    continue;
+                }
+                // the machine is already stopped
+                Some(Event::Interrupt) | None => continue,
+            };
+            if acknowledge {
+                stream.write_all(b"+")?;
+            }
+            let reply = match handle(&packet, interpreter) {
+                Action::Reply(reply) => reply,
+                Action::Continue => run(&mut stream, interpreter)?,
+                Action::Detach => {
+                    stream.write_all(&encode("OK"))?;
+                    interpreter.resume();
+                    return Ok(());
+                }
+                Action::Kill => return Ok(()),
+            };
+            stream.write_all(&encode(&reply))?;
+            if packet == "QStartNoAckMode" {
+                acknowledge = false;
+            }
+        }
+    }
+}
+
+/// Runs frames in real time until the machine stops or GDB interrupts it,
+/// returning the stop reply.
+fn run(stream: &mut TcpStream, interpreter: &mut Interpreter) -> io::Result<String> {
+    let clock = SystemClock::new();
+    let mut scheduler = FrameScheduler::new(clock.now());
+    stream.set_nonblocking(true)?;
+    let result = loop {
+        let mut byte = [0];
+        match stream.read(&mut byte) {
+            Ok(0) => {
+                interpreter.pause();
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(_) if byte[0] == INTERRUPT => interpreter.pause(),
+            Ok(_) => {}
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {}
+            Err(error) => {
+                stream.set_nonblocking(false)?;
+                return Err(error);
+            }
+        }
+        let mut result = Ok(());
+        for _ in 0..scheduler.frames_due(clock.now()) {
+            result = interpreter.run_frame();
+            if result.is_err() || interpreter.is_halted() {
+                break;
+            }
+        }
+        if result.is_err() || interpreter.is_halted() || interpreter.debugger().is_paused() {
+            break result;
+        }
+        thread::sleep(scheduler.time_until_next_frame(clock.now()));
+    };
+    stream.set_nonblocking(false)?;
+    let reply = stop_reply(interpreter, &result);
+    interpreter.pause();
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio::NullBuzzer;
+    use crate::debugger::WatchKind;
+    use crate::debugger::Watchpoint;
+    use crate::gdb::encode;
+    use crate::gdb::handle;
+    use crate::gdb::Action;
+    use crate::gdb::Decoder;
+    use crate::gdb::Event;
+    use crate::graphics::ConsoleDisplay;
+    use crate::movie::PlaybackKeyboard;
+    use crate::Interpreter;
+
+    fn paused_interpreter(rom: &[u8]) -> Interpreter {
+        let mut interpreter = Interpreter::new(
+            Box::new(ConsoleDisplay::quiet()),
+            Box::new(NullBuzzer),
+            Box::new(PlaybackKeyboard::from_frames(vec![])),
+            rom,
+        )
+        .unwrap();
+        interpreter.pause();
+        interpreter
+    }
+
+    fn reply(packet: &str, interpreter: &mut Interpreter) -> String {
+        match handle(packet, interpreter) {
+            Action::Reply(reply) => reply,
+            action => panic!("expected a reply to {}, got {:?}", packet, action),
+        }
+    }
+
+    #[test]
+    fn decoder() {
+        let mut decoder = Decoder::new();
+        let mut events = vec![];
+        for byte in b"+$g#67\x03$m200,2#00".iter().chain(&encode("?")) {
+            events.extend(decoder.push(*byte));
+        }
+        assert_eq!(
+            events,
+            vec![
+                Event::Packet("g".to_string()),
+                Event::Interrupt,
+                Event::BadChecksum,
+                Event::Packet("?".to_string()),
+            ]
+        );
+        assert_eq!(encode("OK"), b"$OK#9a");
+    }
+
+    #[test]
+    fn registers_and_memory() {
+        let mut interpreter = paused_interpreter(&[0x60, 0x01, 0xA3, 0x00]);
+        interpreter.set_register(0xF, 0xAB);
+        assert_eq!(
+            reply("g", &mut interpreter),
+            format!("{}ab00000200000000", "00".repeat(15))
+        );
+        assert_eq!(reply("p11", &mut interpreter), "0200");
+        assert_eq!(reply("P10=0345", &mut interpreter), "OK");
+        assert_eq!(interpreter.address_register(), 0x345);
+        let registers = format!("{}0000{}", "01".repeat(16), "0202000304");
+        assert_eq!(reply(&format!("G{}", registers), &mut interpreter), "OK");
+        assert_eq!(interpreter.register(0xA), 1);
+        assert_eq!(interpreter.program_counter(), 0x202);
+        assert_eq!(interpreter.sound_timer(), 4);
+        // SP is read only
+        assert_eq!(reply("P12=01", &mut interpreter), "E01");
+
+        assert_eq!(reply("m200,4", &mut interpreter), "6001a300");
+        assert_eq!(reply("M300,2:beef", &mut interpreter), "OK");
+        assert_eq!(reply("m300,2", &mut interpreter), "beef");
+        assert_eq!(reply("m10000,1", &mut interpreter), "E01");
+        assert_eq!(reply("M300,2:be", &mut interpreter), "E01");
+    }
+
+    #[test]
+    fn stop_points_and_stepping() {
+        // 0x200: LD V0, 1
+        // 0x202: LD I, 0x300
+        // 0x204: LD [I], V0
+        // 0x206: JP 0x206
+        let mut interpreter = paused_interpreter(&[0x60, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06]);
+        assert_eq!(reply("?", &mut interpreter), "S02");
+        assert_eq!(reply("Z0,204,2", &mut interpreter), "OK");
+        assert_eq!(reply("Z2,300,1", &mut interpreter), "OK");
+        assert_eq!(
+            interpreter.watchpoints(),
+            &[Watchpoint::new(0x300, 0x300, WatchKind::Write)]
+        );
+        assert_eq!(reply("s", &mut interpreter), "S05");
+        assert_eq!(interpreter.program_counter(), 0x202);
+
+        assert_eq!(handle("c", &mut interpreter), Action::Continue);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x204);
+        assert_eq!(reply("?", &mut interpreter), "S05");
+        assert_eq!(reply("z0,204,2", &mut interpreter), "OK");
+        assert!(interpreter.debugger().breakpoints().is_empty());
+
+        assert_eq!(handle("c", &mut interpreter), Action::Continue);
+        interpreter.run_frame().unwrap();
+        assert_eq!(interpreter.program_counter(), 0x206);
+        assert_eq!(reply("?", &mut interpreter), "T05watch:0300;");
+        assert_eq!(reply("bs", &mut interpreter), "S05");
+        assert_eq!(interpreter.program_counter(), 0x204);
+        assert_eq!(reply("bc", &mut interpreter), "T05replaylog:begin;");
+        assert_eq!(interpreter.program_counter(), 0x200);
+        assert_eq!(reply("z2,300,1", &mut interpreter), "OK");
+        assert!(interpreter.watchpoints().is_empty());
+    }
+
+    #[test]
+    fn queries() {
+        let mut interpreter = paused_interpreter(&[]);
+        assert!(
+            reply("qSupported:multiprocess+", &mut interpreter).contains("qXfer:features:read+")
+        );
+        let start = reply("qXfer:features:read:target.xml:0,a", &mut interpreter);
+        assert_eq!(start, "m<?xml vers");
+        let end = reply("qXfer:features:read:target.xml:400,1000", &mut interpreter);
+        assert!(end.starts_with('l') && end.ends_with("</target>\n"));
+        assert_eq!(reply("vMustReplyEmpty", &mut interpreter), "");
+        assert_eq!(handle("k", &mut interpreter), Action::Kill);
+    }
+}
@@ -0,0 +1,578 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The width of the screen in low resolution mode.
+pub const WIDTH: usize = 64;
+/// The height of the screen in low resolution mode.
+pub const HEIGHT: usize = 32;
+/// The width of the screen in SUPER-CHIP high resolution mode.
+pub const HIGH_RESOLUTION_WIDTH: usize = 128;
+/// The height of the screen in SUPER-CHIP high resolution mode.
+pub const HIGH_RESOLUTION_HEIGHT: usize = 64;
+/// The number of XO-CHIP bit planes. Each pixel holds one bit per plane,
+/// giving four colours.
+pub const PLANES: usize = 2;
+
+/// The bytes of a sprite. Sprites are 8 pixels wide with one byte per row,
+/// or 16 pixels wide with two bytes per row for SUPER-CHIP's 16x16 sprites.
+pub struct Sprite {
+    bytes: std::vec::Vec<u8>,
+    width: usize,
+}
+
+impl Sprite {
+    /// Creates a 16 pixel wide sprite from pairs of bytes.
+    pub fn wide(bytes: &[u8]) -> Sprite {
+        Sprite {
+            bytes: std::vec::Vec::from(bytes),
+            width: 16,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, u8> {
+        self.bytes.iter()
+    }
+
+    /// The width of the sprite in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the sprite in pixels.
+    pub fn height(&self) -> usize {
+        self.bytes.len() / (self.width / 8)
+    }
+
+    /// Returns whether the pixel at column `x` of row `y` is set.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        let byte = self.bytes[y * (self.width / 8) + x / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+}
+
+impl From<&[u8]> for Sprite {
+    fn from(s: &[u8]) -> Sprite {
+        Sprite {
+            bytes: std::vec::Vec::from(s),
+            width: 8,
+        }
+    }
+}
+
+/// The pixels on the screen, in either resolution.
+///
+/// Each pixel is a colour from 0 to 3 made of one bit per plane, with the
+/// first plane in bit 0. Drawing, clearing and scrolling only touch the
+/// selected planes. Only the first plane is selected unless an XO-CHIP
+/// program selects others.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    planes: u8,
+}
+
+impl FrameBuffer {
+    /// Creates a blank low resolution screen.
+    pub fn new() -> Self {
+        Self {
+            width: WIDTH,
+            height: HEIGHT,
+            pixels: vec![0; WIDTH * HEIGHT],
+            planes: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_high_resolution(&self) -> bool {
+        self.width == HIGH_RESOLUTION_WIDTH
+    }
+
+    /// Switches between 64x32 and 128x64 pixels. The screen is cleared.
+    pub fn set_high_resolution(&mut self, high_resolution: bool) {
+        if high_resolution {
+            self.width = HIGH_RESOLUTION_WIDTH;
+            self.height = HIGH_RESOLUTION_HEIGHT;
+        } else {
+            self.width = WIDTH;
+            self.height = HEIGHT;
+        }
+        self.pixels = vec![0; self.width * self.height];
+    }
+
+    /// Returns true if the buffer has one of the two resolutions, a pixel
+    /// for every position and only colours and planes that exist. Buffers
+    /// read from a save state may not.
+    pub fn is_valid(&self) -> bool {
+        let resolution = (self.width, self.height);
+        (resolution == (WIDTH, HEIGHT)
+            || resolution == (HIGH_RESOLUTION_WIDTH, HIGH_RESOLUTION_HEIGHT))
+            && self.pixels.len() == self.width * self.height
+            && self
+                .pixels
+                .iter()
+                .all(|pixel| usize::from(*pixel) < 1 << PLANES)
+            && usize::from(self.planes) < 1 << PLANES
+    }
+
+    /// The bit mask of the planes that drawing, clearing and scrolling
+    /// affect.
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
+
+    /// Selects the planes by bit mask, as XO-CHIP's FN01 does.
+    pub fn select_planes(&mut self, planes: u8) {
+        self.planes = planes & ((1 << PLANES) - 1);
+    }
+
+    /// The number of selected planes, which is how many sprites `draw`
+    /// expects.
+    pub fn plane_count(&self) -> usize {
+        self.planes.count_ones() as usize
+    }
+
+    /// The colour of every pixel row by row, starting at the top left.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub(crate) fn pixels_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.pixels
+    }
+
+    /// Returns whether the pixel is on in any plane.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.colour(x, y) != 0
+    }
+
+    pub fn colour(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Turns every pixel off in the selected planes.
+    pub fn clear(&mut self) {
+        let keep = !self.planes;
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= keep);
+    }
+
+    /// XORs `sprite` onto the screen at (`x`, `y`), returning 1 if any pixel
+    /// was turned off and 0 otherwise.
+    ///
+    /// The sprite is split evenly between the selected planes, so with both
+    /// planes selected the first half is drawn to plane 1 and the second
+    /// half to plane 2.
+    ///
+    /// The starting position always wraps around the screen. Parts of the
+    /// sprite that cross the edge are cut off when `clip` is set and wrap
+    /// around to the opposite edge otherwise.
+    pub fn draw(&mut self, x: usize, y: usize, sprite: &Sprite, clip: bool) -> u8 {
+        let plane_count = self.plane_count();
+        if plane_count == 0 {
+            return 0;
+        }
+        let length = sprite.len() / plane_count;
+        let mut collision = 0;
+        let selected = self.planes;
+        let planes = (0..PLANES as u8)
+            .map(|plane| 1 << plane)
+            .filter(move |plane| selected & plane != 0);
+        for (index, plane) in planes.enumerate() {
+            let part = Sprite {
+                bytes: sprite.bytes[index * length..(index + 1) * length].to_vec(),
+                width: sprite.width,
+            };
+            collision |= self.draw_plane(x, y, &part, clip, plane);
+        }
+        collision
+    }
+
+    fn draw_plane(&mut self, x: usize, y: usize, sprite: &Sprite, clip: bool, plane: u8) -> u8 {
+        let x = x % self.width;
+        let y = y % self.height;
+        let mut collision = 0;
+        for row in 0..sprite.height() {
+            let target_y = y + row;
+            if clip && target_y >= self.height {
+                break;
+            }
+            let target_y = target_y % self.height;
+            for column in 0..sprite.width() {
+                let target_x = x + column;
+                if clip && target_x >= self.width {
+                    break;
+                }
+                let target_x = target_x % self.width;
+                if sprite.pixel(column, row) {
+                    let target = &mut self.pixels[target_y * self.width + target_x];
+                    if *target & plane != 0 {
+                        collision = 1;
+                    }
+                    *target ^= plane;
+                }
+            }
+        }
+        collision
+    }
+
+    /// Moves the selected planes down by `rows` pixels, blanking the rows
+    /// uncovered at the top.
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.shift(0, rows as isize);
+    }
+
+    /// Moves the selected planes right by `columns` pixels, blanking the
+    /// columns uncovered on the left.
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.shift(columns as isize, 0);
+    }
+
+    /// Moves the selected planes left by `columns` pixels, blanking the
+    /// columns uncovered on the right.
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.shift(-(columns as isize), 0);
+    }
+
+    fn shift(&mut self, columns: isize, rows: isize) {
+        let source = self.pixels.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let source_x = x as isize - columns;
+                let source_y = y as isize - rows;
+                let moved = if (0..self.width as isize).contains(&source_x)
+                    && (0..self.height as isize).contains(&source_y)
+                {
+                    source[source_y as usize * self.width + source_x as usize] & self.planes
+                } else {
+                    0
+                };
+                let pixel = &mut self.pixels[y * self.width + x];
+                *pixel = (*pixel & !self.planes) | moved;
+            }
+        }
+    }
+
+    /// Renders the screen as lines of text with a space for colour 0, `*`
+    /// for colour 1, `+` for colour 2 and `#` for colour 3.
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width) {
+            for pixel in row {
+                text.push([' ', '*', '+', '#'][usize::from(*pixel & 3)]);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The screen.
+///
+/// The pixels live in a `FrameBuffer` owned by the implementation, and the
+/// provided methods draw into it. Implementations only need to give access
+/// to the buffer and present it in `refresh`, which the interpreter calls at
+/// the end of every frame.
+pub trait Display: Send + Sync {
+    fn frame_buffer(&self) -> &FrameBuffer;
+    fn frame_buffer_mut(&mut self) -> &mut FrameBuffer;
+    /// Presents the current contents of the screen.
+    fn refresh(&mut self);
+
+    /// Turns every pixel off.
+    fn clear(&mut self) {
+        self.frame_buffer_mut().clear();
+    }
+
+    /// XORs `sprite` onto the screen at (`x`, `y`), returning 1 if any pixel
+    /// was turned off and 0 otherwise. See `FrameBuffer::draw`.
+    fn draw(&mut self, x: u8, y: u8, sprite: &Sprite, clip: bool) -> u8 {
+        self.frame_buffer_mut()
+            .draw(usize::from(x), usize::from(y), sprite, clip)
+    }
+
+    fn set_high_resolution(&mut self, high_resolution: bool) {
+        self.frame_buffer_mut().set_high_resolution(high_resolution);
+    }
+
+    fn select_planes(&mut self, planes: u8) {
+        self.frame_buffer_mut().select_planes(planes);
+    }
+
+    fn scroll_down(&mut self, rows: usize) {
+        self.frame_buffer_mut().scroll_down(rows);
+    }
+
+    fn scroll_right(&mut self, columns: usize) {
+        self.frame_buffer_mut().scroll_right(columns);
+    }
+
+    fn scroll_left(&mut self, columns: usize) {
+        self.frame_buffer_mut().scroll_left(columns);
+    }
+}
+
+/// A `Display` that prints the screen to standard output whenever it has
+/// changed.
+pub struct ConsoleDisplay {
+    buffer: FrameBuffer,
+    printed: Option<FrameBuffer>,
+    quiet: bool,
+}
+
+impl ConsoleDisplay {
+    pub fn new() -> Self {
+        Self {
+            buffer: FrameBuffer::new(),
+            printed: None,
+            quiet: false,
+        }
+    }
+
+    /// A display that never prints, for running programs where only the
+    /// final screen matters.
+    pub fn quiet() -> Self {
+        Self {
+            quiet: true,
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for ConsoleDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for ConsoleDisplay {
+    fn frame_buffer(&self) -> &FrameBuffer {
+        &self.buffer
+    }
+
+    fn frame_buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.buffer
+    }
+
+    fn refresh(&mut self) {
+        if !self.quiet && self.printed.as_ref() != Some(&self.buffer) {
+            print!("{}", self.buffer.to_text());
+            self.printed = Some(self.buffer.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::ConsoleDisplay;
+    use crate::graphics::Display;
+    use crate::graphics::FrameBuffer;
+    use crate::graphics::Sprite;
+
+    fn lit(buffer: &FrameBuffer) -> Vec<(usize, usize)> {
+        let mut pixels = Vec::new();
+        for y in 0..buffer.height() {
+            for x in 0..buffer.width() {
+                if buffer.pixel(x, y) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_console_display_clear() {
+        let mut x = ConsoleDisplay::new();
+        let bytes = [0xFF];
+        x.draw(0, 0, &Sprite::from(&bytes[..]), false);
+        x.clear();
+        assert_eq!(x.buffer, FrameBuffer::new());
+    }
+
+    #[test]
+    fn test_draw_byte() {
+        let mut buffer = FrameBuffer::new();
+        let bytes = [0xFF];
+        assert_eq!(buffer.draw(0, 0, &Sprite::from(&bytes[..]), false), 0);
+        assert_eq!(buffer.pixels()[0..8], [1; 8]);
+        assert!(buffer.pixels()[8..].iter().all(|pixel| *pixel == 0));
+        assert_eq!(buffer.draw(0, 0, &Sprite::from(&bytes[..]), false), 1);
+        assert_eq!(buffer, FrameBuffer::new());
+    }
+
+    #[test]
+    fn test_draw_byte_horizontal_wrap() {
+        let mut buffer = FrameBuffer::new();
+        let bytes = [0xFF];
+        buffer.draw(60, 0, &Sprite::from(&bytes[..]), false);
+        let expected: Vec<(usize, usize)> = [60, 61, 62, 63, 0, 1, 2, 3]
+            .iter()
+            .map(|x| (*x, 0))
+            .collect();
+        let mut actual = lit(&buffer);
+        actual.sort_by_key(|(x, _)| (*x + 4) % 64);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_draw_vertical_wrap() {
+        let mut display = ConsoleDisplay::new();
+        let bytes = [0xFF, 0xFF];
+        let sprite = Sprite::from(&bytes[..]);
+        display.draw(55, 31, &sprite, false);
+        let mut expected = Vec::new();
+        for y in [0, 31] {
+            for x in 55..63 {
+                expected.push((x, y));
+            }
+        }
+        assert_eq!(lit(&display.buffer), expected);
+    }
+
+    #[test]
+    fn test_draw_clipped() {
+        let mut buffer = FrameBuffer::new();
+        let bytes = [0xFF, 0xFF, 0xFF];
+        buffer.draw(60, 30, &Sprite::from(&bytes[..]), true);
+        assert_eq!(
+            lit(&buffer),
+            vec![
+                (60, 30),
+                (61, 30),
+                (62, 30),
+                (63, 30),
+                (60, 31),
+                (61, 31),
+                (62, 31),
+                (63, 31)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_draw_start_position_wraps() {
+        let mut buffer = FrameBuffer::new();
+        let bytes = [0x80];
+        buffer.draw(64 + 3, 32 + 2, &Sprite::from(&bytes[..]), true);
+        assert_eq!(lit(&buffer), vec![(3, 2)]);
+    }
+
+    #[test]
+    fn test_draw_wide_sprite() {
+        let mut buffer = FrameBuffer::new();
+        buffer.set_high_resolution(true);
+        let bytes = [0x80, 0x01, 0x00, 0x00];
+        let sprite = Sprite::wide(&bytes[..]);
+        assert_eq!(sprite.height(), 2);
+        buffer.draw(100, 10, &sprite, true);
+        assert_eq!(lit(&buffer), vec![(100, 10), (115, 10)]);
+    }
+
+    #[test]
+    fn test_high_resolution() {
+        let mut buffer = FrameBuffer::new();
+        buffer.set_high_resolution(true);
+        assert_eq!((buffer.width(), buffer.height()), (128, 64));
+        assert!(buffer.is_high_resolution());
+        buffer.set_high_resolution(false);
+        assert_eq!((buffer.width(), buffer.height()), (64, 32));
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut buffer = FrameBuffer::new();
+        let bytes = [0x80];
+        buffer.draw(10, 10, &Sprite::from(&bytes[..]), false);
+        buffer.scroll_down(3);
+        assert_eq!(lit(&buffer), vec![(10, 13)]);
+        buffer.scroll_right(4);
+        assert_eq!(lit(&buffer), vec![(14, 13)]);
+        buffer.scroll_left(4);
+        buffer.scroll_left(4);
+        assert_eq!(lit(&buffer), vec![(6, 13)]);
+        buffer.scroll_left(8);
+        assert_eq!(lit(&buffer), vec![]);
+    }
+
+    #[test]
+    fn test_draw_planes() {
+        let mut buffer = FrameBuffer::new();
+        buffer.select_planes(3);
+        assert_eq!(buffer.plane_count(), 2);
+        let bytes = [0xC0, 0x80];
+        assert_eq!(buffer.draw(0, 0, &Sprite::from(&bytes[..]), false), 0);
+        assert_eq!(buffer.colour(0, 0), 3);
+        assert_eq!(buffer.colour(1, 0), 1);
+        buffer.select_planes(2);
+        let bytes = [0x80];
+        assert_eq!(buffer.draw(0, 0, &Sprite::from(&bytes[..]), false), 1);
+        assert_eq!(buffer.colour(0, 0), 1);
+        buffer.select_planes(0);
+        assert_eq!(buffer.draw(1, 0, &Sprite::from(&bytes[..]), false), 0);
+        assert_eq!(buffer.colour(1, 0), 1);
+    }
+
+    #[test]
+    fn test_clear_and_scroll_selected_planes() {
+        let mut buffer = FrameBuffer::new();
+        buffer.select_planes(3);
+        let bytes = [0x80, 0x80];
+        buffer.draw(0, 0, &Sprite::from(&bytes[..]), false);
+        buffer.select_planes(2);
+        buffer.scroll_right(4);
+        assert_eq!(buffer.colour(0, 0), 1);
+        assert_eq!(buffer.colour(4, 0), 2);
+        buffer.clear();
+        assert_eq!(lit(&buffer), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_to_text() {
+        let mut buffer = FrameBuffer::new();
+        let bytes = [0xA0];
+        buffer.draw(0, 0, &Sprite::from(&bytes[..]), false);
+        let text = buffer.to_text();
+        let first_line = text.lines().next().unwrap();
+        assert_eq!(&first_line[..4], "* * ");
+        assert_eq!(text.lines().count(), 32);
+    }
+}
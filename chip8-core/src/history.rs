@@ -0,0 +1,152 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The undo log behind stepping backwards.
+//!
+//! Before each instruction the interpreter records its registers, I, PC,
+//! stack, timers and random number generator, and memory notes the old
+//! value of every byte the instruction stores. Undoing an entry puts all of
+//! that back. The screen and sound are not recorded, so they stay as they
+//! are while stepping back; the [`RewindBuffer`](crate::RewindBuffer) covers
+//! those a frame at a time.
+
+use crate::memory::Stack;
+use crate::rng::Rng;
+use std::collections::VecDeque;
+
+/// The number of instructions recorded unless told otherwise, a little over
+/// 16 seconds at the default speed.
+pub const DEFAULT_LIMIT: usize = 10_000;
+
+/// The state an instruction can change, from before it ran.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Undo {
+    pub program_counter: u16,
+    pub address_register: u16,
+    pub registers: [u8; 16],
+    pub stack: Stack,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub rng: Rng,
+    pub halted: bool,
+    /// The old value of every byte stored, in store order.
+    pub memory: Vec<(u16, u8)>,
+}
+
+/// Undo entries for the most recent instructions, oldest first, up to a
+/// limit.
+#[derive(Debug)]
+pub struct History {
+    entries: VecDeque<Undo>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIMIT)
+    }
+}
+
+impl History {
+    /// Creates a log of up to `limit` instructions. A limit of 0 turns
+    /// recording off.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// The number of instructions that can be stepped back.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Changes the limit, forgetting the oldest instructions beyond it.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.limit > 0
+    }
+
+    pub(crate) fn push(&mut self, undo: Undo) {
+        self.entries.push_back(undo);
+        self.trim();
+    }
+
+    /// Takes the entry for the most recent instruction.
+    pub(crate) fn pop(&mut self) -> Option<Undo> {
+        self.entries.pop_back()
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::History;
+    use crate::history::Undo;
+    use crate::memory::Stack;
+    use crate::rng::Rng;
+
+    fn undo(program_counter: u16) -> Undo {
+        Undo {
+            program_counter,
+            address_register: 0,
+            registers: [0; 16],
+            stack: Stack::new(),
+            delay_timer: 0,
+            sound_timer: 0,
+            rng: Rng::new(),
+            halted: false,
+            memory: vec![],
+        }
+    }
+
+    #[test]
+    fn limit() {
+        let mut history = History::new(2);
+        for address in [0x200, 0x202, 0x204] {
+            history.push(undo(address));
+        }
+        assert_eq!(history.len(), 2);
+        history.set_limit(1);
+        assert_eq!(history.pop().map(|undo| undo.program_counter), Some(0x204));
+        assert!(history.is_empty());
+        history.set_limit(0);
+        assert!(!history.is_recording());
+    }
+}
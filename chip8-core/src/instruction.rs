@@ -0,0 +1,459 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::error::Chip8Error;
+use crate::platform::Platform;
+use crate::registers::Address;
+use std::fmt;
+
+/// A decoded instruction.
+///
+/// `x` and `y` are register numbers, `byte` and `address` the immediate
+/// operands. The listings in the comments, and those made by `Display`, use
+/// Cowgod's mnemonics with the SUPER-CHIP and XO-CHIP extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// 0nnn - SYS addr, which is ignored.
+    System { address: u16 },
+    /// 00E0 - CLS
+    Clear,
+    /// 00EE - RET
+    Return,
+    /// 00Cn - SCD n (SUPER-CHIP)
+    ScrollDown { rows: u8 },
+    /// 00FB - SCR (SUPER-CHIP)
+    ScrollRight,
+    /// 00FC - SCL (SUPER-CHIP)
+    ScrollLeft,
+    /// 00FD - EXIT (SUPER-CHIP)
+    Exit,
+    /// 00FE - LOW (SUPER-CHIP)
+    LowResolution,
+    /// 00FF - HIGH (SUPER-CHIP)
+    HighResolution,
+    /// 1nnn - JP addr
+    Jump { address: u16 },
+    /// 2nnn - CALL addr
+    Call { address: u16 },
+    /// 3xkk - SE Vx, byte
+    SkipIfEqualByte { x: u8, byte: u8 },
+    /// 4xkk - SNE Vx, byte
+    SkipIfNotEqualByte { x: u8, byte: u8 },
+    /// 5xy0 - SE Vx, Vy
+    SkipIfEqual { x: u8, y: u8 },
+    /// 5xy2 - SAVE Vx - Vy (XO-CHIP)
+    SaveRange { x: u8, y: u8 },
+    /// 5xy3 - LOAD Vx - Vy (XO-CHIP)
+    LoadRange { x: u8, y: u8 },
+    /// 6xkk - LD Vx, byte
+    LoadByte { x: u8, byte: u8 },
+    /// 7xkk - ADD Vx, byte
+    AddByte { x: u8, byte: u8 },
+    /// 8xy0 - LD Vx, Vy
+    Load { x: u8, y: u8 },
+    /// 8xy1 - OR Vx, Vy
+    Or { x: u8, y: u8 },
+    /// 8xy2 - AND Vx, Vy
+    And { x: u8, y: u8 },
+    /// 8xy3 - XOR Vx, Vy
+    Xor { x: u8, y: u8 },
+    /// 8xy4 - ADD Vx, Vy
+    Add { x: u8, y: u8 },
+    /// 8xy5 - SUB Vx, Vy
+    Subtract { x: u8, y: u8 },
+    /// 8xy6 - SHR Vx, Vy
+    ShiftRight { x: u8, y: u8 },
+    /// 8xy7 - SUBN Vx, Vy
+    SubtractReversed { x: u8, y: u8 },
+    /// 8xyE - SHL Vx, Vy
+    ShiftLeft { x: u8, y: u8 },
+    /// 9xy0 - SNE Vx, Vy
+    SkipIfNotEqual { x: u8, y: u8 },
+    /// Annn - LD I, addr
+    LoadAddress { address: u16 },
+    /// Bnnn - JP V0, addr
+    JumpOffset { address: u16 },
+    /// Cxkk - RND Vx, byte
+    Random { x: u8, byte: u8 },
+    /// Dxyn - DRW Vx, Vy, nibble
+    Draw { x: u8, y: u8, rows: u8 },
+    /// Ex9E - SKP Vx
+    SkipIfKey { x: u8 },
+    /// ExA1 - SKNP Vx
+    SkipIfNotKey { x: u8 },
+    /// F000 nnnn - LD I, long (XO-CHIP)
+    ///
+    /// The address is the word after the instruction, which is read when
+    /// the instruction runs.
+    LoadLongAddress,
+    /// Fn01 - PLANE n (XO-CHIP)
+    SelectPlanes { planes: u8 },
+    /// F002 - AUDIO (XO-CHIP)
+    LoadAudioPattern,
+    /// Fx07 - LD Vx, DT
+    LoadDelayTimer { x: u8 },
+    /// Fx0A - LD Vx, K
+    WaitForKey { x: u8 },
+    /// Fx15 - LD DT, Vx
+    SetDelayTimer { x: u8 },
+    /// Fx18 - LD ST, Vx
+    SetSoundTimer { x: u8 },
+    /// Fx1E - ADD I, Vx
+    AddAddress { x: u8 },
+    /// Fx29 - LD F, Vx
+    LoadFont { x: u8 },
+    /// Fx30 - LD HF, Vx (SUPER-CHIP)
+    LoadLargeFont { x: u8 },
+    /// Fx33 - LD B, Vx
+    StoreBcd { x: u8 },
+    /// Fx3A - PITCH Vx (XO-CHIP)
+    SetPitch { x: u8 },
+    /// Fx55 - LD [I], Vx
+    StoreRegisters { x: u8 },
+    /// Fx65 - LD Vx, [I]
+    LoadRegisters { x: u8 },
+    /// Fx75 - LD R, Vx (SUPER-CHIP)
+    SaveFlags { x: u8 },
+    /// Fx85 - LD Vx, R (SUPER-CHIP)
+    LoadFlags { x: u8 },
+}
+
+/// A word that isn't an instruction on the platform it was decoded for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidInstruction {
+    pub opcode: u16,
+}
+
+impl InvalidInstruction {
+    /// The error for running the word found at `address`.
+    pub fn at(self, address: Address) -> Chip8Error {
+        Chip8Error::InvalidOpcode {
+            opcode: self.opcode,
+            address,
+        }
+    }
+}
+
+impl Instruction {
+    /// Decodes `code` for `platform`. The SUPER-CHIP instructions are only
+    /// decoded for SUPER-CHIP and XO-CHIP, and the XO-CHIP instructions only
+    /// for XO-CHIP. Plain CHIP-8 reads 00Cn and 00FB to 00FF as SYS calls.
+    pub fn decode(code: u16, platform: Platform) -> Result<Instruction, InvalidInstruction> {
+        use Instruction::*;
+        let x = ((code >> 8) & 0xF) as u8;
+        let y = ((code >> 4) & 0xF) as u8;
+        let n = (code & 0xF) as u8;
+        let byte = code as u8;
+        let address = code & 0xFFF;
+        let super_chip = platform != Platform::Chip8;
+        let xo_chip = platform == Platform::XoChip;
+        let instruction = match (code >> 12, x, y, n) {
+            (0x0, 0x0, 0xE, 0x0) => Clear,
+            (0x0, 0x0, 0xE, 0xE) => Return,
+            (0x0, 0x0, 0xC, rows) if super_chip => ScrollDown { rows },
+            (0x0, 0x0, 0xF, 0xB) if super_chip => ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) if super_chip => ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) if super_chip => Exit,
+            (0x0, 0x0, 0xF, 0xE) if super_chip => LowResolution,
+            (0x0, 0x0, 0xF, 0xF) if super_chip => HighResolution,
+            (0x0, ..) => System { address },
+            (0x1, ..) => Jump { address },
+            (0x2, ..) => Call { address },
+            (0x3, ..) => SkipIfEqualByte { x, byte },
+            (0x4, ..) => SkipIfNotEqualByte { x, byte },
+            (0x5, _, _, 0x0) => SkipIfEqual { x, y },
+            (0x5, _, _, 0x2) if xo_chip => SaveRange { x, y },
+            (0x5, _, _, 0x3) if xo_chip => LoadRange { x, y },
+            (0x6, ..) => LoadByte { x, byte },
+            (0x7, ..) => AddByte { x, byte },
+            (0x8, _, _, 0x0) => Load { x, y },
+            (0x8, _, _, 0x1) => Or { x, y },
+            (0x8, _, _, 0x2) => And { x, y },
+            (0x8, _, _, 0x3) => Xor { x, y },
+            (0x8, _, _, 0x4) => Add { x, y },
+            (0x8, _, _, 0x5) => Subtract { x, y },
+            (0x8, _, _, 0x6) => ShiftRight { x, y },
+            (0x8, _, _, 0x7) => SubtractReversed { x, y },
+            (0x8, _, _, 0xE) => ShiftLeft { x, y },
+            (0x9, _, _, 0x0) => SkipIfNotEqual { x, y },
+            (0xA, ..) => LoadAddress { address },
+            (0xB, ..) => JumpOffset { address },
+            (0xC, ..) => Random { x, byte },
+            (0xD, _, _, rows) => Draw { x, y, rows },
+            (0xE, _, 0x9, 0xE) => SkipIfKey { x },
+            (0xE, _, 0xA, 0x1) => SkipIfNotKey { x },
+            (0xF, 0x0, 0x0, 0x0) if xo_chip => LoadLongAddress,
+            (0xF, planes, 0x0, 0x1) if xo_chip => SelectPlanes { planes },
+            (0xF, 0x0, 0x0, 0x2) if xo_chip => LoadAudioPattern,
+            (0xF, _, 0x0, 0x7) => LoadDelayTimer { x },
+            (0xF, _, 0x0, 0xA) => WaitForKey { x },
+            (0xF, _, 0x1, 0x5) => SetDelayTimer { x },
+            (0xF, _, 0x1, 0x8) => SetSoundTimer { x },
+            (0xF, _, 0x1, 0xE) => AddAddress { x },
+            (0xF, _, 0x2, 0x9) => LoadFont { x },
+            (0xF, _, 0x3, 0x0) if super_chip => LoadLargeFont { x },
+            (0xF, _, 0x3, 0x3) => StoreBcd { x },
+            (0xF, _, 0x3, 0xA) if xo_chip => SetPitch { x },
+            (0xF, _, 0x5, 0x5) => StoreRegisters { x },
+            (0xF, _, 0x6, 0x5) => LoadRegisters { x },
+            (0xF, _, 0x7, 0x5) if super_chip => SaveFlags { x },
+            (0xF, _, 0x8, 0x5) if super_chip => LoadFlags { x },
+            _ => return Err(InvalidInstruction { opcode: code }),
+        };
+        Ok(instruction)
+    }
+
+    /// The opcode that decodes to the instruction.
+    pub fn encode(&self) -> u16 {
+        use Instruction::*;
+        let xy = |high: u16, x: u8, y: u8, low: u16| {
+            high << 12 | u16::from(x) << 8 | u16::from(y) << 4 | low
+        };
+        let xkk = |high: u16, x: u8, byte: u8| high << 12 | u16::from(x) << 8 | u16::from(byte);
+        let fx = |x: u8, low: u16| 0xF000 | u16::from(x) << 8 | low;
+        match *self {
+            System { address } => address & 0xFFF,
+            Clear => 0x00E0,
+            Return => 0x00EE,
+            ScrollDown { rows } => 0x00C0 | u16::from(rows & 0xF),
+            ScrollRight => 0x00FB,
+            ScrollLeft => 0x00FC,
+            Exit => 0x00FD,
+            LowResolution => 0x00FE,
+            HighResolution => 0x00FF,
+            Jump { address } => 0x1000 | address & 0xFFF,
+            Call { address } => 0x2000 | address & 0xFFF,
+            SkipIfEqualByte { x, byte } => xkk(0x3, x, byte),
+            SkipIfNotEqualByte { x, byte } => xkk(0x4, x, byte),
+            SkipIfEqual { x, y } => xy(0x5, x, y, 0x0),
+            SaveRange { x, y } => xy(0x5, x, y, 0x2),
+            LoadRange { x, y } => xy(0x5, x, y, 0x3),
+            LoadByte { x, byte } => xkk(0x6, x, byte),
+            AddByte { x, byte } => xkk(0x7, x, byte),
+            Load { x, y } => xy(0x8, x, y, 0x0),
+            Or { x, y } => xy(0x8, x, y, 0x1),
+            And { x, y } => xy(0x8, x, y, 0x2),
+            Xor { x, y } => xy(0x8, x, y, 0x3),
+            Add { x, y } => xy(0x8, x, y, 0x4),
+            Subtract { x, y } => xy(0x8, x, y, 0x5),
+            ShiftRight { x, y } => xy(0x8, x, y, 0x6),
+            SubtractReversed { x, y } => xy(0x8, x, y, 0x7),
+            ShiftLeft { x, y } => xy(0x8, x, y, 0xE),
+            SkipIfNotEqual { x, y } => xy(0x9, x, y, 0x0),
+            LoadAddress { address } => 0xA000 | address & 0xFFF,
+            JumpOffset { address } => 0xB000 | address & 0xFFF,
+            Random { x, byte } => xkk(0xC, x, byte),
+            Draw { x, y, rows } => xy(0xD, x, y, u16::from(rows & 0xF)),
+            SkipIfKey { x } => xkk(0xE, x, 0x9E),
+            SkipIfNotKey { x } => xkk(0xE, x, 0xA1),
+            LoadLongAddress => 0xF000,
+            SelectPlanes { planes } => fx(planes, 0x01),
+            LoadAudioPattern => 0xF002,
+            LoadDelayTimer { x } => fx(x, 0x07),
+            WaitForKey { x } => fx(x, 0x0A),
+            SetDelayTimer { x } => fx(x, 0x15),
+            SetSoundTimer { x } => fx(x, 0x18),
+            AddAddress { x } => fx(x, 0x1E),
+            LoadFont { x } => fx(x, 0x29),
+            LoadLargeFont { x } => fx(x, 0x30),
+            StoreBcd { x } => fx(x, 0x33),
+            SetPitch { x } => fx(x, 0x3A),
+            StoreRegisters { x } => fx(x, 0x55),
+            LoadRegisters { x } => fx(x, 0x65),
+            SaveFlags { x } => fx(x, 0x75),
+            LoadFlags { x } => fx(x, 0x85),
+        }
+    }
+
+    /// The number of bytes the instruction takes up in memory: 4 for
+    /// F000 nnnn and 2 for everything else.
+    pub fn size(&self) -> u16 {
+        match self {
+            Instruction::LoadLongAddress => 4,
+            _ => 2,
+        }
+    }
+
+    /// Returns true for the instructions that may skip the next one.
+    pub fn is_skip(&self) -> bool {
+        use Instruction::*;
+        matches!(
+            self,
+            SkipIfEqualByte { .. }
+                | SkipIfNotEqualByte { .. }
+                | SkipIfEqual { .. }
+                | SkipIfNotEqual { .. }
+                | SkipIfKey { .. }
+                | SkipIfNotKey { .. }
+        )
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+        match *self {
+            System { address } => write!(f, "SYS 0x{:03X}", address),
+            Clear => write!(f, "CLS"),
+            Return => write!(f, "RET"),
+            ScrollDown { rows } => write!(f, "SCD {}", rows),
+            ScrollRight => write!(f, "SCR"),
+            ScrollLeft => write!(f, "SCL"),
+            Exit => write!(f, "EXIT"),
+            LowResolution => write!(f, "LOW"),
+            HighResolution => write!(f, "HIGH"),
+            Jump { address } => write!(f, "JP 0x{:03X}", address),
+            Call { address } => write!(f, "CALL 0x{:03X}", address),
+            SkipIfEqualByte { x, byte } => write!(f, "SE V{:X}, 0x{:02X}", x, byte),
+            SkipIfNotEqualByte { x, byte } => write!(f, "SNE V{:X}, 0x{:02X}", x, byte),
+            SkipIfEqual { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            SaveRange { x, y } => write!(f, "SAVE V{:X} - V{:X}", x, y),
+            LoadRange { x, y } => write!(f, "LOAD V{:X} - V{:X}", x, y),
+            LoadByte { x, byte } => write!(f, "LD V{:X}, 0x{:02X}", x, byte),
+            AddByte { x, byte } => write!(f, "ADD V{:X}, 0x{:02X}", x, byte),
+            Load { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Add { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Subtract { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            ShiftRight { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            SubtractReversed { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            ShiftLeft { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            SkipIfNotEqual { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            LoadAddress { address } => write!(f, "LD I, 0x{:03X}", address),
+            JumpOffset { address } => write!(f, "JP V0, 0x{:03X}", address),
+            Random { x, byte } => write!(f, "RND V{:X}, 0x{:02X}", x, byte),
+            Draw { x, y, rows } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, rows),
+            SkipIfKey { x } => write!(f, "SKP V{:X}", x),
+            SkipIfNotKey { x } => write!(f, "SKNP V{:X}", x),
+            LoadLongAddress => write!(f, "LD I, long"),
+            SelectPlanes { planes } => write!(f, "PLANE {}", planes),
+            LoadAudioPattern => write!(f, "AUDIO"),
+            LoadDelayTimer { x } => write!(f, "LD V{:X}, DT", x),
+            WaitForKey { x } => write!(f, "LD V{:X}, K", x),
+            SetDelayTimer { x } => write!(f, "LD DT, V{:X}", x),
+            SetSoundTimer { x } => write!(f, "LD ST, V{:X}", x),
+            AddAddress { x } => write!(f, "ADD I, V{:X}", x),
+            LoadFont { x } => write!(f, "LD F, V{:X}", x),
+            LoadLargeFont { x } => write!(f, "LD HF, V{:X}", x),
+            StoreBcd { x } => write!(f, "LD B, V{:X}", x),
+            SetPitch { x } => write!(f, "PITCH V{:X}", x),
+            StoreRegisters { x } => write!(f, "LD [I], V{:X}", x),
+            LoadRegisters { x } => write!(f, "LD V{:X}, [I]", x),
+            SaveFlags { x } => write!(f, "LD R, V{:X}", x),
+            LoadFlags { x } => write!(f, "LD V{:X}, R", x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::Instruction;
+    use crate::instruction::InvalidInstruction;
+    use crate::platform::Platform;
+
+    fn listing(code: u16, platform: Platform) -> Option<String> {
+        Instruction::decode(code, platform)
+            .ok()
+            .map(|instruction| instruction.to_string())
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            Instruction::decode(0xD015, Platform::Chip8),
+            Ok(Instruction::Draw {
+                x: 0,
+                y: 1,
+                rows: 5
+            })
+        );
+        assert_eq!(listing(0x00E0, Platform::Chip8).as_deref(), Some("CLS"));
+        assert_eq!(
+            listing(0x0123, Platform::Chip8).as_deref(),
+            Some("SYS 0x123")
+        );
+        assert_eq!(
+            listing(0x631F, Platform::Chip8).as_deref(),
+            Some("LD V3, 0x1F")
+        );
+        assert_eq!(
+            listing(0x00C4, Platform::SuperChip).as_deref(),
+            Some("SCD 4")
+        );
+        assert_eq!(
+            listing(0xFA65, Platform::Chip8).as_deref(),
+            Some("LD VA, [I]")
+        );
+        for code in [0x5AB1, 0x8AB8, 0x9AB1, 0xE0A2, 0xF0FF] {
+            assert_eq!(
+                Instruction::decode(code, Platform::XoChip),
+                Err(InvalidInstruction { opcode: code })
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_xo_chip() {
+        assert!(Instruction::decode(0xF000, Platform::Chip8).is_err());
+        assert_eq!(
+            Instruction::decode(0xF000, Platform::XoChip),
+            Ok(Instruction::LoadLongAddress)
+        );
+        assert_eq!(Instruction::LoadLongAddress.size(), 4);
+        assert_eq!(
+            listing(0xF201, Platform::XoChip).as_deref(),
+            Some("PLANE 2")
+        );
+        assert_eq!(
+            listing(0x5122, Platform::XoChip).as_deref(),
+            Some("SAVE V1 - V2")
+        );
+        assert_eq!(listing(0xF33A, Platform::SuperChip), None);
+    }
+
+    #[test]
+    fn test_decode_super_chip() {
+        assert_eq!(
+            Instruction::decode(0x00FD, Platform::Chip8),
+            Ok(Instruction::System { address: 0x0FD })
+        );
+        assert_eq!(
+            Instruction::decode(0x00FD, Platform::SuperChip),
+            Ok(Instruction::Exit)
+        );
+        assert_eq!(
+            listing(0x00C4, Platform::Chip8).as_deref(),
+            Some("SYS 0x0C4")
+        );
+        assert_eq!(listing(0x00FF, Platform::XoChip).as_deref(), Some("HIGH"));
+        for code in [0xF130, 0xF175, 0xF185] {
+            assert!(Instruction::decode(code, Platform::Chip8).is_err());
+            assert!(Instruction::decode(code, Platform::SuperChip).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_encode_round_trips() {
+        for code in 0..=0xFFFF {
+            if let Ok(instruction) = Instruction::decode(code, Platform::XoChip) {
+                assert_eq!(instruction.encode(), code, "{}", instruction);
+            }
+        }
+    }
+}
@@ -15,7 +15,21 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+/// The 16 key hexadecimal keypad.
 pub trait Keyboard: Send + Sync {
+    /// Returns true while the key with the value `key` (0x0 - 0xF) is held.
     fn is_key_down(&self, key: u8) -> bool;
+    /// Returns the value of a key that is currently held, if any.
     fn get_pressed_key(&self) -> Option<u8>;
-}
\ No newline at end of file
+
+    /// Returns the held keys as a bit mask, bit n for key n.
+    fn keys(&self) -> u16 {
+        (0..16).fold(0, |keys, key| match self.is_key_down(key) {
+            true => keys | 1 << key,
+            false => keys,
+        })
+    }
+
+    /// Called by the interpreter before each frame runs.
+    fn start_frame(&mut self) {}
+}
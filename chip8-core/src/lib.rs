@@ -0,0 +1,70 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The CHIP-8 interpreter core.
+//!
+//! This crate contains the machine itself and nothing else. Front ends
+//! supply a [`Display`], a [`Buzzer`] and a [`Keyboard`], hand them to
+//! [`Interpreter::new`] together with a ROM, and then drive the machine by
+//! calling [`Interpreter::run_iteration`], which reports faults in the
+//! program as a [`Chip8Error`].
+//!
+//! The delay and sound timers only move when the host asks them to, so a run
+//! is fully reproducible. By default they tick once per
+//! [`Interpreter::run_frame`]. A host that sets a [`clock::Clock`] with
+//! [`Interpreter::set_clock`] has them follow it instead, from `run_frame`
+//! or from [`Interpreter::update_timers`].
+
+pub mod assembler;
+pub mod audio;
+pub mod chip8;
+pub mod clock;
+pub mod dap;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod expression;
+pub mod gdb;
+pub mod graphics;
+pub mod history;
+pub mod instruction;
+pub mod keyboard;
+pub mod memory;
+pub mod movie;
+pub mod platform;
+pub mod quirks;
+pub mod registers;
+pub mod rewind;
+pub mod rng;
+pub mod snapshot;
+pub mod symbols;
+
+pub use audio::Buzzer;
+pub use chip8::Interpreter;
+pub use clock::FrameScheduler;
+pub use debugger::Debugger;
+pub use error::Chip8Error;
+pub use graphics::Display;
+pub use instruction::Instruction;
+pub use keyboard::Keyboard;
+pub use memory::Memory;
+pub use movie::Movie;
+pub use platform::Platform;
+pub use quirks::Quirks;
+pub use rewind::RewindBuffer;
+pub use snapshot::Snapshot;
+pub use symbols::Symbols;
@@ -0,0 +1,517 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::debugger::MemoryAccess;
+use crate::debugger::WatchHit;
+use crate::debugger::Watchpoint;
+use crate::error::Chip8Error;
+use crate::registers::Address;
+use crate::registers::AddressRegister;
+use crate::registers::ProgramCounter;
+use std::cell::Cell;
+
+/// Where the SUPER-CHIP 8x10 hexadecimal font starts in memory.
+pub const LARGE_FONT_ADDRESS: u16 = 0x50;
+
+/// The SUPER-CHIP font, 10 bytes per digit from 0 to F.
+const LARGE_FONT: [[u8; 10]; 16] = [
+    [0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF], // 0
+    [0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF], // 1
+    [0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF], // 2
+    [0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF], // 3
+    [0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03], // 4
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF], // 5
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF], // 6
+    [0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18], // 7
+    [0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF], // 8
+    [0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF], // 9
+    [0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3], // A
+    [0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC], // B
+    [0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C], // C
+    [0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC], // D
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF], // E
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0], // F
+];
+
+/// The size of RAM on every platform but XO-CHIP.
+pub const DEFAULT_SIZE: usize = 0x1000;
+
+/// Where ROMs are loaded and programs start.
+pub const PROGRAM_START: u16 = 0x200;
+
+/// The machine's RAM, 4 KiB unless created with `with_size`, with the
+/// hexadecimal fonts at 0x000 and programs loaded at 0x200.
+#[derive(Debug, PartialEq)]
+pub struct Memory {
+    bytes: Vec<u8>,
+    watchpoints: Vec<Watchpoint>,
+    /// The first watched access since `take_watch_hit`. Reads note it too,
+    /// through `&self`.
+    watch_hit: Cell<Option<WatchHit>>,
+    /// The old value of each byte stored since `start_journal`, while
+    /// journaling.
+    journal: Option<Vec<(u16, u8)>>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::with_size(DEFAULT_SIZE)
+    }
+
+    /// Creates RAM of `size` bytes, which must be at least 0x200 and at most
+    /// the 64 KiB a 16 bit address can reach.
+    pub fn with_size(size: usize) -> Memory {
+        assert!((0x200..=0x10000).contains(&size));
+        let mut bytes = vec![0; size];
+        // 0
+        bytes[0..5].clone_from_slice(&[0xF0, 0x90, 0x90, 0x90, 0xF0]);
+        // 1
+        bytes[5..10].clone_from_slice(&[0x20, 0x60, 0x20, 0x20, 0x70]);
+        // 2
+        bytes[10..15].clone_from_slice(&[0xF0, 0x10, 0xF0, 0x80, 0xF0]);
+        // 3
+        bytes[15..20].clone_from_slice(&[0xF0, 0x10, 0xF0, 0x10, 0xF0]);
+        // 4
+        bytes[20..25].clone_from_slice(&[0x90, 0x90, 0xF0, 0x10, 0x10]);
+        // 5
+        bytes[25..30].clone_from_slice(&[0xF0, 0x80, 0xF0, 0x10, 0xF0]);
+        // 6
+        bytes[30..35].clone_from_slice(&[0xF0, 0x80, 0xF0, 0x90, 0xF0]);
+        // 7
+        bytes[35..40].clone_from_slice(&[0xF0, 0x10, 0x20, 0x40, 0x40]);
+        // 8
+        bytes[40..45].clone_from_slice(&[0xF0, 0x90, 0xF0, 0x90, 0xF0]);
+        // 9
+        bytes[45..50].clone_from_slice(&[0xF0, 0x90, 0xF0, 0x10, 0xF0]);
+        // A
+        bytes[50..55].clone_from_slice(&[0xF0, 0x90, 0xF0, 0x90, 0x90]);
+        // B
+        bytes[55..60].clone_from_slice(&[0xE0, 0x90, 0xE0, 0x90, 0xE0]);
+        // C
+        bytes[60..65].clone_from_slice(&[0xF0, 0x80, 0x80, 0x80, 0xF0]);
+        // D
+        bytes[65..70].clone_from_slice(&[0xE0, 0x90, 0x90, 0x90, 0xE0]);
+        // E
+        bytes[70..75].clone_from_slice(&[0xF0, 0x80, 0xF0, 0x80, 0xF0]);
+        // F
+        bytes[75..80].clone_from_slice(&[0xF0, 0x80, 0xF0, 0x80, 0x80]);
+
+        for (digit, glyph) in LARGE_FONT.iter().enumerate() {
+            let start = LARGE_FONT_ADDRESS as usize + 10 * digit;
+            bytes[start..start + 10].clone_from_slice(glyph);
+        }
+
+        Memory::from_vec(bytes)
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> Memory {
+        Memory {
+            bytes,
+            watchpoints: Vec::new(),
+            watch_hit: Cell::new(None),
+            journal: None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// The whole of memory, starting at address 0.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Creates memory holding exactly `bytes`, as saved by `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Memory {
+        assert!((0x200..=0x10000).contains(&bytes.len()));
+        Memory::from_vec(bytes.to_vec())
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Watches for `fetch`, `load` and `store` touching the watchpoint's
+    /// bytes, which covers every instruction that reads or writes memory.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    /// Removes a watchpoint, returning false if there was no such watchpoint.
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|existing| existing != watchpoint);
+        self.watchpoints.len() != count
+    }
+
+    pub fn set_watchpoints(&mut self, watchpoints: Vec<Watchpoint>) {
+        self.watchpoints = watchpoints;
+    }
+
+    /// The first watched access since the last call, if any.
+    pub(crate) fn take_watch_hit(&self) -> Option<WatchHit> {
+        self.watch_hit.take()
+    }
+
+    /// Starts noting the old value of every byte `store` changes, for
+    /// undoing an instruction.
+    pub(crate) fn start_journal(&mut self) {
+        self.journal = Some(Vec::new());
+    }
+
+    /// Stops journaling and returns the old values noted, in store order.
+    pub(crate) fn take_journal(&mut self) -> Vec<(u16, u8)> {
+        self.journal.take().unwrap_or_default()
+    }
+
+    /// Puts back the old values from a journal, newest first.
+    pub(crate) fn undo(&mut self, journal: &[(u16, u8)]) {
+        for &(address, old) in journal.iter().rev() {
+            self.bytes[usize::from(address)] = old;
+        }
+    }
+
+    pub fn fetch(&self, program_counter: &ProgramCounter) -> Result<&[u8; 2], Chip8Error> {
+        let address = usize::from(program_counter);
+        let bytes = self.range(address, 2)?;
+        self.watch(address, MemoryAccess::Read, bytes);
+        Ok(bytes.try_into().expect("range is two bytes long"))
+    }
+
+    pub fn load(&self, i: &AddressRegister, number_of_bytes: u16) -> Result<&[u8], Chip8Error> {
+        let address = usize::from(*i);
+        let bytes = self.range(address, number_of_bytes as usize)?;
+        self.watch(address, MemoryAccess::Read, bytes);
+        Ok(bytes)
+    }
+
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        let start = usize::from(PROGRAM_START);
+        let capacity = self.bytes.len() - start;
+        if rom.len() > capacity {
+            return Err(Chip8Error::RomTooLarge {
+                size: rom.len(),
+                capacity,
+            });
+        }
+        self.bytes[start..start + rom.len()].clone_from_slice(rom);
+        Ok(())
+    }
+
+    pub fn store(&mut self, i: &AddressRegister, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let start: usize = (*i).into();
+        self.check_bounds(start, bytes.len())?;
+        self.watch(start, MemoryAccess::Write, bytes);
+        if let Some(journal) = &mut self.journal {
+            let old = &self.bytes[start..start + bytes.len()];
+            journal.extend(
+                (start..)
+                    .map(|address| address as u16)
+                    .zip(old.iter().copied()),
+            );
+        }
+        self.bytes[start..start + bytes.len()].clone_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Overwrites memory from `address` without checking watchpoints, for
+    /// debuggers poking at a paused machine.
+    pub fn write(&mut self, address: u16, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let start = usize::from(address);
+        self.check_bounds(start, bytes.len())?;
+        self.bytes[start..start + bytes.len()].clone_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Notes an access of `new.len()` bytes from `address` if it is the first
+    /// to touch a watchpoint. Must be called before a store changes memory.
+    fn watch(&self, address: usize, access: MemoryAccess, new: &[u8]) {
+        if self.watchpoints.is_empty() || self.watch_hit.get().is_some() {
+            return;
+        }
+        for (offset, new) in new.iter().enumerate() {
+            let address = address + offset;
+            let watched = self
+                .watchpoints
+                .iter()
+                .any(|watchpoint| watchpoint.matches(address as u16, access));
+            if watched {
+                self.watch_hit.set(Some(WatchHit {
+                    address: address as u16,
+                    access,
+                    old: self.bytes[address],
+                    new: *new,
+                }));
+                return;
+            }
+        }
+    }
+
+    fn range(&self, address: usize, length: usize) -> Result<&[u8], Chip8Error> {
+        self.check_bounds(address, length)?;
+        Ok(&self.bytes[address..address + length])
+    }
+
+    fn check_bounds(&self, address: usize, length: usize) -> Result<(), Chip8Error> {
+        if address + length > self.bytes.len() {
+            Err(Chip8Error::MemoryOutOfBounds { address, length })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+type StackPointer = u8;
+
+/// The 16 entry call stack of return addresses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stack {
+    frames: [Address; 16],
+    stack_pointer: StackPointer,
+    /// The deepest the stack has been.
+    max_depth: StackPointer,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Self {
+            frames: [Address::new(); 16],
+            stack_pointer: 0,
+            max_depth: 0,
+        }
+    }
+
+    /// Creates a stack with the bottom `stack_pointer` of `frames` in use.
+    pub fn from_frames(frames: [Address; 16], stack_pointer: u8) -> Self {
+        assert!(usize::from(stack_pointer) <= frames.len());
+        Self {
+            frames,
+            stack_pointer,
+            max_depth: stack_pointer,
+        }
+    }
+
+    pub fn frames(&self) -> &[Address; 16] {
+        &self.frames
+    }
+
+    /// The return addresses of the calls in progress, outermost first.
+    pub fn return_addresses(&self) -> &[Address] {
+        &self.frames[..usize::from(self.stack_pointer)]
+    }
+
+    /// The number of frames in use.
+    pub fn stack_pointer(&self) -> u8 {
+        self.stack_pointer
+    }
+
+    pub fn pop(&mut self) -> Option<Address> {
+        if self.stack_pointer > 0 {
+            let top = (self.stack_pointer - 1) as usize;
+            let frame = self.frames[top];
+            self.frames[top] = Address::from(0);
+            self.stack_pointer -= 1;
+            Some(frame)
+        } else {
+            None
+        }
+    }
+
+    pub fn push(&mut self, address: &Address) -> Result<(), Chip8Error> {
+        if self.stack_pointer as usize >= self.frames.len() {
+            Err(Chip8Error::StackOverflow)
+        } else {
+            self.frames[self.stack_pointer as usize] = *address;
+            self.stack_pointer += 1;
+            self.max_depth = self.max_depth.max(self.stack_pointer);
+            Ok(())
+        }
+    }
+
+    /// The return address of the innermost call.
+    pub fn peek(&self) -> Option<Address> {
+        if self.stack_pointer == 0 {
+            None
+        } else {
+            let top = (self.stack_pointer - 1) as usize;
+            let temp = self.frames[top];
+            Some(temp)
+        }
+    }
+
+    /// The most frames that have been in use at once.
+    pub fn max_depth(&self) -> u8 {
+        self.max_depth
+    }
+}
+
+#[test]
+fn memory_load_rom() {
+    let rom = vec![0xAA, 0xBB, 0xCC];
+    let mut memory = Memory::new();
+    memory.load_rom(&rom).unwrap();
+    let memory_cmp = Memory::new();
+    assert_ne!(memory, memory_cmp);
+}
+
+#[test]
+fn memory_load() {
+    let memory = Memory::new();
+    let mut address_register = AddressRegister::new();
+    address_register.set(Address::from(0));
+    assert_eq!(
+        memory.load(&address_register, 5).unwrap(),
+        &[0xF0, 0x90, 0x90, 0x90, 0xF0]
+    );
+}
+
+#[test]
+fn memory_store() {
+    let data = [0xAA, 0xBB, 0xCC];
+    let mut memory = Memory::new();
+    let mut address_register = AddressRegister::new();
+    address_register.set(Address::from(0x200));
+    memory.store(&address_register, &data).unwrap();
+    assert_ne!(memory, Memory::new());
+}
+
+#[test]
+fn memory_load_rom_too_large() {
+    let rom = vec![0; 4096 - 0x200 + 1];
+    let mut memory = Memory::new();
+    assert_eq!(
+        memory.load_rom(&rom),
+        Err(Chip8Error::RomTooLarge {
+            size: 3585,
+            capacity: 3584
+        })
+    );
+}
+
+#[test]
+fn memory_with_size() {
+    let mut memory = Memory::with_size(0x10000);
+    assert_eq!(memory.size(), 0x10000);
+    assert_eq!(memory.load_rom(&vec![0; 0x10000 - 0x200]), Ok(()));
+    let mut address_register = AddressRegister::new();
+    address_register.set(Address::from(0xFFFF));
+    assert_eq!(memory.store(&address_register, &[0xAA]), Ok(()));
+    assert_eq!(memory.load(&address_register, 1).unwrap(), &[0xAA]);
+}
+
+#[test]
+fn memory_load_out_of_bounds() {
+    let memory = Memory::new();
+    let mut address_register = AddressRegister::new();
+    address_register.set(Address::from(0xFFE));
+    assert_eq!(
+        memory.load(&address_register, 3),
+        Err(Chip8Error::MemoryOutOfBounds {
+            address: 0xFFE,
+            length: 3
+        })
+    );
+}
+
+#[test]
+fn memory_store_out_of_bounds() {
+    let mut memory = Memory::new();
+    let mut address_register = AddressRegister::new();
+    address_register.set(Address::from(0x1000));
+    assert!(memory.store(&address_register, &[0xAA]).is_err());
+    assert_eq!(memory, Memory::new());
+}
+
+#[test]
+fn memory_watchpoints() {
+    use crate::debugger::WatchKind;
+    let mut memory = Memory::new();
+    memory.add_watchpoint(Watchpoint::new(0x301, 0x302, WatchKind::Write));
+    let mut address_register = AddressRegister::new();
+    address_register.set(Address::from(0x300));
+    memory.load(&address_register, 4).unwrap();
+    assert_eq!(memory.take_watch_hit(), None);
+    memory.store(&address_register, &[1, 2, 3]).unwrap();
+    assert_eq!(
+        memory.take_watch_hit(),
+        Some(WatchHit {
+            address: 0x301,
+            access: MemoryAccess::Write,
+            old: 0,
+            new: 2
+        })
+    );
+    assert_eq!(memory.take_watch_hit(), None);
+    memory.add_watchpoint(Watchpoint::new(0x200, 0x200, WatchKind::Read));
+    memory
+        .fetch(&ProgramCounter::from(&Address::from(0x200)))
+        .unwrap();
+    assert_eq!(memory.take_watch_hit().unwrap().access, MemoryAccess::Read);
+    assert!(memory.remove_watchpoint(&Watchpoint::new(0x200, 0x200, WatchKind::Read)));
+    assert_eq!(memory.watchpoints().len(), 1);
+}
+
+#[test]
+fn memory_fetch_out_of_bounds() {
+    let memory = Memory::new();
+    let program_counter = ProgramCounter::from(&Address::from(0xFFF));
+    assert!(memory.fetch(&program_counter).is_err());
+}
+
+#[test]
+fn stack_overflow() {
+    let mut stack = Stack::new();
+    for _ in 0..16 {
+        stack.push(&Address::from(0x200)).unwrap();
+    }
+    assert_eq!(
+        stack.push(&Address::from(0x200)),
+        Err(Chip8Error::StackOverflow)
+    );
+}
+
+#[test]
+fn stack_return_addresses() {
+    let mut stack = Stack::new();
+    assert!(stack.return_addresses().is_empty());
+    stack.push(&Address::from(0x202)).unwrap();
+    stack.push(&Address::from(0x30A)).unwrap();
+    stack.pop();
+    stack.push(&Address::from(0x20C)).unwrap();
+    assert_eq!(
+        stack.return_addresses(),
+        &[Address::from(0x202), Address::from(0x20C)]
+    );
+    stack.pop();
+    assert_eq!(stack.return_addresses(), &[Address::from(0x202)]);
+    assert_eq!(stack.max_depth(), 2);
+}
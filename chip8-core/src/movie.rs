@@ -0,0 +1,301 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::chip8::Interpreter;
+use crate::error::Chip8Error;
+use crate::keyboard::Keyboard;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::rng::RngMode;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// The version written into new movies. Movies with any other version are
+/// rejected.
+pub const MOVIE_VERSION: u32 = 1;
+
+/// Returns the SHA-1 of `rom` as lowercase hexadecimal, which is how movies,
+/// and anything else that needs to recognise a ROM, identify it.
+pub fn rom_hash(rom: &[u8]) -> String {
+    sha1_smol::Sha1::from(rom).digest().to_string()
+}
+
+/// A recording of the keypad, one entry per frame, together with everything
+/// else that decides how the program runs.
+///
+/// A movie played back on a freshly loaded copy of the same ROM with
+/// `configure` applied runs exactly as the recorded session did.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Movie {
+    pub version: u32,
+    pub rom_hash: String,
+    pub platform: Platform,
+    pub seed: u64,
+    pub rng_mode: RngMode,
+    pub quirks: Quirks,
+    pub instructions_per_frame: usize,
+    /// The keys held during each frame as a bit mask, bit n for key n.
+    pub frames: Vec<u16>,
+}
+
+impl Movie {
+    /// Starts an empty movie of `interpreter`, which should have just been
+    /// created so that no frames have run yet.
+    pub fn new(interpreter: &Interpreter) -> Self {
+        Movie {
+            version: MOVIE_VERSION,
+            rom_hash: interpreter.rom_hash().to_string(),
+            platform: interpreter.platform(),
+            seed: interpreter.seed(),
+            rng_mode: interpreter.rng_mode(),
+            quirks: interpreter.quirks(),
+            instructions_per_frame: interpreter.instructions_per_frame(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("movies always serialize")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Movie, Chip8Error> {
+        let movie: Movie = serde_json::from_slice(bytes)
+            .map_err(|error| invalid(format!("unreadable: {}", error)))?;
+        if movie.version != MOVIE_VERSION {
+            return Err(invalid(format!(
+                "version {} is not supported, expected {}",
+                movie.version, MOVIE_VERSION
+            )));
+        }
+        if movie.instructions_per_frame == 0 {
+            return Err(invalid("no instructions per frame".to_string()));
+        }
+        Ok(movie)
+    }
+
+    /// Sets up a freshly created interpreter to replay the movie: the seed,
+    /// random number mode, quirks and speed it was recorded with. Fails if
+    /// the interpreter is running a different ROM or platform.
+    pub fn configure(&self, interpreter: &mut Interpreter) -> Result<(), Chip8Error> {
+        if interpreter.rom_hash() != self.rom_hash {
+            return Err(invalid(format!(
+                "recorded with ROM {}, not {}",
+                self.rom_hash,
+                interpreter.rom_hash()
+            )));
+        }
+        if interpreter.platform() != self.platform {
+            return Err(invalid(format!(
+                "recorded on {}, not {}",
+                self.platform.name(),
+                interpreter.platform().name()
+            )));
+        }
+        interpreter.set_rng_mode(self.rng_mode);
+        interpreter.set_seed(self.seed);
+        interpreter.set_quirks(self.quirks);
+        interpreter.set_instructions_per_frame(self.instructions_per_frame);
+        Ok(())
+    }
+}
+
+fn invalid(reason: String) -> Chip8Error {
+    Chip8Error::InvalidMovie { reason }
+}
+
+/// Wraps another keyboard and appends the keys held in every frame to a
+/// movie.
+///
+/// The keys are read once at the start of each frame and held for the rest
+/// of it, so the program sees exactly what is recorded.
+pub struct RecordingKeyboard {
+    keyboard: Box<dyn Keyboard>,
+    movie: Arc<Mutex<Movie>>,
+    keys: u16,
+}
+
+impl RecordingKeyboard {
+    /// Records into `movie`, which the host keeps a handle to so it can save
+    /// it when the recording stops.
+    pub fn new(keyboard: Box<dyn Keyboard>, movie: Arc<Mutex<Movie>>) -> Self {
+        Self {
+            keyboard,
+            movie,
+            keys: 0,
+        }
+    }
+}
+
+impl Keyboard for RecordingKeyboard {
+    fn is_key_down(&self, key: u8) -> bool {
+        self.keys & (1 << key) != 0
+    }
+
+    fn get_pressed_key(&self) -> Option<u8> {
+        pressed_key(self.keys)
+    }
+
+    fn start_frame(&mut self) {
+        self.keys = self.keyboard.keys();
+        self.movie.lock().unwrap().frames.push(self.keys);
+    }
+}
+
+/// Replays the keys of a movie, one frame at a time. Once the movie runs
+/// out no keys are held.
+pub struct PlaybackKeyboard {
+    frames: Vec<u16>,
+    next_frame: usize,
+    keys: u16,
+}
+
+impl PlaybackKeyboard {
+    pub fn new(movie: &Movie) -> Self {
+        Self::from_frames(movie.frames.clone())
+    }
+
+    /// Replays `frames`, the keys held in each frame as bit masks.
+    pub fn from_frames(frames: Vec<u16>) -> Self {
+        Self {
+            frames,
+            next_frame: 0,
+            keys: 0,
+        }
+    }
+
+    /// Returns true once every recorded frame has been played.
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.frames.len()
+    }
+}
+
+impl Keyboard for PlaybackKeyboard {
+    fn is_key_down(&self, key: u8) -> bool {
+        self.keys & (1 << key) != 0
+    }
+
+    fn get_pressed_key(&self) -> Option<u8> {
+        pressed_key(self.keys)
+    }
+
+    fn start_frame(&mut self) {
+        self.keys = self.frames.get(self.next_frame).copied().unwrap_or(0);
+        self.next_frame += 1;
+    }
+}
+
+fn pressed_key(keys: u16) -> Option<u8> {
+    (0..16).find(|key| keys & (1 << key) != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keyboard::Keyboard;
+    use crate::movie::rom_hash;
+    use crate::movie::Movie;
+    use crate::movie::PlaybackKeyboard;
+    use crate::movie::RecordingKeyboard;
+    use crate::movie::MOVIE_VERSION;
+    use crate::platform::Platform;
+    use crate::quirks::Quirks;
+    use crate::rng::RngMode;
+    use std::sync::atomic::AtomicU16;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    fn movie(frames: Vec<u16>) -> Movie {
+        Movie {
+            version: MOVIE_VERSION,
+            rom_hash: rom_hash(&[0x12, 0x00]),
+            platform: Platform::Chip8,
+            seed: 99,
+            rng_mode: RngMode::Xorshift,
+            quirks: Quirks::OCTO,
+            instructions_per_frame: 10,
+            frames,
+        }
+    }
+
+    // Holds one more key every time it is read.
+    struct CountingKeyboard {
+        keys: AtomicU16,
+    }
+
+    impl Keyboard for CountingKeyboard {
+        fn is_key_down(&self, key: u8) -> bool {
+            self.keys() & (1 << key) != 0
+        }
+
+        fn get_pressed_key(&self) -> Option<u8> {
+            None
+        }
+
+        fn keys(&self) -> u16 {
+            self.keys.fetch_add(1, Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn test_rom_hash() {
+        assert_eq!(rom_hash(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    }
+
+    #[test]
+    fn test_movie_round_trip() {
+        let movie = movie(vec![0, 1, 0x8000]);
+        assert_eq!(Movie::from_bytes(&movie.to_bytes()), Ok(movie.clone()));
+        let mut future = movie;
+        future.version = MOVIE_VERSION + 1;
+        assert!(Movie::from_bytes(&future.to_bytes()).is_err());
+        assert!(Movie::from_bytes(b"not a movie").is_err());
+    }
+
+    #[test]
+    fn test_recording_latches_keys_per_frame() {
+        let recording = Arc::new(Mutex::new(movie(vec![])));
+        let mut keyboard = RecordingKeyboard::new(
+            Box::new(CountingKeyboard {
+                keys: AtomicU16::new(0b100),
+            }),
+            recording.clone(),
+        );
+        keyboard.start_frame();
+        assert!(keyboard.is_key_down(2));
+        assert!(keyboard.is_key_down(2));
+        assert_eq!(keyboard.get_pressed_key(), Some(2));
+        keyboard.start_frame();
+        assert!(keyboard.is_key_down(0));
+        assert_eq!(recording.lock().unwrap().frames, vec![0b100, 0b101]);
+    }
+
+    #[test]
+    fn test_playback() {
+        let mut keyboard = PlaybackKeyboard::new(&movie(vec![0b10, 0]));
+        assert!(!keyboard.is_finished());
+        keyboard.start_frame();
+        assert!(keyboard.is_key_down(1));
+        assert_eq!(keyboard.get_pressed_key(), Some(1));
+        keyboard.start_frame();
+        assert_eq!(keyboard.get_pressed_key(), None);
+        assert!(keyboard.is_finished());
+        keyboard.start_frame();
+        assert!(!keyboard.is_key_down(1));
+    }
+}
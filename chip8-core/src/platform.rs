@@ -0,0 +1,115 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::Path;
+
+/// The CHIP-8 variant a ROM was written for.
+///
+/// The platform decides how much memory the machine has and which of the
+/// SUPER-CHIP and XO-CHIP instructions are decoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Platform {
+    #[default]
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Platform {
+    /// The names accepted by `Platform::from_name`, in the order a host
+    /// should offer them.
+    pub const NAMES: [&'static str; 3] = ["chip-8", "super-chip", "xo-chip"];
+
+    pub fn from_name(name: &str) -> Option<Platform> {
+        match name {
+            "chip-8" => Some(Platform::Chip8),
+            "super-chip" => Some(Platform::SuperChip),
+            "xo-chip" => Some(Platform::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Chip8 => "chip-8",
+            Platform::SuperChip => "super-chip",
+            Platform::XoChip => "xo-chip",
+        }
+    }
+
+    /// Guesses the platform from a ROM's file extension, following the
+    /// chip8Archive's `.ch8`, `.sc8` and `.xo8` convention. Octo `.8o` sources
+    /// can use every extension, so they get XO-CHIP. Anything else is treated
+    /// as plain CHIP-8.
+    pub fn from_path(path: &Path) -> Platform {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("sc8") => Platform::SuperChip,
+            Some("xo8") | Some("8o") => Platform::XoChip,
+            _ => Platform::Chip8,
+        }
+    }
+
+    /// The size of RAM in bytes: 4 KiB, or 64 KiB for XO-CHIP.
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Platform::Chip8 | Platform::SuperChip => 0x1000,
+            Platform::XoChip => 0x10000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::platform::Platform;
+    use std::path::Path;
+
+    #[test]
+    fn every_platform_name_resolves() {
+        for name in Platform::NAMES {
+            assert_eq!(Platform::from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(Platform::from_name("chip-8x"), None);
+    }
+
+    #[test]
+    fn platform_from_path() {
+        assert_eq!(
+            Platform::from_path(Path::new("roms/br8kout.ch8")),
+            Platform::Chip8
+        );
+        assert_eq!(
+            Platform::from_path(Path::new("roms/ANT.SC8")),
+            Platform::SuperChip
+        );
+        assert_eq!(
+            Platform::from_path(Path::new("roms/chicken.xo8")),
+            Platform::XoChip
+        );
+        assert_eq!(
+            Platform::from_path(Path::new("src/game.8o")),
+            Platform::XoChip
+        );
+        assert_eq!(Platform::from_path(Path::new("roms/rom")), Platform::Chip8);
+    }
+}
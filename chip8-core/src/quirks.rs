@@ -0,0 +1,143 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Switches for the instructions whose behaviour differs between CHIP-8
+/// implementations.
+///
+/// Each flag is named after the behaviour it turns on. Programs are usually
+/// written against one of the presets below, so most hosts pick a preset and
+/// only touch individual flags to work around a particular ROM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quirks {
+    /// 8xy6 and 8xyE shift Vx in place and ignore Vy. When off, Vy is
+    /// shifted and the result stored in Vx.
+    pub shift: bool,
+    /// Fx55 and Fx65 leave I pointing just past the last register they
+    /// touched. When off, I is unchanged.
+    pub memory_increment: bool,
+    /// With `memory_increment`, Fx55 and Fx65 leave I pointing at the last
+    /// register they touched instead, as CHIP-48 does.
+    pub memory_increment_by_x: bool,
+    /// Bnnn jumps to nnn plus Vx, where x is the highest nibble of nnn. When
+    /// off, it jumps to nnn plus V0.
+    pub jump: bool,
+    /// 8xy1, 8xy2 and 8xy3 set VF to 0.
+    pub vf_reset: bool,
+    /// Sprites that cross the edge of the screen are cut off. When off, they
+    /// wrap around to the opposite edge.
+    pub clipping: bool,
+    /// Drawing a sprite waits for the next frame, so at most one Dxyn runs
+    /// per frame.
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    /// The original interpreter on the RCA COSMAC VIP.
+    pub const COSMAC_VIP: Quirks = Quirks {
+        shift: false,
+        memory_increment: true,
+        memory_increment_by_x: false,
+        jump: false,
+        vf_reset: true,
+        clipping: true,
+        display_wait: true,
+    };
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub const CHIP_48: Quirks = Quirks {
+        shift: true,
+        memory_increment: true,
+        memory_increment_by_x: true,
+        jump: true,
+        vf_reset: false,
+        clipping: true,
+        display_wait: false,
+    };
+
+    /// SUPER-CHIP 1.1 on the HP-48 calculators, which is CHIP-48 except
+    /// that Fx55 and Fx65 leave I alone.
+    pub const SUPER_CHIP: Quirks = Quirks {
+        shift: true,
+        memory_increment: false,
+        memory_increment_by_x: false,
+        jump: true,
+        vf_reset: false,
+        clipping: true,
+        display_wait: false,
+    };
+
+    /// The Octo IDE's defaults, which most programs in the chip8Archive are
+    /// written against.
+    pub const OCTO: Quirks = Quirks {
+        shift: false,
+        memory_increment: true,
+        memory_increment_by_x: false,
+        jump: false,
+        vf_reset: false,
+        clipping: false,
+        display_wait: false,
+    };
+
+    /// The names accepted by `Quirks::preset`, in the order a host should
+    /// offer them.
+    pub const PRESETS: [&'static str; 4] = ["cosmac-vip", "chip-48", "super-chip", "octo"];
+
+    /// Looks up a preset by name.
+    pub fn preset(name: &str) -> Option<Quirks> {
+        match name {
+            "cosmac-vip" => Some(Quirks::COSMAC_VIP),
+            "chip-48" => Some(Quirks::CHIP_48),
+            "super-chip" => Some(Quirks::SUPER_CHIP),
+            "octo" => Some(Quirks::OCTO),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quirks {
+    /// The behaviour this interpreter had before quirks were configurable:
+    /// in place shifts, I left alone by Fx55/Fx65, Bnnn relative to V0 and
+    /// wrapping sprites.
+    fn default() -> Self {
+        Quirks {
+            shift: true,
+            memory_increment: false,
+            memory_increment_by_x: false,
+            jump: false,
+            vf_reset: false,
+            clipping: false,
+            display_wait: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quirks::Quirks;
+
+    #[test]
+    fn every_preset_name_resolves() {
+        for name in Quirks::PRESETS {
+            assert!(Quirks::preset(name).is_some());
+        }
+        assert_eq!(Quirks::preset("octo"), Some(Quirks::OCTO));
+        assert_eq!(Quirks::preset("chip-8x"), None);
+    }
+}
@@ -25,14 +25,16 @@ use std::ops::ShlAssign;
 use std::ops::ShrAssign;
 use std::ops::Sub;
 use std::ops::SubAssign;
-use std::sync::Arc;
-use std::sync::LockResult;
-use std::sync::Mutex;
-use std::sync::MutexGuard;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Address(u16);
 
+impl Default for Address {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Address {
     pub fn new() -> Self {
         Address(0)
@@ -80,6 +82,12 @@ impl std::ops::SubAssign<u16> for Address {
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct AddressRegister(u16);
 
+impl Default for AddressRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AddressRegister {
     pub fn new() -> Self {
         Self(0)
@@ -96,6 +104,12 @@ impl std::ops::AddAssign<Register> for AddressRegister {
     }
 }
 
+impl From<AddressRegister> for u16 {
+    fn from(i: AddressRegister) -> u16 {
+        i.0
+    }
+}
+
 impl From<AddressRegister> for usize {
     fn from(i: AddressRegister) -> usize {
         i.0 as usize
@@ -113,33 +127,25 @@ impl Register {
 
 impl From<DelayTimer> for Register {
     fn from(delay_timer: DelayTimer) -> Self {
-        Self(*delay_timer.0.lock().unwrap())
+        Self(delay_timer.0)
     }
 }
 
 impl From<SoundTimer> for Register {
     fn from(sound_timer: SoundTimer) -> Self {
-        Self(*sound_timer.0.lock().unwrap())
+        Self(sound_timer.0)
     }
 }
 impl PartialEq<u8> for Register {
     fn eq(&self, other: &u8) -> bool {
         self.0 == *other
     }
-
-    fn ne(&self, other: &u8) -> bool {
-        self.0 != *other
-    }
 }
 
 impl PartialEq for Register {
     fn eq(&self, other: &Self) -> bool {
         self.0 == other.0
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.0 != other.0
-    }
 }
 
 impl PartialOrd for Register {
@@ -227,6 +233,12 @@ pub struct ProgramCounter {
     pub value: Address,
 }
 
+impl Default for ProgramCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProgramCounter {
     pub fn new() -> Self {
         Self {
@@ -259,54 +271,61 @@ impl From<&ProgramCounter> for usize {
     }
 }
 
-#[derive(Debug)]
-pub struct DelayTimer(Arc<Mutex<u8>>);
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct DelayTimer(u8);
 
 impl DelayTimer {
     pub fn new() -> Self {
-        Self(Arc::new(Mutex::new(0)))
+        Self(0)
     }
 
-    pub fn lock(&self) -> LockResult<MutexGuard<'_, u8>> {
-        self.0.lock()
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+
+    pub fn set(&mut self, value: u8) {
+        self.0 = value;
     }
-}
 
-impl Clone for DelayTimer {
-    fn clone(&self) -> Self {
-        Self(Arc::clone(&self.0))
+    pub fn tick(&mut self) {
+        self.0 = self.0.saturating_sub(1);
     }
 }
 
 impl From<Register> for DelayTimer {
     fn from(register: Register) -> Self {
-        Self(Arc::new(Mutex::new(register.into())))
+        Self(register.into())
     }
 }
 
-pub struct SoundTimer(Arc<Mutex<u8>>);
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SoundTimer(u8);
 
 impl SoundTimer {
     pub fn new() -> Self {
-        Self(Arc::new(Mutex::new(0)))
+        Self(0)
     }
 
-    pub fn lock(&self) -> LockResult<MutexGuard<'_, u8>> {
-        self.0.lock()
+    pub fn get(&self) -> u8 {
+        self.0
     }
-}
 
-impl Clone for SoundTimer {
-    fn clone(&self) -> Self {
-        Self(Arc::clone(&self.0))
+    pub fn set(&mut self, value: u8) {
+        self.0 = value;
+    }
+
+    pub fn tick(&mut self) {
+        self.0 = self.0.saturating_sub(1);
     }
 }
 #[cfg(test)]
 mod tests {
     use crate::registers::Address;
     use crate::registers::AddressRegister;
+    use crate::registers::DelayTimer;
     use crate::registers::ProgramCounter;
     use crate::registers::Register;
+    use crate::registers::SoundTimer;
 
     #[test]
     fn program_counter_increments() {
@@ -358,6 +377,24 @@ mod tests {
         assert_eq!(address_register_2.0, 0);
     }
 
+    #[test]
+    fn delay_timer_tick() {
+        let mut delay_timer = DelayTimer::new();
+        delay_timer.set(1);
+        delay_timer.tick();
+        assert_eq!(delay_timer.get(), 0);
+        delay_timer.tick();
+        assert_eq!(delay_timer.get(), 0);
+    }
+
+    #[test]
+    fn sound_timer_tick() {
+        let mut sound_timer = SoundTimer::new();
+        sound_timer.set(2);
+        sound_timer.tick();
+        assert_eq!(sound_timer.get(), 1);
+    }
+
     #[test]
     fn register_add_assign() {
         let mut register = Register::from(0);
@@ -385,38 +422,38 @@ mod tests {
     #[test]
     fn register_eq_u8() {
         let register = Register::from(0xFE);
-        assert_eq!(register.0 == 0xFE, true);
+        assert!(register.0 == 0xFE);
     }
     #[test]
     fn register_ne_u8() {
         let register = Register::from(0xFE);
-        assert_eq!(register.0 != 0xFA, true);
+        assert!(register.0 != 0xFA);
     }
     #[test]
     fn register_eq_register() {
-        assert_eq!(Register::from(0xFE) == Register::from(0xFE), true);
+        assert!(Register::from(0xFE) == Register::from(0xFE));
     }
     #[test]
     fn register_ne_register() {
-        assert_eq!(Register::from(0xFA) != Register::from(0xFE), true);
+        assert!(Register::from(0xFA) != Register::from(0xFE));
     }
     #[test]
     fn register_ge_register() {
-        assert_eq!(Register::from(0xFF) >= Register::from(0xFF), true);
-        assert_eq!(Register::from(0xFF) >= Register::from(0xFE), true);
+        assert!(Register::from(0xFF) >= Register::from(0xFF));
+        assert!(Register::from(0xFF) >= Register::from(0xFE));
     }
     #[test]
     fn register_gt_register() {
-        assert_eq!(Register::from(0xFF) > Register::from(0xFE), true);
+        assert!(Register::from(0xFF) > Register::from(0xFE));
     }
     #[test]
     fn register_le() {
-        assert_eq!(Register::from(0xFF) <= Register::from(0xFF), true);
-        assert_eq!(Register::from(0xFE) <= Register::from(0xFF), true);
+        assert!(Register::from(0xFF) <= Register::from(0xFF));
+        assert!(Register::from(0xFE) <= Register::from(0xFF));
     }
     #[test]
     fn register_lt() {
-        assert_eq!(Register::from(0xFE) < Register::from(0xFF), true);
+        assert!(Register::from(0xFE) < Register::from(0xFF));
     }
     #[test]
     fn register_shift_left_assign() {
@@ -0,0 +1,271 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::chip8::Interpreter;
+use crate::error::Chip8Error;
+use crate::snapshot::Snapshot;
+use std::collections::VecDeque;
+use std::mem;
+
+/// A recorded frame with its memory and pixels taken out.
+struct Frame {
+    state: Snapshot,
+    /// How long memory and the pixels are together in this frame.
+    length: usize,
+    /// The bytes to change in the next newer frame's memory and pixels to
+    /// get this frame's, by offset. Empty for the newest frame.
+    changes: Vec<(u32, u8)>,
+}
+
+impl Frame {
+    fn size(&self) -> usize {
+        mem::size_of::<Frame>() + self.changes.len() * mem::size_of::<(u32, u8)>()
+    }
+}
+
+/// A ring buffer of the machine's state at the start of recent frames, for
+/// playing a game backwards.
+///
+/// Call `record` before every `Interpreter::run_frame` and `rewind` to step
+/// back one frame at a time; running frames again resumes from wherever the
+/// rewind stopped. Only the newest frame keeps a full copy of memory and
+/// the screen, older ones keep the bytes that differ from the frame after
+/// them. The oldest frames are dropped once the buffer would use more than
+/// its memory budget.
+pub struct RewindBuffer {
+    frames: VecDeque<Frame>,
+    /// Memory followed by the pixels of the newest frame.
+    newest: Vec<u8>,
+    budget: usize,
+    used: usize,
+}
+
+impl RewindBuffer {
+    /// 16 MiB, which holds several minutes of a typical CHIP-8 game.
+    pub const DEFAULT_BUDGET: usize = 16 * 1024 * 1024;
+
+    /// Creates an empty buffer that uses at most `budget` bytes.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            newest: Vec::new(),
+            budget,
+            used: 0,
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The approximate number of bytes the recorded frames take up.
+    pub fn memory_used(&self) -> usize {
+        self.used + self.newest.len()
+    }
+
+    /// The number of frames that can be rewound.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.newest.clear();
+        self.used = 0;
+    }
+
+    /// Records the interpreter's current state as the newest frame.
+    pub fn record(&mut self, interpreter: &Interpreter) {
+        self.push(interpreter.snapshot());
+    }
+
+    /// Puts the interpreter back to the newest recorded frame and forgets
+    /// it. Returns false, leaving the interpreter alone, if there is
+    /// nothing left to rewind.
+    pub fn rewind(&mut self, interpreter: &mut Interpreter) -> Result<bool, Chip8Error> {
+        match self.pop() {
+            Some(snapshot) => {
+                interpreter.restore(&snapshot)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Adds `snapshot` as the newest frame.
+    pub fn push(&mut self, mut snapshot: Snapshot) {
+        let mut bulk = mem::take(&mut snapshot.memory);
+        bulk.append(snapshot.frame_buffer.pixels_mut());
+        if let Some(previous) = self.frames.back_mut() {
+            previous.changes = self
+                .newest
+                .iter()
+                .enumerate()
+                .filter(|(offset, byte)| bulk.get(*offset) != Some(byte))
+                .map(|(offset, byte)| (offset as u32, *byte))
+                .collect();
+            self.used += previous.changes.len() * mem::size_of::<(u32, u8)>();
+        }
+        let frame = Frame {
+            state: snapshot,
+            length: bulk.len(),
+            changes: Vec::new(),
+        };
+        self.used += frame.size();
+        self.frames.push_back(frame);
+        self.newest = bulk;
+        self.evict();
+    }
+
+    /// Removes and returns the newest frame.
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        let frame = self.frames.pop_back()?;
+        self.used -= frame.size();
+        let mut bulk = mem::take(&mut self.newest);
+        if let Some(previous) = self.frames.back_mut() {
+            self.used -= previous.changes.len() * mem::size_of::<(u32, u8)>();
+            let mut older = bulk.clone();
+            older.resize(previous.length, 0);
+            for (offset, byte) in mem::take(&mut previous.changes) {
+                older[offset as usize] = byte;
+            }
+            self.newest = older;
+        }
+        let mut snapshot = frame.state;
+        let pixels = bulk.split_off(snapshot.platform.memory_size());
+        snapshot.memory = bulk;
+        *snapshot.frame_buffer.pixels_mut() = pixels;
+        Some(snapshot)
+    }
+
+    /// Drops the oldest frames until the buffer fits in its budget, always
+    /// keeping the newest.
+    fn evict(&mut self) {
+        while self.memory_used() > self.budget && self.frames.len() > 1 {
+            let frame = self.frames.pop_front().unwrap();
+            self.used -= frame.size();
+        }
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(RewindBuffer::DEFAULT_BUDGET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::FrameBuffer;
+    use crate::platform::Platform;
+    use crate::rewind::RewindBuffer;
+    use crate::rng::Rng;
+    use crate::snapshot::Snapshot;
+    use crate::snapshot::SNAPSHOT_VERSION;
+
+    fn snapshot(frame: u8) -> Snapshot {
+        let mut memory = vec![0; 0x1000];
+        memory[0x300] = frame;
+        memory[0x400 + usize::from(frame)] = 0xFF;
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            platform: Platform::Chip8,
+            memory,
+            stack: [0; 16],
+            stack_pointer: 0,
+            program_counter: 0x200 + 2 * u16::from(frame),
+            address_register: 0,
+            registers: [frame; 16],
+            delay_timer: 0,
+            sound_timer: 0,
+            frame_buffer: FrameBuffer::new(),
+            rng: Rng::from_seed(u64::from(frame)),
+            halted: false,
+            rpl_flags: [0; 16],
+            audio_pattern: [0; 16],
+            pitch: 64,
+        }
+    }
+
+    #[test]
+    fn rewind_returns_frames_newest_first() {
+        let mut buffer = RewindBuffer::default();
+        for frame in 0..150 {
+            buffer.push(snapshot(frame));
+        }
+        assert_eq!(buffer.len(), 150);
+        for frame in (0..150).rev() {
+            assert_eq!(buffer.pop(), Some(snapshot(frame)));
+        }
+        assert_eq!(buffer.pop(), None);
+        assert_eq!(buffer.memory_used(), 0);
+    }
+
+    #[test]
+    fn older_frames_only_keep_changes() {
+        let mut buffer = RewindBuffer::default();
+        buffer.push(snapshot(0));
+        let first = buffer.memory_used();
+        buffer.push(snapshot(1));
+        assert!(buffer.memory_used() - first < first / 4);
+    }
+
+    #[test]
+    fn oldest_frames_are_dropped_over_budget() {
+        let mut buffer = RewindBuffer::default();
+        buffer.push(snapshot(0));
+        let budget = buffer.memory_used() * 3;
+        let mut buffer = RewindBuffer::new(budget);
+        for frame in 0..200 {
+            buffer.push(snapshot(frame));
+            assert!(buffer.memory_used() <= budget);
+        }
+        assert!(!buffer.is_empty());
+        assert_eq!(buffer.pop(), Some(snapshot(199)));
+    }
+
+    #[test]
+    fn resolution_changes_are_rewound() {
+        let mut buffer = RewindBuffer::default();
+        buffer.push(snapshot(0));
+        let mut high_resolution = snapshot(1);
+        high_resolution.frame_buffer.set_high_resolution(true);
+        buffer.push(high_resolution.clone());
+        buffer.push(snapshot(2));
+        assert_eq!(buffer.pop(), Some(snapshot(2)));
+        assert_eq!(buffer.pop(), Some(high_resolution));
+        assert_eq!(buffer.pop(), Some(snapshot(0)));
+    }
+
+    #[test]
+    fn record_after_rewind_resumes() {
+        let mut buffer = RewindBuffer::default();
+        for frame in 0..10 {
+            buffer.push(snapshot(frame));
+        }
+        for _ in 0..5 {
+            buffer.pop();
+        }
+        buffer.push(snapshot(100));
+        assert_eq!(buffer.pop(), Some(snapshot(100)));
+        assert_eq!(buffer.pop(), Some(snapshot(4)));
+    }
+}
@@ -0,0 +1,173 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// How `Rng` turns its state into random bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RngMode {
+    /// xorshift64*, which gives good quality bytes.
+    #[default]
+    Xorshift,
+    /// Modelled on the COSMAC VIP interpreter, which stepped a pointer
+    /// through a page of memory and added the byte it found to the last
+    /// result. Sequences are short and repetitive like the VIP's, which some
+    /// programs were tuned against. It is not bit for bit identical, since
+    /// the page read is the bottom of this interpreter's memory rather than
+    /// the VIP's own code.
+    CosmacVip,
+}
+
+impl RngMode {
+    /// The names accepted by `RngMode::from_name`.
+    pub const NAMES: [&'static str; 2] = ["xorshift", "cosmac-vip"];
+
+    pub fn from_name(name: &str) -> Option<RngMode> {
+        match name {
+            "xorshift" => Some(RngMode::Xorshift),
+            "cosmac-vip" => Some(RngMode::CosmacVip),
+            _ => None,
+        }
+    }
+}
+
+/// The random number generator behind Cxkk.
+///
+/// The generator is seeded once and its whole state is a few numbers that
+/// are saved with the rest of the machine, so a run started from the same
+/// seed, or restored from a snapshot, produces the same sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rng {
+    seed: u64,
+    mode: RngMode,
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator seeded from the operating system.
+    pub fn new() -> Self {
+        Rng::from_seed(rand::random())
+    }
+
+    /// Creates a generator that always produces the same sequence for the
+    /// same seed.
+    pub fn from_seed(seed: u64) -> Self {
+        Rng::with_mode(seed, RngMode::Xorshift)
+    }
+
+    pub fn with_mode(seed: u64, mode: RngMode) -> Self {
+        // splitmix64, so that similar seeds give unrelated sequences and the
+        // state is never zero
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Rng {
+            seed,
+            mode,
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    /// The seed the generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn mode(&self) -> RngMode {
+        self.mode
+    }
+
+    /// Returns true if the generator can produce numbers. A state of zero,
+    /// which only a corrupt save state can hold, would produce zeros forever.
+    pub fn is_valid(&self) -> bool {
+        self.state != 0
+    }
+
+    /// Returns the next random byte. `page` is the memory a COSMAC VIP
+    /// generator reads from and is ignored in the other modes.
+    pub fn next_byte(&mut self, page: &[u8]) -> u8 {
+        match self.mode {
+            RngMode::Xorshift => {
+                self.state ^= self.state >> 12;
+                self.state ^= self.state << 25;
+                self.state ^= self.state >> 27;
+                (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+            }
+            RngMode::CosmacVip => {
+                // the low byte is the pointer and the next byte the last
+                // result; the rest of the state is left alone so it never
+                // becomes zero
+                let pointer = (self.state as u8).wrapping_add(1);
+                let byte = page.get(usize::from(pointer)).copied().unwrap_or(0);
+                let result = ((self.state >> 8) as u8)
+                    .wrapping_add(byte)
+                    .wrapping_add(pointer);
+                self.state = (self.state & !0xFFFF) | u64::from(result) << 8 | u64::from(pointer);
+                result
+            }
+        }
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+    use crate::rng::RngMode;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::from_seed(42);
+        let mut b = Rng::from_seed(42);
+        let mut c = Rng::from_seed(43);
+        let a: Vec<u8> = (0..32).map(|_| a.next_byte(&[])).collect();
+        let b: Vec<u8> = (0..32).map(|_| b.next_byte(&[])).collect();
+        let c: Vec<u8> = (0..32).map(|_| c.next_byte(&[])).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn zero_seed_is_valid() {
+        assert!(Rng::from_seed(0).is_valid());
+    }
+
+    #[test]
+    fn cosmac_vip_mode_walks_the_page() {
+        let page: Vec<u8> = (0..=255).collect();
+        let mut rng = Rng::with_mode(7, RngMode::CosmacVip);
+        let first: Vec<u8> = (0..512).map(|_| rng.next_byte(&page)).collect();
+        let mut again = Rng::with_mode(7, RngMode::CosmacVip);
+        let second: Vec<u8> = (0..512).map(|_| again.next_byte(&page)).collect();
+        assert_eq!(first, second);
+        assert!(rng.is_valid());
+        assert_eq!(rng.seed(), 7);
+        assert_eq!(rng.mode(), RngMode::CosmacVip);
+        assert_eq!(
+            RngMode::from_name(RngMode::NAMES[1]),
+            Some(RngMode::CosmacVip)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::error::Chip8Error;
+use crate::graphics::FrameBuffer;
+use crate::platform::Platform;
+use crate::rng::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The version written into new snapshots. Snapshots with any other version
+/// are rejected, so this must change whenever a field is added, removed or
+/// reinterpreted.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Everything needed to put an `Interpreter` back exactly as it was.
+///
+/// Snapshots are taken with `Interpreter::snapshot` and applied with
+/// `Interpreter::restore`. `to_bytes` and `from_bytes` convert them to and
+/// from JSON for saving to disk; `from_bytes` validates what it reads.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub platform: Platform,
+    pub memory: Vec<u8>,
+    pub stack: [u16; 16],
+    pub stack_pointer: u8,
+    pub program_counter: u16,
+    pub address_register: u16,
+    pub registers: [u8; 16],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub frame_buffer: FrameBuffer,
+    pub rng: Rng,
+    pub halted: bool,
+    pub rpl_flags: [u8; 16],
+    pub audio_pattern: [u8; 16],
+    pub pitch: u8,
+}
+
+impl Snapshot {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("snapshots always serialize")
+    }
+
+    /// Reads a snapshot written by `to_bytes` and checks that it is valid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, Chip8Error> {
+        let snapshot: Snapshot = serde_json::from_slice(bytes)
+            .map_err(|error| invalid(format!("unreadable: {}", error)))?;
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    /// Checks that the snapshot is for this version and describes a machine
+    /// that could exist.
+    pub fn validate(&self) -> Result<(), Chip8Error> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(invalid(format!(
+                "version {} is not supported, expected {}",
+                self.version, SNAPSHOT_VERSION
+            )));
+        }
+        if self.memory.len() != self.platform.memory_size() {
+            return Err(invalid(format!(
+                "{} bytes of memory is wrong for {}",
+                self.memory.len(),
+                self.platform.name()
+            )));
+        }
+        if usize::from(self.stack_pointer) > self.stack.len() {
+            return Err(invalid(format!(
+                "stack pointer {} is past the end of the stack",
+                self.stack_pointer
+            )));
+        }
+        if usize::from(self.program_counter) >= self.memory.len() {
+            return Err(invalid(format!(
+                "program counter {:#05X} is outside memory",
+                self.program_counter
+            )));
+        }
+        if !self.frame_buffer.is_valid() {
+            return Err(invalid("the display buffer is corrupt".to_string()));
+        }
+        if !self.rng.is_valid() {
+            return Err(invalid(
+                "the random number generator is corrupt".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn invalid(reason: String) -> Chip8Error {
+    Chip8Error::InvalidSnapshot { reason }
+}
@@ -0,0 +1,329 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Names for addresses, so debuggers and listings can say `draw_player`
+//! instead of `0x2A4`.
+//!
+//! Symbols come from the assembler or from a symbol file, which is either
+//! JSON, mapping names to addresses directly or under `"labels"` as Octo
+//! exports them, or text with a name and an address on each line in either
+//! order:
+//!
+//! ```text
+//! # rom 0b3f0c5e2f8a2b4cb1b6b4f0b4c7e1c6f3a3d1e2
+//! main 0x200
+//! 0x2A4 draw_player
+//! ```
+//!
+//! A `# rom` line, or a `"rom"` key, gives the hash of the ROM the symbols
+//! were made for, so that stale symbols aren't loaded for a changed ROM.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// The extension of symbol files looked for next to a ROM.
+pub const EXTENSION: &str = "sym";
+
+/// Why a symbol file could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolsError {
+    pub reason: String,
+}
+
+impl fmt::Display for SymbolsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid symbols: {}", self.reason)
+    }
+}
+
+impl std::error::Error for SymbolsError {}
+
+/// Labels and their addresses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Symbols {
+    /// The hash of the ROM the symbols describe, if known.
+    pub rom_hash: Option<String>,
+    addresses: BTreeMap<String, u16>,
+    /// The first name given to each address.
+    names: BTreeMap<u16, String>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects labels, such as those of an `Assembly`.
+    pub fn from_labels<'a>(labels: impl IntoIterator<Item = (&'a String, &'a u16)>) -> Self {
+        let mut symbols = Symbols::new();
+        for (name, address) in labels {
+            symbols.insert(name, *address);
+        }
+        symbols
+    }
+
+    /// Reads a symbol file in either format.
+    pub fn parse(text: &str) -> Result<Symbols, SymbolsError> {
+        match text.trim_start().starts_with('{') {
+            true => Symbols::parse_json(text),
+            false => Symbols::parse_text(text),
+        }
+    }
+
+    fn parse_json(text: &str) -> Result<Symbols, SymbolsError> {
+        let json: Value = serde_json::from_str(text).map_err(|error| invalid(error.to_string()))?;
+        let labels = match json.get("labels") {
+            Some(labels) => labels,
+            None => &json,
+        };
+        let labels = labels
+            .as_object()
+            .ok_or_else(|| invalid("the labels are not an object".to_string()))?;
+        let mut symbols = Symbols::new();
+        symbols.rom_hash = json["rom"].as_str().map(str::to_string);
+        for (name, address) in labels {
+            if name == "rom" {
+                continue;
+            }
+            let parsed = match address {
+                Value::Number(number) => number
+                    .as_u64()
+                    .and_then(|number| u16::try_from(number).ok()),
+                Value::String(text) => parse_address(text),
+                _ => None,
+            };
+            let address = parsed.ok_or_else(|| invalid(format!("'{}' has no address", name)))?;
+            symbols.insert(name, address);
+        }
+        Ok(symbols)
+    }
+
+    fn parse_text(text: &str) -> Result<Symbols, SymbolsError> {
+        let mut symbols = Symbols::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(hash) = comment.trim().strip_prefix("rom ") {
+                    symbols.rom_hash = Some(hash.trim().to_string());
+                }
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (name, address) = match words[..] {
+                [] => continue,
+                [first, second] => match (parse_address(first), parse_address(second)) {
+                    (Some(address), _) => (second, address),
+                    (None, Some(address)) => (first, address),
+                    (None, None) => {
+                        return Err(invalid(format!("line {} has no address", number + 1)))
+                    }
+                },
+                _ => {
+                    return Err(invalid(format!(
+                        "line {} is not a name and an address",
+                        number + 1
+                    )))
+                }
+            };
+            symbols.insert(name, address);
+        }
+        Ok(symbols)
+    }
+
+    /// Writes the symbols as text, which `parse` reads back.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(hash) = &self.rom_hash {
+            text.push_str(&format!("# rom {}\n", hash));
+        }
+        for (name, address) in &self.addresses {
+            text.push_str(&format!("{} 0x{:03X}\n", name, address));
+        }
+        text
+    }
+
+    /// Names `address`, replacing any name it had.
+    pub fn insert(&mut self, name: &str, address: u16) {
+        if let Some(old) = self.addresses.insert(name.to_string(), address) {
+            if self
+                .names
+                .get(&old)
+                .is_some_and(|existing| existing == name)
+            {
+                // fall back to another name the old address still has
+                match self.addresses.iter().find(|(_, &address)| address == old) {
+                    Some((other, _)) => self.names.insert(old, other.clone()),
+                    None => self.names.remove(&old),
+                };
+            }
+        }
+        self.names
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// The name of `address` itself.
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.names.get(&address).map(String::as_str)
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.addresses.get(name).copied()
+    }
+
+    /// Every name and its address, by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.addresses
+            .iter()
+            .map(|(name, address)| (name.as_str(), *address))
+    }
+
+    /// Describes `address` by the nearest label at or before it, such as
+    /// `draw_player` or `draw_player+4`, or in hexadecimal before the first
+    /// label.
+    pub fn describe(&self, address: u16) -> String {
+        self.locate(address)
+            .unwrap_or_else(|| format!("0x{:03X}", address))
+    }
+
+    /// Describes `address` like `describe`, or `None` before the first
+    /// label.
+    pub fn locate(&self, address: u16) -> Option<String> {
+        match self.names.range(..=address).next_back()? {
+            (&start, name) if start == address => Some(name.clone()),
+            (&start, name) => Some(format!("{}+{}", name, address - start)),
+        }
+    }
+
+    /// Whether the symbols may describe the ROM with `rom_hash`: they do
+    /// unless they name another ROM.
+    pub fn matches(&self, rom_hash: &str) -> bool {
+        self.rom_hash
+            .as_deref()
+            .is_none_or(|hash| hash.eq_ignore_ascii_case(rom_hash))
+    }
+
+    /// Loads the symbols kept next to the ROM at `rom_path`: a file named
+    /// after the ROM's hash, such as `0b3f...e2.sym`, or failing that after
+    /// the ROM, such as `game.sym`. Files that don't parse or that were made
+    /// for another ROM are passed over.
+    pub fn find(rom_path: &Path, rom_hash: &str) -> Option<Symbols> {
+        let directory = rom_path.parent().unwrap_or_else(|| Path::new(""));
+        let candidates = [
+            directory.join(format!("{}.{}", rom_hash, EXTENSION)),
+            rom_path.with_extension(EXTENSION),
+        ];
+        candidates
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .filter_map(|text| Symbols::parse(&text).ok())
+            .find(|symbols| symbols.matches(rom_hash))
+    }
+}
+
+/// Reads an address, in hexadecimal with a `0x` or `$` prefix or in
+/// decimal.
+fn parse_address(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix('$')) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn invalid(reason: String) -> SymbolsError {
+    SymbolsError { reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbols::Symbols;
+    use crate::symbols::SymbolsError;
+
+    #[test]
+    fn parse_text() {
+        let symbols = Symbols::parse(
+            "# rom ABC123
+main 0x200
+0x2A4 draw_player
+# a comment
+loop 522
+",
+        )
+        .unwrap();
+        assert_eq!(symbols.rom_hash.as_deref(), Some("ABC123"));
+        assert_eq!(symbols.address("draw_player"), Some(0x2A4));
+        assert_eq!(symbols.name(0x20A), Some("loop"));
+        assert_eq!(Symbols::parse(&symbols.to_text()).unwrap(), symbols);
+        assert!(matches!(
+            Symbols::parse("main 0x200\nnothing here\n"),
+            Err(SymbolsError { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_json() {
+        let octo = Symbols::parse(r#"{ "labels": { "main": 512, "draw": "0x2A4" } }"#).unwrap();
+        let flat = Symbols::parse(r#"{ "main": 512, "draw": 676 }"#).unwrap();
+        assert_eq!(octo, flat);
+        assert_eq!(octo.len(), 2);
+        let keyed = Symbols::parse(r#"{ "rom": "abc123", "labels": { "main": 512 } }"#).unwrap();
+        assert!(keyed.matches("ABC123"));
+        assert!(!keyed.matches("def456"));
+        assert!(Symbols::parse(r#"{ "main": true }"#).is_err());
+    }
+
+    #[test]
+    fn describe() {
+        let mut symbols = Symbols::new();
+        symbols.insert("main", 0x200);
+        symbols.insert("draw_player", 0x2A4);
+        symbols.insert("also_draw", 0x2A4);
+        assert_eq!(symbols.describe(0x1FE), "0x1FE");
+        assert_eq!(symbols.locate(0x1FE), None);
+        assert_eq!(symbols.describe(0x2A4), "draw_player");
+        assert_eq!(symbols.describe(0x2A8), "draw_player+4");
+        symbols.insert("draw_player", 0x300);
+        assert_eq!(symbols.name(0x2A4), Some("also_draw"));
+        assert_eq!(symbols.describe(0x2A8), "also_draw+4");
+        assert_eq!(symbols.name(0x300), Some("draw_player"));
+    }
+
+    #[test]
+    fn find() {
+        let directory = std::env::temp_dir().join(format!("chip8-symbols-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let rom = directory.join("game.ch8");
+        std::fs::write(directory.join("game.sym"), "# rom other\nmain 0x200\n").unwrap();
+        assert_eq!(Symbols::find(&rom, "abc"), None);
+        std::fs::write(directory.join("abc.sym"), "draw 0x2A4\n").unwrap();
+        assert_eq!(
+            Symbols::find(&rom, "abc").unwrap().address("draw"),
+            Some(0x2A4)
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+/* chip8 - A cross platform CHIP-8 interpreter.
+ * Copyright (C) 2022  James D. Hasselman
+ * 
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ * 
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ * 
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Runs small programs end to end and compares the machine they leave
+//! behind with the golden files in `tests/golden`.
+//!
+//! Each golden file holds the program counter, I, the stack pointer, the
+//! registers and the screen, with `.` for an unlit pixel. After a deliberate
+//! change in behaviour, regenerate them with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test -p chip8-core --test conformance
+//! ```
+//!
+//! and review the diff.
+
+use chip8_core::audio::NullBuzzer;
+use chip8_core::graphics::ConsoleDisplay;
+use chip8_core::movie::PlaybackKeyboard;
+use chip8_core::Interpreter;
+use chip8_core::Platform;
+use chip8_core::Quirks;
+use chip8_core::Snapshot;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Programs are stopped if they haven't finished after this many
+/// instructions.
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+/// Runs `rom` until it halts or jumps to itself.
+fn run(rom: &[u8], platform: Platform, quirks: &str) -> Snapshot {
+    let mut interpreter = Interpreter::with_platform(
+        Box::new(ConsoleDisplay::quiet()),
+        Box::new(NullBuzzer),
+        Box::new(PlaybackKeyboard::from_frames(vec![])),
+        rom,
+        platform,
+    )
+    .unwrap();
+    interpreter.set_quirks(Quirks::preset(quirks).unwrap());
+    interpreter.set_seed(0);
+    for _ in 0..MAX_INSTRUCTIONS {
+        let address = usize::from(interpreter.program_counter());
+        let instruction = &interpreter.memory().bytes()[address..address + 2];
+        if interpreter.is_halted() || instruction == (0x1000 | address as u16).to_be_bytes() {
+            return interpreter.snapshot();
+        }
+        interpreter.run_iteration().unwrap();
+    }
+    panic!(
+        "the program did not finish within {} instructions",
+        MAX_INSTRUCTIONS
+    );
+}
+
+/// Describes the parts of the machine a golden file records.
+fn describe(snapshot: &Snapshot) -> String {
+    let mut text = String::new();
+    writeln!(
+        text,
+        "pc {:03X} i {:03X} sp {}",
+        snapshot.program_counter, snapshot.address_register, snapshot.stack_pointer
+    )
+    .unwrap();
+    for registers in snapshot.registers.chunks(8) {
+        let registers: Vec<String> = registers
+            .iter()
+            .map(|value| format!("{:02X}", value))
+            .collect();
+        writeln!(text, "{}", registers.join(" ")).unwrap();
+    }
+    text.push_str(&snapshot.frame_buffer.to_text().replace(' ', "."));
+    text
+}
+
+/// Runs `rom` and compares the result with `tests/golden/<name>.txt`.
+fn check(name: &str, rom: &[u8], platform: Platform, quirks: &str) {
+    let actual = describe(&run(rom, platform, quirks));
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "{} is missing, create it with UPDATE_GOLDEN=1",
+            path.display()
+        )
+    });
+    assert!(
+        actual == expected,
+        "{} does not match its golden file\nexpected:\n{}\nactual:\n{}",
+        name,
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn arithmetic() {
+    let rom = [
+        0x60, 0xFF, // LD V0, 0xFF
+        0x61, 0x02, // LD V1, 0x02
+        0x80, 0x14, // ADD V0, V1
+        0x62, 0x05, // LD V2, 0x05
+        0x63, 0x07, // LD V3, 0x07
+        0x82, 0x35, // SUB V2, V3
+        0x64, 0x0F, // LD V4, 0x0F
+        0x65, 0xF0, // LD V5, 0xF0
+        0x84, 0x51, // OR V4, V5
+        0x66, 0x3C, // LD V6, 0x3C
+        0x86, 0x42, // AND V6, V4
+        0x67, 0xAA, // LD V7, 0xAA
+        0x87, 0x63, // XOR V7, V6
+        0x68, 0x81, // LD V8, 0x81
+        0x88, 0x8E, // SHL V8
+        0x69, 0x09, // LD V9, 0x09
+        0x6A, 0x04, // LD VA, 0x04
+        0x8A, 0x97, // SUBN VA, V9
+        0x12, 0x24, // JP 0x224
+    ];
+    check("arithmetic", &rom, Platform::Chip8, "octo");
+}
+
+/// Draws the 16 font digits in a row, so that C crosses the right edge of
+/// the screen and the rest start again from the left.
+const FONT_ROM: [u8; 20] = [
+    0x60, 0x00, // LD V0, 0
+    0x61, 0x02, // LD V1, 2
+    0x62, 0x00, // LD V2, 0
+    0xF0, 0x29, // 0x206: LD F, V0
+    0xD1, 0x25, // DRW V1, V2, 5
+    0x70, 0x01, // ADD V0, 1
+    0x71, 0x05, // ADD V1, 5
+    0x30, 0x10, // SE V0, 0x10
+    0x12, 0x06, // JP 0x206
+    0x12, 0x12, // JP 0x212
+];
+
+#[test]
+fn font_wrapping() {
+    check("font_wrapping", &FONT_ROM, Platform::Chip8, "octo");
+}
+
+#[test]
+fn font_clipping() {
+    check("font_clipping", &FONT_ROM, Platform::Chip8, "cosmac-vip");
+}
+
+#[test]
+fn collision() {
+    let rom = [
+        0xA2, 0x0C, // LD I, 0x20C
+        0xD0, 0x01, // DRW V0, V0, 1
+        0xD0, 0x01, // DRW V0, V0, 1
+        0x8A, 0xF0, // LD VA, VF
+        0xD0, 0x01, // DRW V0, V0, 1
+        0x12, 0x0A, // JP 0x20A
+        0xF0, 0x00, // sprite
+    ];
+    check("collision", &rom, Platform::Chip8, "octo");
+}
+
+/// Shifts V1 into V0 and V2, which only uses V1 without the shift quirk.
+const SHIFT_ROM: [u8; 10] = [
+    0x60, 0x01, // LD V0, 0x01
+    0x61, 0x81, // LD V1, 0x81
+    0x80, 0x16, // SHR V0, V1
+    0x82, 0x1E, // SHL V2, V1
+    0x12, 0x08, // JP 0x208
+];
+
+#[test]
+fn shift_cosmac_vip() {
+    check(
+        "shift_cosmac_vip",
+        &SHIFT_ROM,
+        Platform::Chip8,
+        "cosmac-vip",
+    );
+}
+
+#[test]
+fn shift_chip_48() {
+    check("shift_chip_48", &SHIFT_ROM, Platform::Chip8, "chip-48");
+}
+
+/// Stores V0 and V1 and loads them back, which only finds them again if
+/// Fx55 leaves I alone.
+const MEMORY_ROM: [u8; 12] = [
+    0x60, 0x11, // LD V0, 0x11
+    0x61, 0x22, // LD V1, 0x22
+    0xA3, 0x00, // LD I, 0x300
+    0xF1, 0x55, // LD [I], V1
+    0xF1, 0x65, // LD V1, [I]
+    0x12, 0x0A, // JP 0x20A
+];
+
+#[test]
+fn memory_cosmac_vip() {
+    check(
+        "memory_cosmac_vip",
+        &MEMORY_ROM,
+        Platform::Chip8,
+        "cosmac-vip",
+    );
+}
+
+#[test]
+fn memory_chip_48() {
+    check("memory_chip_48", &MEMORY_ROM, Platform::Chip8, "chip-48");
+}
+
+#[test]
+fn binary_coded_decimal() {
+    let rom = [
+        0x60, 0x9C, // LD V0, 156
+        0xA3, 0x00, // LD I, 0x300
+        0xF0, 0x33, // LD B, V0
+        0xF2, 0x65, // LD V2, [I]
+        0x63, 0x00, // LD V3, 0
+        0xF0, 0x29, // LD F, V0
+        0xD3, 0x45, // DRW V3, V4, 5
+        0x73, 0x05, // ADD V3, 5
+        0xF1, 0x29, // LD F, V1
+        0xD3, 0x45, // DRW V3, V4, 5
+        0x73, 0x05, // ADD V3, 5
+        0xF2, 0x29, // LD F, V2
+        0xD3, 0x45, // DRW V3, V4, 5
+        0x12, 0x1A, // JP 0x21A
+    ];
+    check("binary_coded_decimal", &rom, Platform::Chip8, "octo");
+}
+
+#[test]
+fn call_return() {
+    let rom = [
+        0x22, 0x08, // CALL 0x208
+        0x22, 0x08, // CALL 0x208
+        0x22, 0x0C, // CALL 0x20C
+        0x12, 0x06, // JP 0x206
+        0x70, 0x01, // 0x208: ADD V0, 1
+        0x00, 0xEE, // RET
+        0x22, 0x08, // 0x20C: CALL 0x208
+        0x71, 0x01, // ADD V1, 1
+        0x00, 0xEE, // RET
+    ];
+    check("call_return", &rom, Platform::Chip8, "octo");
+}
+
+#[test]
+fn super_chip_high_resolution() {
+    let rom = [
+        0x00, 0xFF, // HIGH
+        0x60, 0x07, // LD V0, 7
+        0xF0, 0x30, // LD HF, V0
+        0x61, 0x00, // LD V1, 0
+        0xD1, 0x1A, // DRW V1, V1, 10
+        0x00, 0xC4, // SCD 4
+        0x00, 0xFB, // SCR
+        0x00, 0xFD, // EXIT
+    ];
+    check(
+        "super_chip_high_resolution",
+        &rom,
+        Platform::SuperChip,
+        "super-chip",
+    );
+}
+
+#[test]
+fn xo_chip_planes() {
+    let rom = [
+        0xF2, 0x01, // PLANE 2
+        0xA2, 0x0E, // LD I, 0x20E
+        0xD0, 0x01, // DRW V0, V0, 1
+        0xF3, 0x01, // PLANE 3
+        0x60, 0x04, // LD V0, 4
+        0xD0, 0x01, // DRW V0, V0, 1
+        0x12, 0x0C, // JP 0x20C
+        0xF0, 0x3C, // sprite, one byte per plane
+    ];
+    check("xo_chip_planes", &rom, Platform::XoChip, "octo");
+}
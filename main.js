@@ -24,16 +24,37 @@ let canvas = document.querySelector('#display');
 let display = canvas.getContext('2d', { alpha: false });
 let canvas_buffer = document.createElement('canvas');
 let display_buffer = canvas_buffer.getContext('2d', { alpha: false });
-var pixel_size = 0;
 
 //const AudioContext = window.AudioContext || window.webkitAudioContext;
 var audio_context = null;
 var oscillator = null;
 var gain_node = null;
+var pattern_source = null;
 var is_audio_started = false;
 
-window.addEventListener('keydown', e => emit('keydown', { key: `${e.code}` }));
-window.addEventListener('keyup', e => emit('keyup', { key: `${e.code}` }));
+// colours 0 to 3, one bit per XO-CHIP plane
+const palette = ["#000000", "#FFFFFF", "#AAAAAA", "#555555"];
+
+// hold Backspace to rewind
+const REWIND_KEY = 'Backspace';
+
+window.addEventListener('keydown', e => {
+  if (e.code === REWIND_KEY) {
+    if (!e.repeat) {
+      invoke('set_rewinding', { rewinding: true })
+        .catch(message => window.alert(`Rewind failed: ${message}`));
+    }
+    return;
+  }
+  emit('keydown', { key: `${e.code}` });
+});
+window.addEventListener('keyup', e => {
+  if (e.code === REWIND_KEY) {
+    invoke('set_rewinding', { rewinding: false });
+    return;
+  }
+  emit('keyup', { key: `${e.code}` });
+});
 window.addEventListener('contextmenu', e => {
   e.preventDefault();
   return false;
@@ -44,6 +65,7 @@ listen('rom-loaded', event => {
     oscillator.start();
     is_audio_started = true;
   }
+  stopPattern();
   clearDisplay();
   invoke('initialize_interpreter', { rom: event.payload });
 })
@@ -51,6 +73,148 @@ listen('stop', () => {
   clearDisplay();
 })
 
+listen('interpreter-error', event => {
+  let payload = event.payload;
+  let backtrace = payload.backtrace ? `\n\n${payload.backtrace}` : '';
+  console.error(`${payload.message}${backtrace}`);
+  window.alert(`The interpreter stopped: ${payload.message}${backtrace}`);
+})
+
+listen('save-state-error', event => {
+  window.alert(`Save state failed: ${event.payload.message}`);
+})
+
+listen('movie-error', event => {
+  window.alert(`Movie failed: ${event.payload.message}`);
+})
+
+listen('debug-error', event => {
+  window.alert(`Debugger: ${event.payload.message}`);
+})
+
+// show where the debugger stopped in the title bar
+const title = document.title;
+
+listen('debug-paused', event => {
+  let pause = event.payload;
+  let address = pause.program_counter.toString(16).toUpperCase().padStart(3, '0');
+  let reason = pause.reason;
+  if (reason === 'watchpoint') {
+    let hex = value => value.toString(16).toUpperCase();
+    reason = `${pause.access} of 0x${hex(pause.address)} by 0x${hex(pause.instruction_address)}, ` +
+      `0x${hex(pause.old)} to 0x${hex(pause.new)}`;
+  } else if (reason === 'history-start') {
+    reason = 'oldest recorded instruction';
+  }
+  let location = pause.location.startsWith('0x') ? `0x${address}` : `${pause.location} (0x${address})`;
+  document.title = `${title} - paused (${reason}) at ${location}: ${pause.instruction}`;
+})
+
+listen('debug-resumed', () => {
+  document.title = title;
+})
+
+// Add Watchpoint asks for a range and what to watch for, like "300-30F write"
+let promptWatchpoint = () => {
+  let answer = window.prompt('Watch addresses (START[-END] read|write|access):');
+  if (answer === null) {
+    return;
+  }
+  let match = answer.trim().match(/^(?:0x)?([0-9a-f]+)(?:\s*-\s*(?:0x)?([0-9a-f]+))?\s*(read|write|access)?$/i);
+  if (match === null) {
+    window.alert(`'${answer}' is not a watchpoint`);
+    return;
+  }
+  let start = parseInt(match[1], 16);
+  let end = match[2] === undefined ? start : parseInt(match[2], 16);
+  let kind = (match[3] || 'write').toLowerCase();
+  invoke('add_watchpoint', { start, end, kind }).catch(message => window.alert(`Debugger: ${message}`));
+}
+
+// reads an address typed into a prompt, as a symbol or in hexadecimal; a
+// symbol such as `beef` wins over the number it spells, which can still be
+// given as 0xBEEF or $BEEF
+let promptAddress = async answer => {
+  let text = answer.trim();
+  try {
+    return await invoke('symbol_address', { name: text });
+  } catch (message) {
+    let hex = text.replace(/^(0x|\$)/i, '');
+    if (/^[0-9a-f]{1,4}$/i.test(hex)) {
+      return parseInt(hex, 16);
+    }
+    throw message;
+  }
+}
+
+// Add Conditional Breakpoint asks for the condition, then optionally where,
+// from which hit and a message to log instead of pausing
+let promptConditionalBreakpoint = async () => {
+  let condition = window.prompt('Pause when (for example V3 == 0x10 && I > 0x300, or is DRW):');
+  if (condition === null || condition.trim() === '') {
+    return;
+  }
+  let at = window.prompt('At address (blank for every instruction):', '');
+  let hits = window.prompt('Pause from hit number (blank for the first):', '');
+  let log = window.prompt('Log this message instead of pausing, with {V0} or {I:x} for values (blank to pause):', '');
+  let hit_count = hits ? parseInt(hits, 10) : null;
+  if (Number.isNaN(hit_count)) {
+    window.alert('That is not a number');
+    return;
+  }
+  let address = null;
+  if (at) {
+    try {
+      address = await promptAddress(at);
+    } catch (message) {
+      window.alert(`Debugger: ${message}`);
+      return;
+    }
+  }
+  invoke('add_conditional_breakpoint', { address, condition, hitCount: hit_count, log: log || null })
+    .catch(message => window.alert(`Debugger: ${message}`));
+}
+
+// Backtrace lists the calls in progress, innermost first
+listen('debug-backtrace', event => {
+  let hex = value => value.toString(16).toUpperCase().padStart(3, '0');
+  let frames = event.payload.frames.map((frame, depth) =>
+    `#${depth} 0x${hex(frame.address)}${frame.location ? ` ${frame.location}` : ''}`);
+  window.alert(`${frames.join('\n')}\n\nDeepest stack: ${event.payload.max_depth} of 16`);
+})
+
+listen('debug-log', event => {
+  console.log(`trace: ${event.payload}`);
+})
+
+// Run To and Toggle Breakpoint ask for an address, in hexadecimal or as a
+// symbol
+listen('debug-prompt', async event => {
+  let action = event.payload.action;
+  if (action === 'watchpoint') {
+    promptWatchpoint();
+    return;
+  }
+  if (action === 'condition') {
+    promptConditionalBreakpoint();
+    return;
+  }
+  let question = action === 'run_to' ? 'Run to address:' : 'Toggle breakpoint at address:';
+  let answer = window.prompt(question);
+  if (answer === null) {
+    return;
+  }
+  let address;
+  try {
+    address = await promptAddress(answer);
+  } catch (message) {
+    window.alert(`Debugger: ${message}`);
+    return;
+  }
+  let command = action === 'run_to' ? 'debug_run_to' : 'toggle_breakpoint';
+  invoke(command, { address }).catch(message => window.alert(`Debugger: ${message}`));
+})
+
 listen('play-buzzer', () => {
   gain_node.gain.value = 0.005;
 })
@@ -59,31 +223,51 @@ listen('pause-buzzer', () => {
   gain_node.gain.value = 0.0;
 })
 
-listen('draw-sprite', event => {
-  let update_x = event.payload.x;
-  let update_y = event.payload.y;
-  let update = event.payload.update;
-  update.forEach((byte, y_offset) => {
-    byte.forEach((bit, x_offset) => {
-      let x = ((update_x + x_offset) % 64) * pixel_size;
-      let y = ((update_y + y_offset) % 32) * pixel_size;
-      if (bit) {
-        display_buffer.fillStyle = "#FFFFFF";
-      } else {
-        display_buffer.fillStyle = "#000000";
-      }
-      display_buffer.fillRect(x, y, pixel_size, pixel_size)
-    })
+// XO-CHIP programs replace the tone with a looping 128 sample pattern
+listen('buzzer-pattern', event => {
+  let pattern = event.payload.pattern;
+  let buffer = audio_context.createBuffer(1, 128, event.payload.sample_rate);
+  let samples = buffer.getChannelData(0);
+  for (let i = 0; i < 128; i++) {
+    samples[i] = (pattern[i >> 3] >> (7 - (i & 7))) & 1 ? 1.0 : -1.0;
+  }
+  stopPattern();
+  oscillator.disconnect();
+  pattern_source = audio_context.createBufferSource();
+  pattern_source.buffer = buffer;
+  pattern_source.loop = true;
+  pattern_source.connect(gain_node);
+  pattern_source.start();
+})
+
+let stopPattern = () => {
+  if (pattern_source != null) {
+    pattern_source.stop();
+    pattern_source.disconnect();
+    pattern_source = null;
+    oscillator.connect(gain_node);
+  }
+}
+
+listen('frame', event => {
+  let frame = event.payload;
+  let pixel_width = canvas_buffer.width / frame.width;
+  let pixel_height = canvas_buffer.height / frame.height;
+  display_buffer.fillStyle = palette[0];
+  display_buffer.fillRect(0.0, 0.0, canvas_buffer.width, canvas_buffer.height);
+  frame.pixels.forEach((pixel, index) => {
+    if (pixel) {
+      display_buffer.fillStyle = palette[pixel];
+      let x = (index % frame.width) * pixel_width;
+      let y = Math.floor(index / frame.width) * pixel_height;
+      display_buffer.fillRect(x, y, pixel_width, pixel_height);
+    }
   })
   window.requestAnimationFrame(() => {
     display.drawImage(canvas_buffer, 0, 0);
   })
 });
 
-listen('clear', () => {
-  clearDisplay();
-});
-
 let resizeDisplay = () => {
   var height = Math.floor(game_container.offsetHeight);
   var width = Math.floor(game_container.offsetWidth);
@@ -103,7 +287,6 @@ let resizeDisplay = () => {
     canvas.style.height = height + 'px';
     canvas.height = height;
   }
-  pixel_size = Math.floor(canvas.height / 32);
   canvas_buffer.width = canvas.width;
   canvas_buffer.height = canvas.height;
 };
@@ -20,19 +20,39 @@
     windows_subsystem = "windows"
 )]
 
-mod audio;
-mod chip8;
-mod graphics;
-mod keyboard;
-mod memory;
-mod registers;
-
-use audio::Buzzer;
-use chip8::Interpreter;
-use graphics::Display;
-use keyboard::Keyboard;
+use chip8_core::assembler;
+use chip8_core::chip8::DEFAULT_INSTRUCTIONS_PER_FRAME;
+use chip8_core::clock::Clock;
+use chip8_core::clock::SystemClock;
+use chip8_core::debugger::Breakpoint;
+use chip8_core::debugger::PauseReason;
+use chip8_core::debugger::WatchKind;
+use chip8_core::debugger::Watchpoint;
+use chip8_core::disasm;
+use chip8_core::expression::Expression;
+use chip8_core::expression::Template;
+use chip8_core::graphics::FrameBuffer;
+use chip8_core::movie::PlaybackKeyboard;
+use chip8_core::movie::RecordingKeyboard;
+use chip8_core::rng::RngMode;
+use chip8_core::symbols;
+use chip8_core::Buzzer;
+use chip8_core::Chip8Error;
+use chip8_core::Display;
+use chip8_core::FrameScheduler;
+use chip8_core::Interpreter;
+use chip8_core::Keyboard;
+use chip8_core::Movie;
+use chip8_core::Platform;
+use chip8_core::Quirks;
+use chip8_core::RewindBuffer;
+use chip8_core::Snapshot;
+use chip8_core::Symbols;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -51,6 +71,89 @@ struct StopPayload {
     message: String,
 }
 
+/// Why the interpreter stopped, sent to the front end as
+/// `interpreter-error`.
+#[derive(Clone, serde::Serialize)]
+struct InterpreterErrorPayload {
+    message: String,
+    /// Where the program was, if it got as far as running.
+    backtrace: Option<String>,
+}
+
+/// The call stack, innermost call first.
+#[derive(Clone, serde::Serialize)]
+struct BacktraceInfo {
+    frames: Vec<FrameInfo>,
+    max_depth: u8,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct FrameInfo {
+    address: u16,
+    /// The address by the nearest symbol.
+    location: Option<String>,
+}
+
+impl From<&Interpreter> for BacktraceInfo {
+    fn from(interpreter: &Interpreter) -> Self {
+        Self {
+            frames: interpreter
+                .backtrace()
+                .into_iter()
+                .map(|address| FrameInfo {
+                    address,
+                    location: interpreter.symbols().locate(address),
+                })
+                .collect(),
+            max_depth: interpreter.max_stack_depth(),
+        }
+    }
+}
+
+/// The registers, as shown and edited by the front end's debug panel.
+#[derive(Clone, serde::Serialize)]
+struct RegistersInfo {
+    v: [u8; 16],
+    i: u16,
+    pc: u16,
+    sp: u8,
+    dt: u8,
+    st: u8,
+}
+
+impl From<&Interpreter> for RegistersInfo {
+    fn from(interpreter: &Interpreter) -> Self {
+        let mut v = [0; 16];
+        for (x, register) in v.iter_mut().enumerate() {
+            *register = interpreter.register(x as u8);
+        }
+        Self {
+            v,
+            i: interpreter.address_register(),
+            pc: interpreter.program_counter(),
+            sp: interpreter.stack().stack_pointer(),
+            dt: interpreter.delay_timer(),
+            st: interpreter.sound_timer(),
+        }
+    }
+}
+
+/// The memory the front end is watching while the ROM runs.
+#[derive(Clone, Copy)]
+struct Inspection {
+    address: u16,
+    length: usize,
+}
+
+/// The registers and watched memory, sent to the front end as
+/// `machine-state` after every batch of frames.
+#[derive(Clone, serde::Serialize)]
+struct MachineStatePayload {
+    registers: RegistersInfo,
+    address: u16,
+    memory: Vec<u8>,
+}
+
 #[derive(Clone, serde::Deserialize)]
 struct KeyDown {
     key: String,
@@ -61,14 +164,93 @@ struct KeyUp {
     key: String,
 }
 
+/// Where and why the interpreter paused, sent to the front end as
+/// `debug-paused`.
+#[derive(Clone, serde::Serialize)]
+struct DebugPause {
+    #[serde(flatten)]
+    reason: PauseReason,
+    program_counter: u16,
+    /// The program counter by the nearest symbol, such as `draw+2`.
+    location: String,
+    instruction: String,
+}
+
+/// A breakpoint as listed to the front end.
+#[derive(Clone, serde::Serialize)]
+struct BreakpointInfo {
+    id: u32,
+    address: Option<u16>,
+    condition: Option<String>,
+    hit_count: Option<u32>,
+    log: Option<String>,
+    hits: u32,
+    /// The address by the nearest symbol.
+    location: Option<String>,
+}
+
+impl From<&Breakpoint> for BreakpointInfo {
+    fn from(breakpoint: &Breakpoint) -> Self {
+        Self {
+            id: breakpoint.id(),
+            address: breakpoint.address,
+            condition: breakpoint.condition.as_ref().map(Expression::to_string),
+            hit_count: breakpoint.hit_count,
+            log: breakpoint.log.as_ref().map(Template::to_string),
+            hits: breakpoint.hits(),
+            location: None,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct DebugPrompt {
+    action: String,
+}
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct Rom {
     path: String,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Speed {
+    instructions_per_frame: usize,
+    multiplier: f64,
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Self {
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            multiplier: 1.0,
+        }
+    }
+}
+
+/// The number of save state slots offered for each ROM.
+const SAVE_STATE_SLOTS: u8 = 4;
+const MOVIE_EXTENSION: &str = "c8m";
+
+const FAST_FORWARD_MULTIPLIER: f64 = 4.0;
+const SLOW_MOTION_MULTIPLIER: f64 = 0.25;
+
+/// The whole screen, sent to the front end once per frame whenever it changed.
 #[derive(Clone, serde::Serialize)]
 struct Frame {
-    buffer: Vec<bool>,
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl From<&FrameBuffer> for Frame {
+    fn from(buffer: &FrameBuffer) -> Self {
+        Self {
+            width: buffer.width(),
+            height: buffer.height(),
+            pixels: buffer.pixels().to_vec(),
+        }
+    }
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -78,54 +260,44 @@ struct BuzzerSpec {
 }
 
 #[derive(Clone, serde::Serialize)]
-struct JsSprite {
-    x: u8,
-    y: u8,
-    update: Vec<Vec<bool>>,
+struct BuzzerPattern {
+    pattern: Vec<u8>,
+    sample_rate: f32,
 }
 
 struct TauriDisplay {
-    buffer: [[bool; 64]; 32],
+    buffer: FrameBuffer,
+    last_frame: Option<FrameBuffer>,
     window: tauri::Window,
 }
 
 impl TauriDisplay {
-
     fn new(window: tauri::Window) -> Self {
         Self {
-            buffer: [[false; 64]; 32],
+            buffer: FrameBuffer::new(),
+            last_frame: None,
             window,
         }
     }
 }
 
 impl Display for TauriDisplay {
-    fn clear(&mut self) {
-        self.buffer = [[false; 64]; 32];
-        match self.window.emit("clear", ()) {
-            Err(error) => {
-                eprintln!("Error sending 'clear' event: {}", error);
-            }
-            _ => ()
-        }
+    fn frame_buffer(&self) -> &FrameBuffer {
+        &self.buffer
     }
 
-    fn draw(&mut self, x: u8, y: u8, sprite: &graphics::Sprite) -> u8 {
-        let mut new_buffer = self.buffer.clone();
-        let (collision, update) = self.draw_sprite(&mut new_buffer, x, y, &sprite);
-        self.buffer = new_buffer;
-
-        match self.window.emit("draw-sprite", JsSprite { x, y, update }) {
-            Err(error) => {
-                eprintln!("Error sending 'draw-sprite' event: {}", error);
-            }
-            _ => ()
-        }
-
-        return collision;
+    fn frame_buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.buffer
     }
 
     fn refresh(&mut self) {
+        if self.last_frame.as_ref() == Some(&self.buffer) {
+            return;
+        }
+        if let Err(error) = self.window.emit("frame", Frame::from(&self.buffer)) {
+            eprintln!("Error sending 'frame' event: {}", error);
+        }
+        self.last_frame = Some(self.buffer.clone());
     }
 }
 
@@ -153,6 +325,16 @@ impl Buzzer for JavaScriptAudio {
     fn pause(&self) {
         self.window.emit("pause-buzzer", ()).unwrap();
     }
+
+    fn set_pattern(&self, pattern: &[u8; 16], sample_rate: f32) {
+        let payload = BuzzerPattern {
+            pattern: pattern.to_vec(),
+            sample_rate,
+        };
+        if let Err(error) = self.window.emit("buzzer-pattern", payload) {
+            eprintln!("Error sending 'buzzer-pattern' event: {}", error);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -260,32 +442,974 @@ fn initialize_interpreter(
     interpreter_state: State<InterpreterState>,
     rom: Rom,
 ) {
-    let mut rom_file = File::open(rom.path).unwrap();
-    let mut rom = vec![];
-    rom_file.read_to_end(&mut rom).unwrap();
+    interpreter_state.is_running.store(false, Ordering::Relaxed);
+    if let Some(thread) = interpreter_state.interpreter_thread.lock().unwrap().take() {
+        let _ = thread.join();
+    }
+    let movie_action = interpreter_state.movie_action.lock().unwrap().take();
+    *interpreter_state.recording.lock().unwrap() = None;
+    interpreter_state
+        .playing_movie
+        .store(false, Ordering::Relaxed);
+    interpreter_state.rewinding.store(false, Ordering::Relaxed);
+    let platform = match &movie_action {
+        Some(MovieAction::Play(movie)) => movie.platform,
+        _ => platform_for(&interpreter_state, &rom.path),
+    };
+    *interpreter_state.rom_path.lock().unwrap() = Some(rom.path.clone());
+    let mut rom_file = File::open(&rom.path).unwrap();
+    let mut bytes = vec![];
+    rom_file.read_to_end(&mut bytes).unwrap();
+    let rom_path = rom.path;
+    // source brings its own labels, which are always up to date
+    let mut symbols = None;
+    if assembler::is_source(Path::new(&rom_path)) {
+        match assembler::assemble(&String::from_utf8_lossy(&bytes)) {
+            Ok(assembly) => {
+                symbols = Some(assembly.symbol_table());
+                bytes = assembly.rom;
+            }
+            Err(error) => {
+                emit_interpreter_error(&window, &error);
+                return;
+            }
+        }
+    }
+    let rom = bytes;
     let display = TauriDisplay::new(window.clone());
     let keyboard = TauriKeyboard::new(app_handle.clone());
     let buzzer = JavaScriptAudio::new(window.clone());
-    let mut interpreter = Interpreter::new(
+    let mut interpreter = match Interpreter::with_platform(
         Box::new(display),
         Box::new(buzzer),
         Box::new(keyboard),
         &rom,
-    );
+        platform,
+    ) {
+        Ok(interpreter) => interpreter,
+        Err(error) => {
+            emit_interpreter_error(&window, &error);
+            return;
+        }
+    };
+    interpreter.set_rng_mode(*interpreter_state.rng_mode.lock().unwrap());
+    let symbols = symbols.or_else(|| Symbols::find(Path::new(&rom_path), interpreter.rom_hash()));
+    interpreter.set_symbols(symbols.unwrap_or_default());
+    match movie_action {
+        Some(MovieAction::Record) => {
+            // the speed and quirks are applied here as well as by the thread
+            // so that the movie starts out with them
+            interpreter.set_instructions_per_frame(
+                interpreter_state
+                    .speed
+                    .lock()
+                    .unwrap()
+                    .instructions_per_frame,
+            );
+            interpreter.set_quirks(*interpreter_state.quirks.lock().unwrap());
+            let movie = Arc::new(Mutex::new(Movie::new(&interpreter)));
+            interpreter.set_keyboard(Box::new(RecordingKeyboard::new(
+                Box::new(TauriKeyboard::new(app_handle.clone())),
+                movie.clone(),
+            )));
+            *interpreter_state.recording.lock().unwrap() = Some(movie);
+        }
+        Some(MovieAction::Play(movie)) => {
+            if let Err(error) = movie.configure(&mut interpreter) {
+                emit_movie_error(&window, error.to_string());
+                return;
+            }
+            interpreter.set_keyboard(Box::new(PlaybackKeyboard::new(&movie)));
+            // keep the thread from overriding what the movie was recorded with
+            interpreter_state
+                .speed
+                .lock()
+                .unwrap()
+                .instructions_per_frame = movie.instructions_per_frame;
+            *interpreter_state.quirks.lock().unwrap() = movie.quirks;
+            interpreter_state
+                .playing_movie
+                .store(true, Ordering::Relaxed);
+        }
+        None => {}
+    }
+    *interpreter_state.interpreter.lock().unwrap() = Some(interpreter);
     interpreter_state.is_running.store(true, Ordering::Relaxed);
+    let thread_interpreter = interpreter_state.interpreter.clone();
     let thread_is_running = interpreter_state.is_running.clone();
+    let thread_speed = interpreter_state.speed.clone();
+    let thread_quirks = interpreter_state.quirks.clone();
+    let thread_rewinding = interpreter_state.rewinding.clone();
+    let thread_inspection = interpreter_state.inspection.clone();
     *interpreter_state.interpreter_thread.lock().unwrap() = Some(std::thread::spawn(move || {
-        while thread_is_running.load(Ordering::Relaxed) {
-            interpreter.run_iteration();
-            std::thread::sleep(std::time::Duration::from_nanos(2000000));
+        let clock = SystemClock::new();
+        let mut scheduler = FrameScheduler::new(clock.now());
+        let mut rewind = RewindBuffer::default();
+        'running: while thread_is_running.load(Ordering::Relaxed) {
+            let speed = *thread_speed.lock().unwrap();
+            if speed.multiplier != scheduler.speed() {
+                scheduler.set_speed(speed.multiplier, clock.now());
+            }
+            {
+                let mut interpreter = thread_interpreter.lock().unwrap();
+                let interpreter = match interpreter.as_mut() {
+                    Some(interpreter) => interpreter,
+                    None => break 'running,
+                };
+                interpreter.set_instructions_per_frame(speed.instructions_per_frame);
+                interpreter.set_quirks(*thread_quirks.lock().unwrap());
+                let frames = scheduler.frames_due(clock.now());
+                for _ in 0..frames {
+                    if thread_rewinding.load(Ordering::Relaxed) {
+                        if let Err(error) = rewind.rewind(interpreter) {
+                            thread_is_running.store(false, Ordering::Relaxed);
+                            emit_interpreter_crash(&window, &error, interpreter);
+                            break 'running;
+                        }
+                        continue;
+                    }
+                    if interpreter.debugger().is_paused() {
+                        continue;
+                    }
+                    rewind.record(interpreter);
+                    if let Err(error) = interpreter.run_frame() {
+                        thread_is_running.store(false, Ordering::Relaxed);
+                        emit_interpreter_crash(&window, &error, interpreter);
+                        break 'running;
+                    }
+                    for message in interpreter.debugger_mut().take_log() {
+                        if let Err(error) = window.emit("debug-log", message) {
+                            eprintln!("Error sending 'debug-log' event: {}", error);
+                        }
+                    }
+                    if interpreter.debugger().is_paused() {
+                        emit_debug_state(&window, interpreter);
+                    }
+                    if interpreter.is_halted() {
+                        thread_is_running.store(false, Ordering::Relaxed);
+                        if let Err(error) = window.emit("stop", ()) {
+                            eprintln!("Error sending 'stop' event: {}", error);
+                        }
+                        break 'running;
+                    }
+                }
+                if frames > 0 {
+                    if let Some(inspection) = *thread_inspection.lock().unwrap() {
+                        emit_machine_state(&window, interpreter, inspection);
+                    }
+                }
+            }
+            std::thread::sleep(scheduler.time_until_next_frame(clock.now()));
         }
     }));
 }
 
+/// Where the save state in `slot` for the ROM with `rom_hash` is kept. Slots
+/// go by the ROM's contents rather than its file name, so two ROMs that
+/// share a name don't share slots.
+fn save_state_path(app_handle: &AppHandle, rom_hash: &str, slot: u8) -> Result<PathBuf, String> {
+    if !(1..=SAVE_STATE_SLOTS).contains(&slot) {
+        return Err(format!("there is no save state slot {}", slot));
+    }
+    let app_dir = app_handle
+        .path_resolver()
+        .app_dir()
+        .ok_or("there is no application data directory")?;
+    Ok(app_dir
+        .join("save-states")
+        .join(format!("{}.slot{}.json", rom_hash, slot)))
+}
+
+fn loaded_rom_hash(interpreter_state: &InterpreterState) -> Result<String, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => Ok(interpreter.rom_hash().to_string()),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+fn loaded_rom_path(interpreter_state: &InterpreterState) -> Result<String, String> {
+    interpreter_state
+        .rom_path
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "no ROM is loaded".to_string())
+}
+
+fn save_state_to_slot(
+    app_handle: &AppHandle,
+    interpreter_state: &InterpreterState,
+    slot: u8,
+) -> Result<(), String> {
+    let path = save_state_path(app_handle, &loaded_rom_hash(interpreter_state)?, slot)?;
+    let snapshot = match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => interpreter.snapshot(),
+        None => return Err("no ROM is loaded".to_string()),
+    };
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    }
+    std::fs::write(&path, snapshot.to_bytes()).map_err(|error| error.to_string())
+}
+
+fn load_state_from_slot(
+    app_handle: &AppHandle,
+    interpreter_state: &InterpreterState,
+    slot: u8,
+) -> Result<(), String> {
+    check_no_movie(interpreter_state, "load a save state")?;
+    let path = save_state_path(app_handle, &loaded_rom_hash(interpreter_state)?, slot)?;
+    let bytes = std::fs::read(&path).map_err(|_| format!("slot {} is empty", slot))?;
+    let snapshot = Snapshot::from_bytes(&bytes).map_err(|error| error.to_string())?;
+    match interpreter_state.interpreter.lock().unwrap().as_mut() {
+        Some(interpreter) => interpreter
+            .restore(&snapshot)
+            .map_err(|error| error.to_string()),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn save_state(
+    app_handle: tauri::AppHandle,
+    interpreter_state: State<InterpreterState>,
+    slot: u8,
+) -> Result<(), String> {
+    save_state_to_slot(&app_handle, &interpreter_state, slot)
+}
+
+#[tauri::command]
+fn load_state(
+    app_handle: tauri::AppHandle,
+    interpreter_state: State<InterpreterState>,
+    slot: u8,
+) -> Result<(), String> {
+    load_state_from_slot(&app_handle, &interpreter_state, slot)
+}
+
+/// The slots that hold a save state for the loaded ROM.
+#[tauri::command]
+fn list_save_states(
+    app_handle: tauri::AppHandle,
+    interpreter_state: State<InterpreterState>,
+) -> Result<Vec<u8>, String> {
+    let rom_hash = loaded_rom_hash(&interpreter_state)?;
+    let mut slots = vec![];
+    for slot in 1..=SAVE_STATE_SLOTS {
+        if save_state_path(&app_handle, &rom_hash, slot)?.exists() {
+            slots.push(slot);
+        }
+    }
+    Ok(slots)
+}
+
+/// Selects how Cxkk generates random numbers. It takes effect the next time
+/// a ROM is loaded.
+#[tauri::command]
+fn set_rng_mode(interpreter_state: State<InterpreterState>, name: String) -> Result<(), String> {
+    let mode = RngMode::from_name(&name).ok_or(format!("unknown random number mode '{}'", name))?;
+    *interpreter_state.rng_mode.lock().unwrap() = mode;
+    Ok(())
+}
+
+/// Plays the game backwards one frame at a time while `rewinding` is set.
+#[tauri::command]
+fn set_rewinding(
+    interpreter_state: State<InterpreterState>,
+    rewinding: bool,
+) -> Result<(), String> {
+    if rewinding {
+        check_no_movie(&interpreter_state, "rewind")?;
+    }
+    interpreter_state.rewinding.store(rewinding, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Fails while a movie is being recorded or played, since `action` would
+/// change the run in a way the movie can't replay.
+fn check_no_movie(interpreter_state: &InterpreterState, action: &str) -> Result<(), String> {
+    if interpreter_state.recording.lock().unwrap().is_some() {
+        return Err(format!("can't {} while a movie is being recorded", action));
+    }
+    if interpreter_state.playing_movie.load(Ordering::Relaxed) {
+        return Err(format!("can't {} while a movie is playing", action));
+    }
+    Ok(())
+}
+
+/// Restarts the loaded ROM, recording its input from the first frame.
+fn start_recording(window: &Window, interpreter_state: &InterpreterState) -> Result<(), String> {
+    let path = loaded_rom_path(interpreter_state)?;
+    *interpreter_state.movie_action.lock().unwrap() = Some(MovieAction::Record);
+    window
+        .emit("rom-loaded", Rom { path })
+        .map_err(|error| error.to_string())
+}
+
+/// Writes the movie being recorded to `path`. Recording carries on until the
+/// next ROM is loaded.
+fn save_recording(interpreter_state: &InterpreterState, path: &Path) -> Result<(), String> {
+    let bytes = match interpreter_state.recording.lock().unwrap().as_ref() {
+        Some(movie) => movie.lock().unwrap().to_bytes(),
+        None => return Err("no movie is being recorded".to_string()),
+    };
+    std::fs::write(path, bytes).map_err(|error| error.to_string())
+}
+
+/// Restarts the loaded ROM and replays the movie at `path` on it.
+fn play_movie(
+    window: &Window,
+    interpreter_state: &InterpreterState,
+    path: &Path,
+) -> Result<(), String> {
+    let rom_path = loaded_rom_path(interpreter_state)?;
+    let bytes = std::fs::read(path).map_err(|error| error.to_string())?;
+    let movie = Movie::from_bytes(&bytes).map_err(|error| error.to_string())?;
+    *interpreter_state.movie_action.lock().unwrap() = Some(MovieAction::Play(movie));
+    window
+        .emit("rom-loaded", Rom { path: rom_path })
+        .map_err(|error| error.to_string())
+}
+
+fn emit_movie_error(window: &Window, message: String) {
+    if let Err(error) = window.emit("movie-error", StopPayload { message }) {
+        eprintln!("Error sending 'movie-error' event: {}", error);
+    }
+}
+
+fn emit_debug_error(window: &Window, message: String) {
+    if let Err(error) = window.emit("debug-error", StopPayload { message }) {
+        eprintln!("Error sending 'debug-error' event: {}", error);
+    }
+}
+
+fn emit_save_state_error(window: &Window, message: String) {
+    if let Err(error) = window.emit("save-state-error", StopPayload { message }) {
+        eprintln!("Error sending 'save-state-error' event: {}", error);
+    }
+}
+
+#[tauri::command]
+fn get_speed(interpreter_state: State<InterpreterState>) -> Speed {
+    *interpreter_state.speed.lock().unwrap()
+}
+
+#[tauri::command]
+fn set_instructions_per_frame(
+    interpreter_state: State<InterpreterState>,
+    instructions_per_frame: usize,
+) -> Result<(), String> {
+    if instructions_per_frame == 0 {
+        return Err("instructions per frame must be at least 1".to_string());
+    }
+    check_no_movie(&interpreter_state, "change the instructions per frame")?;
+    interpreter_state.speed.lock().unwrap().instructions_per_frame = instructions_per_frame;
+    Ok(())
+}
+
+// the multiplier only paces frames against the wall clock, so movies don't
+// mind it changing
+#[tauri::command]
+fn set_speed_multiplier(
+    interpreter_state: State<InterpreterState>,
+    multiplier: f64,
+) -> Result<(), String> {
+    if !(multiplier.is_finite() && multiplier > 0.0) {
+        return Err("speed multiplier must be a positive number".to_string());
+    }
+    interpreter_state.speed.lock().unwrap().multiplier = multiplier;
+    Ok(())
+}
+
+fn emit_interpreter_error(window: &Window, error: &dyn std::error::Error) {
+    let payload = InterpreterErrorPayload {
+        message: error.to_string(),
+        backtrace: None,
+    };
+    if let Err(error) = window.emit("interpreter-error", payload) {
+        eprintln!("Error sending 'interpreter-error' event: {}", error);
+    }
+}
+
+/// Reports an error from the running program, with where it happened.
+fn emit_interpreter_crash(window: &Window, error: &Chip8Error, interpreter: &Interpreter) {
+    let payload = InterpreterErrorPayload {
+        message: error.to_string(),
+        backtrace: Some(interpreter.format_backtrace()),
+    };
+    if let Err(error) = window.emit("interpreter-error", payload) {
+        eprintln!("Error sending 'interpreter-error' event: {}", error);
+    }
+}
+
+fn emit_machine_state(window: &Window, interpreter: &Interpreter, inspection: Inspection) {
+    let payload = MachineStatePayload {
+        registers: RegistersInfo::from(interpreter),
+        address: inspection.address,
+        memory: memory_range(interpreter, inspection.address, inspection.length),
+    };
+    if let Err(error) = window.emit("machine-state", payload) {
+        eprintln!("Error sending 'machine-state' event: {}", error);
+    }
+}
+
+/// The `length` bytes of memory from `address`, or as many as there are.
+fn memory_range(interpreter: &Interpreter, address: u16, length: usize) -> Vec<u8> {
+    let bytes = interpreter.memory().bytes();
+    let start = usize::from(address).min(bytes.len());
+    let end = start.saturating_add(length).min(bytes.len());
+    bytes[start..end].to_vec()
+}
+
+/// Tells the front end whether the interpreter is paused, and if so where.
+fn emit_debug_state(window: &Window, interpreter: &Interpreter) {
+    let result = match interpreter.debugger().pause_reason() {
+        Some(reason) => {
+            let address = usize::from(interpreter.program_counter());
+            let bytes = interpreter.memory().bytes();
+            let bytes = &bytes[address.min(bytes.len())..(address + 4).min(bytes.len())];
+            let instruction = disasm::disassemble_with_symbols(
+                bytes,
+                address as u16,
+                interpreter.platform(),
+                interpreter.symbols(),
+            )
+            .first()
+            .map(|line| line.text.clone())
+            .unwrap_or_default();
+            window.emit(
+                "debug-paused",
+                DebugPause {
+                    reason,
+                    program_counter: interpreter.program_counter(),
+                    location: interpreter
+                        .symbols()
+                        .describe(interpreter.program_counter()),
+                    instruction,
+                },
+            )
+        }
+        None => window.emit("debug-resumed", ()),
+    };
+    if let Err(error) = result {
+        eprintln!("Error sending debugger state: {}", error);
+    }
+}
+
+/// Runs `action` on the loaded interpreter and reports where it left the
+/// debugger.
+fn debug<T>(
+    window: &Window,
+    interpreter_state: &InterpreterState,
+    action: impl FnOnce(&mut Interpreter) -> Result<T, Chip8Error>,
+) -> Result<T, String> {
+    let mut interpreter = interpreter_state.interpreter.lock().unwrap();
+    let interpreter = interpreter.as_mut().ok_or("no ROM is loaded")?;
+    let result = action(interpreter);
+    emit_debug_state(window, interpreter);
+    result.map_err(|error| error.to_string())
+}
+
+/// What stepping, running to an address and breakpoints all do, which
+/// would make the run differ from a movie's frames.
+const MID_FRAME_PAUSE: &str = "pause in the middle of a frame";
+
+/// Carries out a Debug menu item.
+fn debug_menu_action(
+    window: &Window,
+    interpreter_state: &InterpreterState,
+    action: &str,
+) -> Result<(), String> {
+    if let "step" | "step_over" | "step_out" | "run_to" | "breakpoint" | "condition"
+    | "watchpoint" = action
+    {
+        check_no_movie(interpreter_state, MID_FRAME_PAUSE)?;
+    }
+    match action {
+        "pause" => debug(window, interpreter_state, |interpreter| {
+            interpreter.pause();
+            Ok(())
+        }),
+        "resume" => debug(window, interpreter_state, |interpreter| {
+            interpreter.resume();
+            Ok(())
+        }),
+        "step" => debug(window, interpreter_state, Interpreter::step),
+        "step_over" => debug(window, interpreter_state, Interpreter::step_over),
+        "step_out" => debug(window, interpreter_state, |interpreter| {
+            interpreter.step_out();
+            Ok(())
+        }),
+        "step_back" => {
+            check_no_movie(interpreter_state, "step back")?;
+            debug(window, interpreter_state, |interpreter| {
+                interpreter.step_back();
+                Ok(())
+            })
+        }
+        "reverse_continue" => {
+            check_no_movie(interpreter_state, "run backwards")?;
+            debug(window, interpreter_state, |interpreter| {
+                interpreter.reverse_continue();
+                Ok(())
+            })
+        }
+        "clear_watchpoints" => debug(window, interpreter_state, |interpreter| {
+            for watchpoint in interpreter.watchpoints().to_vec() {
+                interpreter.remove_watchpoint(&watchpoint);
+            }
+            Ok(())
+        }),
+        "backtrace" => {
+            let interpreter = interpreter_state.interpreter.lock().unwrap();
+            let interpreter = interpreter.as_ref().ok_or("no ROM is loaded")?;
+            window
+                .emit("debug-backtrace", BacktraceInfo::from(interpreter))
+                .map_err(|error| error.to_string())
+        }
+        "load_symbols" => {
+            let window = window.clone();
+            FileDialogBuilder::new()
+                .add_filter("Symbols", &[symbols::EXTENSION, "json"])
+                .pick_file(move |path| {
+                    let interpreter_state = window.state::<InterpreterState>();
+                    let result = match path {
+                        Some(path) => load_symbols(&window, &interpreter_state, &path),
+                        None => Ok(()),
+                    };
+                    if let Err(message) = result {
+                        emit_debug_error(&window, message);
+                    }
+                });
+            Ok(())
+        }
+        // these need an address, which the front end asks for
+        "run_to" | "breakpoint" | "condition" | "watchpoint" => window
+            .emit(
+                "debug-prompt",
+                DebugPrompt {
+                    action: action.to_string(),
+                },
+            )
+            .map_err(|error| error.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Names the loaded ROM's addresses with the symbol file at `path`.
+fn load_symbols(
+    window: &Window,
+    interpreter_state: &InterpreterState,
+    path: &Path,
+) -> Result<(), String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let symbols =
+        Symbols::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let matches = debug(window, interpreter_state, |interpreter| {
+        let matches = symbols.matches(interpreter.rom_hash());
+        if matches {
+            interpreter.set_symbols(symbols);
+        }
+        Ok(matches)
+    })?;
+    if !matches {
+        return Err(format!("{} is for another ROM", path.display()));
+    }
+    Ok(())
+}
+
+/// The registers of the loaded ROM, which can be read while it runs.
+#[tauri::command]
+fn get_registers(interpreter_state: State<InterpreterState>) -> Result<RegistersInfo, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => Ok(RegistersInfo::from(interpreter)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+/// Sets `register`, one of V0 to VF, I, PC, DT or ST, without stopping the
+/// ROM. Returns the registers afterwards.
+#[tauri::command]
+fn set_register(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+    register: String,
+    value: u16,
+) -> Result<RegistersInfo, String> {
+    check_no_movie(&interpreter_state, "set a register")?;
+    let name = register.trim().to_ascii_uppercase();
+    let byte = u8::try_from(value).map_err(|_| format!("{} holds a byte, not 0x{:X}", name, value));
+    let set: Box<dyn FnOnce(&mut Interpreter)> = match name.as_str() {
+        "I" => Box::new(move |interpreter| interpreter.set_address_register(value)),
+        "PC" => Box::new(move |interpreter| interpreter.set_program_counter(value)),
+        "DT" => {
+            let value = byte?;
+            Box::new(move |interpreter| interpreter.set_delay_timer(value))
+        }
+        "ST" => {
+            let value = byte?;
+            Box::new(move |interpreter| interpreter.set_sound_timer(value))
+        }
+        "SP" => return Err("SP follows the stack and can't be set".to_string()),
+        _ => {
+            let x = name
+                .strip_prefix('V')
+                .filter(|digit| digit.len() == 1)
+                .and_then(|digit| u8::from_str_radix(digit, 16).ok())
+                .ok_or(format!("there is no register '{}'", register))?;
+            let value = byte?;
+            Box::new(move |interpreter| interpreter.set_register(x, value))
+        }
+    };
+    debug(&window, &interpreter_state, |interpreter| {
+        set(interpreter);
+        Ok(RegistersInfo::from(&*interpreter))
+    })
+}
+
+/// Reads `length` bytes of memory from `address`, or up to the end of
+/// memory, without setting off watchpoints.
+#[tauri::command]
+fn read_memory(
+    interpreter_state: State<InterpreterState>,
+    address: u16,
+    length: usize,
+) -> Result<Vec<u8>, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => Ok(memory_range(interpreter, address, length)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+/// Overwrites memory from `address` without stopping the ROM or setting off
+/// watchpoints.
+#[tauri::command]
+fn write_memory(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+    address: u16,
+    bytes: Vec<u8>,
+) -> Result<(), String> {
+    check_no_movie(&interpreter_state, "write to memory")?;
+    debug(&window, &interpreter_state, |interpreter| {
+        interpreter.write_memory(address, &bytes)
+    })
+}
+
+/// Sends `machine-state` with the registers and the `length` bytes from
+/// `address` after every batch of frames, or stops with no `address`.
+#[tauri::command]
+fn inspect_machine(
+    interpreter_state: State<InterpreterState>,
+    address: Option<u16>,
+    length: usize,
+) {
+    *interpreter_state.inspection.lock().unwrap() =
+        address.map(|address| Inspection { address, length });
+}
+
+/// The call stack of the loaded ROM, which can be read while it runs.
+#[tauri::command]
+fn debug_backtrace(interpreter_state: State<InterpreterState>) -> Result<BacktraceInfo, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => Ok(BacktraceInfo::from(interpreter)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+/// The address of the symbol `name`, for the front end's address prompts.
+#[tauri::command]
+fn symbol_address(interpreter_state: State<InterpreterState>, name: String) -> Result<u16, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => interpreter
+            .symbols()
+            .address(name.trim())
+            .ok_or(format!("'{}' is not an address or a symbol", name)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn debug_pause(window: Window, interpreter_state: State<InterpreterState>) -> Result<(), String> {
+    debug(&window, &interpreter_state, |interpreter| {
+        interpreter.pause();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn debug_resume(window: Window, interpreter_state: State<InterpreterState>) -> Result<(), String> {
+    debug(&window, &interpreter_state, |interpreter| {
+        interpreter.resume();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn debug_step(window: Window, interpreter_state: State<InterpreterState>) -> Result<(), String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    debug(&window, &interpreter_state, Interpreter::step)
+}
+
+#[tauri::command]
+fn debug_step_over(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+) -> Result<(), String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    debug(&window, &interpreter_state, Interpreter::step_over)
+}
+
+#[tauri::command]
+fn debug_step_out(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+) -> Result<(), String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    debug(&window, &interpreter_state, |interpreter| {
+        interpreter.step_out();
+        Ok(())
+    })
+}
+
+/// Undoes the last instruction. Returns false if there was none recorded.
+#[tauri::command]
+fn debug_step_back(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+) -> Result<bool, String> {
+    check_no_movie(&interpreter_state, "step back")?;
+    debug(&window, &interpreter_state, |interpreter| {
+        Ok(interpreter.step_back())
+    })
+}
+
+/// Runs backwards to the last breakpoint passed. Returns false if it ran
+/// out of history first.
+#[tauri::command]
+fn debug_reverse_continue(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+) -> Result<bool, String> {
+    check_no_movie(&interpreter_state, "run backwards")?;
+    debug(&window, &interpreter_state, |interpreter| {
+        Ok(interpreter.reverse_continue())
+    })
+}
+
+#[tauri::command]
+fn debug_run_to(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+    address: u16,
+) -> Result<(), String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    debug(&window, &interpreter_state, |interpreter| {
+        interpreter.run_to(address);
+        Ok(())
+    })
+}
+
+/// Adds a breakpoint at `address`, or removes the one already there.
+/// Returns whether there is a breakpoint afterwards.
+#[tauri::command]
+fn toggle_breakpoint(
+    window: Window,
+    interpreter_state: State<InterpreterState>,
+    address: u16,
+) -> Result<bool, String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    debug(&window, &interpreter_state, |interpreter| {
+        let debugger = interpreter.debugger_mut();
+        Ok(debugger.add_breakpoint(address) || !debugger.remove_breakpoint(address))
+    })
+}
+
+/// Adds a breakpoint at `address`, or before every instruction, that only
+/// pauses when `condition` holds and from the `hit_count`th hit on. With a
+/// `log` message it logs the message instead of pausing. Returns its id.
+#[tauri::command]
+fn add_conditional_breakpoint(
+    interpreter_state: State<InterpreterState>,
+    address: Option<u16>,
+    condition: Option<String>,
+    hit_count: Option<u32>,
+    log: Option<String>,
+) -> Result<u32, String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    let mut breakpoint = match address {
+        Some(address) => Breakpoint::at(address),
+        None => Breakpoint::everywhere(),
+    };
+    if let Some(condition) = condition {
+        breakpoint = breakpoint
+            .with_condition(Expression::parse(&condition).map_err(|error| error.to_string())?);
+    }
+    if let Some(hit_count) = hit_count {
+        breakpoint = breakpoint.with_hit_count(hit_count);
+    }
+    if let Some(log) = log {
+        breakpoint = breakpoint.with_log(Template::parse(&log).map_err(|error| error.to_string())?);
+    }
+    if breakpoint.address.is_none() && breakpoint.condition.is_none() {
+        return Err("a breakpoint needs an address or a condition".to_string());
+    }
+    match interpreter_state.interpreter.lock().unwrap().as_mut() {
+        Some(interpreter) => Ok(interpreter.debugger_mut().add(breakpoint)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn remove_breakpoint(interpreter_state: State<InterpreterState>, id: u32) -> Result<bool, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_mut() {
+        Some(interpreter) => Ok(interpreter.debugger_mut().remove(id)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn list_breakpoints(
+    interpreter_state: State<InterpreterState>,
+) -> Result<Vec<BreakpointInfo>, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => Ok(interpreter
+            .debugger()
+            .breakpoints()
+            .iter()
+            .map(|breakpoint| BreakpointInfo {
+                location: breakpoint
+                    .address
+                    .map(|address| interpreter.symbols().describe(address)),
+                ..BreakpointInfo::from(breakpoint)
+            })
+            .collect()),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+fn watchpoint(start: u16, end: u16, kind: &str) -> Result<Watchpoint, String> {
+    let kind = WatchKind::from_name(kind).ok_or(format!("unknown watchpoint kind '{}'", kind))?;
+    if end < start {
+        return Err(format!("0x{:03X} comes before 0x{:03X}", end, start));
+    }
+    Ok(Watchpoint::new(start, end, kind))
+}
+
+/// Pauses whenever an instruction reads or writes, as `kind` says, the bytes
+/// from `start` to `end` inclusive.
+#[tauri::command]
+fn add_watchpoint(
+    interpreter_state: State<InterpreterState>,
+    start: u16,
+    end: u16,
+    kind: String,
+) -> Result<(), String> {
+    check_no_movie(&interpreter_state, MID_FRAME_PAUSE)?;
+    let watchpoint = watchpoint(start, end, &kind)?;
+    match interpreter_state.interpreter.lock().unwrap().as_mut() {
+        Some(interpreter) => {
+            interpreter.add_watchpoint(watchpoint);
+            Ok(())
+        }
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn remove_watchpoint(
+    interpreter_state: State<InterpreterState>,
+    start: u16,
+    end: u16,
+    kind: String,
+) -> Result<bool, String> {
+    let watchpoint = watchpoint(start, end, &kind)?;
+    match interpreter_state.interpreter.lock().unwrap().as_mut() {
+        Some(interpreter) => Ok(interpreter.remove_watchpoint(&watchpoint)),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn list_watchpoints(interpreter_state: State<InterpreterState>) -> Result<Vec<Watchpoint>, String> {
+    match interpreter_state.interpreter.lock().unwrap().as_ref() {
+        Some(interpreter) => Ok(interpreter.watchpoints().to_vec()),
+        None => Err("no ROM is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+fn get_quirks(interpreter_state: State<InterpreterState>) -> Quirks {
+    *interpreter_state.quirks.lock().unwrap()
+}
+
+#[tauri::command]
+fn set_quirks(interpreter_state: State<InterpreterState>, quirks: Quirks) -> Result<(), String> {
+    check_no_movie(&interpreter_state, "change the quirks")?;
+    *interpreter_state.quirks.lock().unwrap() = quirks;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_quirks_preset(interpreter_state: State<InterpreterState>, name: String) -> Result<(), String> {
+    let quirks = Quirks::preset(&name).ok_or(format!("unknown quirks preset '{}'", name))?;
+    check_no_movie(&interpreter_state, "change the quirks")?;
+    *interpreter_state.quirks.lock().unwrap() = quirks;
+    Ok(())
+}
+
+/// The platform chosen for the ROM at `path`, falling back to a guess from
+/// its file extension.
+fn platform_for(interpreter_state: &InterpreterState, path: &str) -> Platform {
+    interpreter_state
+        .platforms
+        .lock()
+        .unwrap()
+        .get(path)
+        .copied()
+        .unwrap_or_else(|| Platform::from_path(Path::new(path)))
+}
+
+#[tauri::command]
+fn get_platform(interpreter_state: State<InterpreterState>, path: String) -> Platform {
+    platform_for(&interpreter_state, &path)
+}
+
+/// Remembers the platform to run the ROM at `path` on. It takes effect the
+/// next time the ROM is loaded.
+#[tauri::command]
+fn set_platform(
+    interpreter_state: State<InterpreterState>,
+    path: String,
+    name: String,
+) -> Result<(), String> {
+    let platform = Platform::from_name(&name).ok_or(format!("unknown platform '{}'", name))?;
+    interpreter_state.platforms.lock().unwrap().insert(path, platform);
+    Ok(())
+}
+
+/// What to do with the input of the next ROM that is loaded.
+enum MovieAction {
+    Record,
+    Play(Movie),
+}
+
 #[derive(Default)]
 struct InterpreterState {
+    interpreter: Arc<Mutex<Option<Interpreter>>>,
     interpreter_thread: std::sync::Mutex<Option<JoinHandle<()>>>,
     is_running: Arc<AtomicBool>,
+    rewinding: Arc<AtomicBool>,
+    speed: Arc<Mutex<Speed>>,
+    quirks: Arc<Mutex<Quirks>>,
+    rng_mode: Mutex<RngMode>,
+    platforms: Mutex<HashMap<String, Platform>>,
+    rom_path: Mutex<Option<String>>,
+    movie_action: Mutex<Option<MovieAction>>,
+    recording: Mutex<Option<Arc<Mutex<Movie>>>>,
+    playing_movie: AtomicBool,
+    inspection: Arc<Mutex<Option<Inspection>>>,
 }
 
 impl Drop for InterpreterState {
@@ -303,20 +1427,120 @@ fn main() {
     let load_rom = CustomMenuItem::new("load_rom".to_string(), "Load Rom...");
     let stop = CustomMenuItem::new("stop".to_string(), "Stop");
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
+    let normal_speed = CustomMenuItem::new("normal_speed".to_string(), "Normal Speed");
+    let fast_forward = CustomMenuItem::new("fast_forward".to_string(), "Fast Forward");
+    let slow_motion = CustomMenuItem::new("slow_motion".to_string(), "Slow Motion");
+    let speed_menu = Submenu::new(
+        "Speed",
+        Menu::new()
+            .add_item(normal_speed)
+            .add_item(fast_forward)
+            .add_item(slow_motion),
+    );
+    let quirks_menu = Submenu::new(
+        "Quirks",
+        Menu::new()
+            .add_item(CustomMenuItem::new("quirks:cosmac-vip".to_string(), "COSMAC VIP"))
+            .add_item(CustomMenuItem::new("quirks:chip-48".to_string(), "CHIP-48"))
+            .add_item(CustomMenuItem::new("quirks:super-chip".to_string(), "SUPER-CHIP"))
+            .add_item(CustomMenuItem::new("quirks:octo".to_string(), "Octo")),
+    );
+    let platform_menu = Submenu::new(
+        "Platform",
+        Menu::new()
+            .add_item(CustomMenuItem::new("platform:chip-8".to_string(), "CHIP-8"))
+            .add_item(CustomMenuItem::new("platform:super-chip".to_string(), "SUPER-CHIP"))
+            .add_item(CustomMenuItem::new("platform:xo-chip".to_string(), "XO-CHIP")),
+    );
+    let rng_menu = Submenu::new(
+        "Random Numbers",
+        Menu::new()
+            .add_item(CustomMenuItem::new("rng:xorshift".to_string(), "Xorshift"))
+            .add_item(CustomMenuItem::new("rng:cosmac-vip".to_string(), "COSMAC VIP")),
+    );
+    let mut save_state_menu = Menu::new();
+    let mut load_state_menu = Menu::new();
+    for slot in 1..=SAVE_STATE_SLOTS {
+        save_state_menu = save_state_menu.add_item(CustomMenuItem::new(
+            format!("save_state:{}", slot),
+            format!("Slot {}", slot),
+        ));
+        load_state_menu = load_state_menu.add_item(CustomMenuItem::new(
+            format!("load_state:{}", slot),
+            format!("Slot {}", slot),
+        ));
+    }
+    let movie_menu = Submenu::new(
+        "Movie",
+        Menu::new()
+            .add_item(CustomMenuItem::new("record_movie".to_string(), "Record Movie"))
+            .add_item(CustomMenuItem::new("save_movie".to_string(), "Save Movie..."))
+            .add_item(CustomMenuItem::new("play_movie".to_string(), "Play Movie...")),
+    );
     let interpreter_menu = Submenu::new(
         "Interpreter",
         Menu::new()
             .add_item(load_rom)
             .add_native_item(MenuItem::Separator)
+            .add_submenu(Submenu::new("Save State", save_state_menu))
+            .add_submenu(Submenu::new("Load State", load_state_menu))
+            .add_submenu(movie_menu)
+            .add_native_item(MenuItem::Separator)
             .add_item(stop)
+            .add_submenu(speed_menu)
+            .add_submenu(quirks_menu)
+            .add_submenu(platform_menu)
+            .add_submenu(rng_menu)
             .add_native_item(MenuItem::Separator)
             .add_item(quit),
     );
-    let menu = Menu::new().add_submenu(interpreter_menu);
+    let debug_menu = Submenu::new(
+        "Debug",
+        Menu::new()
+            .add_item(CustomMenuItem::new("debug:pause".to_string(), "Pause"))
+            .add_item(CustomMenuItem::new("debug:resume".to_string(), "Continue"))
+            .add_item(CustomMenuItem::new("debug:step".to_string(), "Step"))
+            .add_item(CustomMenuItem::new("debug:step_over".to_string(), "Step Over"))
+            .add_item(CustomMenuItem::new("debug:step_out".to_string(), "Step Out"))
+            .add_item(CustomMenuItem::new("debug:step_back".to_string(), "Step Back"))
+            .add_item(CustomMenuItem::new(
+                "debug:reverse_continue".to_string(),
+                "Reverse Continue",
+            ))
+            .add_native_item(MenuItem::Separator)
+            .add_item(CustomMenuItem::new("debug:run_to".to_string(), "Run To..."))
+            .add_item(CustomMenuItem::new("debug:breakpoint".to_string(), "Toggle Breakpoint..."))
+            .add_item(CustomMenuItem::new(
+                "debug:condition".to_string(),
+                "Add Conditional Breakpoint...",
+            ))
+            .add_item(CustomMenuItem::new("debug:watchpoint".to_string(), "Add Watchpoint..."))
+            .add_item(CustomMenuItem::new("debug:clear_watchpoints".to_string(), "Clear Watchpoints"))
+            .add_native_item(MenuItem::Separator)
+            .add_item(CustomMenuItem::new("debug:backtrace".to_string(), "Backtrace"))
+            .add_item(CustomMenuItem::new(
+                "debug:load_symbols".to_string(),
+                "Load Symbols...",
+            )),
+    );
+    let menu = Menu::new()
+        .add_submenu(interpreter_menu)
+        .add_submenu(debug_menu);
     tauri::Builder::default()
         .manage(InterpreterState {
+            interpreter: Arc::new(Mutex::new(None)),
             interpreter_thread: Mutex::new(None),
             is_running: Arc::new(AtomicBool::new(false)),
+            rewinding: Arc::new(AtomicBool::new(false)),
+            speed: Arc::new(Mutex::new(Speed::default())),
+            quirks: Arc::new(Mutex::new(Quirks::default())),
+            rng_mode: Mutex::new(RngMode::default()),
+            platforms: Mutex::new(HashMap::new()),
+            rom_path: Mutex::new(None),
+            movie_action: Mutex::new(None),
+            recording: Mutex::new(None),
+            playing_movie: AtomicBool::new(false),
+            inspection: Arc::new(Mutex::new(None)),
         })
         .menu(menu)
         .on_menu_event(|event: WindowMenuEvent| match event.menu_item_id() {
@@ -329,6 +1553,98 @@ fn main() {
                 interpreter_state.is_running.store(false, Ordering::Relaxed);
                 event.window().emit("stop", ()).unwrap();
             }
+            "normal_speed" | "fast_forward" | "slow_motion" => {
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                interpreter_state.speed.lock().unwrap().multiplier = match event.menu_item_id() {
+                    "fast_forward" => FAST_FORWARD_MULTIPLIER,
+                    "slow_motion" => SLOW_MOTION_MULTIPLIER,
+                    _ => 1.0,
+                };
+            }
+            id if id.starts_with("quirks:") => {
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                if let Err(message) = check_no_movie(&interpreter_state, "change the quirks") {
+                    emit_movie_error(window, message);
+                } else if let Some(quirks) = Quirks::preset(&id["quirks:".len()..]) {
+                    *interpreter_state.quirks.lock().unwrap() = quirks;
+                }
+            }
+            id if id.starts_with("save_state:") || id.starts_with("load_state:") => {
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                let app_handle = window.app_handle();
+                let (action, slot) = id.split_once(':').unwrap();
+                let slot = slot.parse().unwrap();
+                let result = if action == "save_state" {
+                    save_state_to_slot(&app_handle, &interpreter_state, slot)
+                } else {
+                    load_state_from_slot(&app_handle, &interpreter_state, slot)
+                };
+                if let Err(message) = result {
+                    emit_save_state_error(window, message);
+                }
+            }
+            id if id.starts_with("rng:") => {
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                if let Some(mode) = RngMode::from_name(&id["rng:".len()..]) {
+                    *interpreter_state.rng_mode.lock().unwrap() = mode;
+                }
+            }
+            id if id.starts_with("platform:") => {
+                // remember the platform for the current ROM and restart it
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                let path = interpreter_state.rom_path.lock().unwrap().clone();
+                if let (Some(path), Some(platform)) =
+                    (path, Platform::from_name(&id["platform:".len()..]))
+                {
+                    interpreter_state
+                        .platforms
+                        .lock()
+                        .unwrap()
+                        .insert(path.clone(), platform);
+                    window.emit("rom-loaded", Rom { path }).unwrap();
+                }
+            }
+            id if id.starts_with("debug:") => {
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                let action = &id["debug:".len()..];
+                if let Err(message) = debug_menu_action(window, &interpreter_state, action) {
+                    emit_debug_error(window, message);
+                }
+            }
+            "record_movie" => {
+                let window = event.window();
+                let interpreter_state = window.state::<InterpreterState>();
+                if let Err(message) = start_recording(window, &interpreter_state) {
+                    emit_movie_error(window, message);
+                }
+            }
+            "save_movie" | "play_movie" => {
+                let window = event.window().clone();
+                let saving = event.menu_item_id() == "save_movie";
+                let dialog = FileDialogBuilder::new().add_filter("Movie", &[MOVIE_EXTENSION]);
+                let callback = move |path: Option<PathBuf>| {
+                    let interpreter_state = window.state::<InterpreterState>();
+                    let result = match path {
+                        Some(path) if saving => save_recording(&interpreter_state, &path),
+                        Some(path) => play_movie(&window, &interpreter_state, &path),
+                        None => Ok(()),
+                    };
+                    if let Err(message) = result {
+                        emit_movie_error(&window, message);
+                    }
+                };
+                if saving {
+                    dialog.save_file(callback);
+                } else {
+                    dialog.pick_file(callback);
+                }
+            }
             "load_rom" => {
                 let window = event.window();
                 let interpreter_state = window.state::<InterpreterState>();
@@ -352,7 +1668,44 @@ fn main() {
             }
             _ => {}
         })
-        .invoke_handler(tauri::generate_handler![initialize_interpreter,])
+        .invoke_handler(tauri::generate_handler![
+            initialize_interpreter,
+            get_speed,
+            set_instructions_per_frame,
+            set_speed_multiplier,
+            get_quirks,
+            set_quirks,
+            set_quirks_preset,
+            get_platform,
+            set_platform,
+            save_state,
+            load_state,
+            list_save_states,
+            set_rewinding,
+            set_rng_mode,
+            debug_pause,
+            debug_resume,
+            debug_step,
+            debug_step_over,
+            debug_step_out,
+            debug_step_back,
+            debug_reverse_continue,
+            debug_run_to,
+            toggle_breakpoint,
+            add_conditional_breakpoint,
+            remove_breakpoint,
+            list_breakpoints,
+            add_watchpoint,
+            remove_watchpoint,
+            list_watchpoints,
+            debug_backtrace,
+            symbol_address,
+            get_registers,
+            set_register,
+            read_memory,
+            write_memory,
+            inspect_machine,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }